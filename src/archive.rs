@@ -0,0 +1,136 @@
+//! Recognizing package archives by their content rather than their name.
+//! Appian exports are zip files, but repackaged artifacts may arrive as
+//! gzip-compressed tar archives.
+
+use std::io::Read;
+use std::path::Path;
+
+/// Local file header, and the end record that starts an empty zip
+const ZIP_MAGIC: [&[u8]; 2] = [b"PK\x03\x04", b"PK\x05\x06"];
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Container format of a package file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    /// A gzip-compressed tar archive
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Recognize an archive from its first bytes.
+    pub fn sniff(header: &[u8]) -> Option<Self> {
+        if ZIP_MAGIC.iter().any(|magic| header.starts_with(magic)) {
+            Some(ArchiveFormat::Zip)
+        } else if header.starts_with(GZIP_MAGIC) {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+
+    /// Recognize the archive at `path`, or `None` when it is neither format.
+    pub fn detect(path: &Path) -> std::io::Result<Option<Self>> {
+        let mut header = Vec::with_capacity(4);
+        std::fs::File::open(path)?
+            .take(4)
+            .read_to_end(&mut header)?;
+        Ok(Self::sniff(&header))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+
+    /// Content type of a multipart part holding this format
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+
+    /// File name endings used for this format
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ArchiveFormat::Zip => &[".zip"],
+            ArchiveFormat::TarGz => &[".tar.gz", ".tgz"],
+        }
+    }
+
+    /// Whether the file name of `path` ends in one of [`extensions`](Self::extensions).
+    pub fn matches_extension(self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        self.extensions().iter().any(|ext| name.ends_with(ext))
+    }
+}
+
+/// The UUID of the design object stored at `entry`: the file name without
+/// its last extension, which is how Appian names the files of a package
+/// (`content/_a-0001.xml`). UUIDs themselves may contain dots.
+pub fn object_uuid(entry: &str) -> &str {
+    let file = entry.rsplit('/').next().unwrap_or(entry);
+    file.rsplit_once('.').map_or(file, |(stem, _)| stem)
+}
+
+/// Read a gzip-compressed tar archive to the end and count its entries.
+/// Fails when either layer is corrupt or truncated.
+#[cfg(feature = "validate")]
+pub fn check_tar_gz(path: &Path) -> std::io::Result<usize> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut entries = 0;
+    for entry in archive.entries()? {
+        entry?;
+        entries += 1;
+    }
+    // The gzip checksum is only verified once the stream is read to its end
+    std::io::copy(&mut archive.into_inner(), &mut std::io::sink())?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_and_extensions() {
+        assert_eq!(
+            ArchiveFormat::sniff(b"PK\x03\x04rest"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(b"PK\x05\x06"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::sniff(&[0x1f, 0x8b, 0x08]),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::sniff(b"PK"), None);
+        assert_eq!(ArchiveFormat::sniff(b"hello"), None);
+
+        assert!(ArchiveFormat::TarGz.matches_extension(Path::new("build/app.TAR.GZ")));
+        assert!(ArchiveFormat::TarGz.matches_extension(Path::new("app.tgz")));
+        assert!(!ArchiveFormat::TarGz.matches_extension(Path::new("app.zip")));
+        assert!(ArchiveFormat::Zip.matches_extension(Path::new("app.zip")));
+    }
+
+    #[test]
+    fn test_object_uuid() {
+        assert_eq!(object_uuid("content/_a-0001.xml"), "_a-0001");
+        assert_eq!(object_uuid("content/_a-0002.v1.xml"), "_a-0002.v1");
+        assert_eq!(
+            object_uuid("processModel.old/0002e1b2-7f3c"),
+            "0002e1b2-7f3c"
+        );
+        assert_eq!(object_uuid("_a-0003.pdf"), "_a-0003");
+    }
+}
@@ -0,0 +1,83 @@
+//! SHA-256 checks for packages and artifacts that are streamed rather than
+//! held in memory.
+
+use crate::error::CliError;
+use crate::Result;
+use sha2::Digest;
+
+/// Normalize a SHA-256 given to `flag` to lowercase hex.
+pub fn parse_sha256(value: &str, flag: &str) -> Result<String> {
+    let value = value.trim().to_ascii_lowercase();
+    if value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CliError::InvalidArgument(format!(
+            "{} must be 64 hexadecimal characters",
+            flag
+        )));
+    }
+    Ok(value)
+}
+
+/// Passes writes through while hashing them.
+pub struct Sha256Writer<W> {
+    inner: W,
+    hasher: sha2::Sha256,
+}
+
+impl<W> Sha256Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Sha256Writer {
+            inner,
+            hasher: sha2::Sha256::new(),
+        }
+    }
+
+    /// The wrapped writer and the lowercase hex digest of everything
+    /// written through it.
+    pub fn finish(self) -> (W, String) {
+        let digest = self
+            .hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        (self.inner, digest)
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for Sha256Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_sha256_writer_and_parse() {
+        let mut writer = Sha256Writer::new(Vec::new());
+        writer.write_all(b"PACK").unwrap();
+        writer.write_all(b"AGE").unwrap();
+        let (bytes, digest) = writer.finish();
+        assert_eq!(bytes, b"PACKAGE");
+        assert_eq!(
+            digest,
+            "19b723bc37c15fb257166f057eb49c85ed9758acd1eae99186609e97482d9e70"
+        );
+
+        assert_eq!(parse_sha256(&digest.to_uppercase(), "--x").unwrap(), digest);
+        let err = parse_sha256("abc", "--expected-sha256").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: --expected-sha256 must be 64 hexadecimal characters"
+        );
+    }
+}
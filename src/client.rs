@@ -1,83 +1,735 @@
 use crate::config::Config;
-use crate::error::{CliError, Result};
+use crate::endpoints::EndpointPaths;
+use crate::error::{redact_sensitive_info, with_request_id, CliError, Result};
 use crate::models::*;
+use crate::rate_limit::RateLimiter;
+use crate::transport::{HttpTransport, ReplayTransport, ReqwestTransport};
 use anyhow::Context;
-use reqwest::{Client as HttpClient, Response, StatusCode};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client as HttpClient, RequestBuilder, Response, StatusCode};
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, error, info};
 
+/// Tracing target for `--trace-http` body logging
+pub const HTTP_TRACE_TARGET: &str = "appian_http";
+
+/// Response headers carrying the server-side request ID, in order of
+/// preference. Quoted in error messages for Appian support cases.
+pub const REQUEST_ID_HEADERS: [&str; 2] = ["x-appian-request-id", "x-request-id"];
+
+/// Bodies longer than this are truncated in trace output
+const TRACE_BODY_LIMIT: usize = 4096;
+
+/// Header carrying a key that stays the same across retries of one upload,
+/// so a server or gateway that honors it can drop duplicate submissions
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// A file attached to a multipart upload, summarized for previews
+#[derive(Debug, Clone, Serialize)]
+pub struct FilePartSummary {
+    pub key: String,
+    pub file_name: String,
+    pub size_bytes: usize,
+    /// Set for archives recognized by their content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Everything a request would send, with credentials masked. Shown by
+/// `--print-request` instead of calling the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestPreview {
+    pub method: String,
+    pub url: String,
+    pub headers: std::collections::BTreeMap<String, String>,
+    pub json: Option<serde_json::Value>,
+    pub files: Vec<FilePartSummary>,
+}
+
+impl std::fmt::Display for RequestPreview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {}", self.method, self.url)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        if let Some(json) = &self.json {
+            writeln!(f, "\n[json part]")?;
+            writeln!(
+                f,
+                "{}",
+                serde_json::to_string_pretty(json).map_err(|_| std::fmt::Error)?
+            )?;
+        }
+        if !self.files.is_empty() {
+            writeln!(f, "\n[file parts]")?;
+            for file in &self.files {
+                match &file.content_type {
+                    Some(content_type) => writeln!(
+                        f,
+                        "{}: {} ({} bytes, {})",
+                        file.key, file.file_name, file.size_bytes, content_type
+                    )?,
+                    None => writeln!(
+                        f,
+                        "{}: {} ({} bytes)",
+                        file.key, file.file_name, file.size_bytes
+                    )?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A fully assembled multipart request that has not been sent yet. The
+/// form is rebuilt from the file paths for every attempt, since a sent
+/// multipart body cannot be replayed.
+#[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+pub struct PreparedRequest {
+    /// Method, URL and headers; has no body, so it can be cloned
+    builder: RequestBuilder,
+    upload: MultipartUpload,
+}
+
+#[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+impl PreparedRequest {
+    /// A fresh request with the form read from disk again.
+    fn request(&self) -> Result<RequestBuilder> {
+        let builder = self
+            .builder
+            .try_clone()
+            .context("Failed to clone request")?;
+        Ok(builder.multipart(self.upload.form()?))
+    }
+
+    /// Describe the request without sending it. Auth headers are masked.
+    pub fn preview(self) -> Result<RequestPreview> {
+        let request = self.request()?.build().context("Failed to build request")?;
+
+        let headers = request
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let line = redact_sensitive_info(&format!(
+                    "{}: {}",
+                    name,
+                    value.to_str().unwrap_or("<binary>")
+                ));
+                match line.split_once(": ") {
+                    Some((name, value)) => (name.to_string(), value.to_string()),
+                    None => (name.to_string(), line),
+                }
+            })
+            .collect();
+
+        let json =
+            serde_json::from_str(&self.upload.json).context("Failed to re-read request JSON")?;
+
+        Ok(RequestPreview {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers,
+            json: Some(json),
+            files: self.upload.files,
+        })
+    }
+}
+
+/// A file part of a multipart upload
+#[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+struct FileAttachment {
+    key: String,
+    path: std::path::PathBuf,
+    file_name: String,
+    content_type: Option<&'static str>,
+    what: String,
+}
+
+/// Multipart form under construction, with the parts recorded for previews.
+#[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+struct MultipartUpload {
+    json: String,
+    attachments: Vec<FileAttachment>,
+    files: Vec<FilePartSummary>,
+}
+
+#[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+impl MultipartUpload {
+    /// Build the form, reading every attached file.
+    fn form(&self) -> Result<reqwest::multipart::Form> {
+        use reqwest::multipart::{Form, Part};
+
+        let mut form = Form::new();
+        if let Ok(part) = Part::text(self.json.clone()).mime_str("application/json") {
+            form = form.part("json", part);
+        }
+        for attachment in &self.attachments {
+            let bytes = std::fs::read(&attachment.path)
+                .with_context(|| format!("Failed to read {} for upload", attachment.what))?;
+            let mut part = Part::bytes(bytes).file_name(attachment.file_name.clone());
+            if let Some(content_type) = attachment.content_type {
+                part = part
+                    .mime_str(content_type)
+                    .context("Invalid file part content type")?;
+            }
+            form = form.part(attachment.key.clone(), part);
+        }
+        Ok(form)
+    }
+
+    fn finish(self, builder: RequestBuilder) -> PreparedRequest {
+        PreparedRequest {
+            builder: builder.header(IDEMPOTENCY_KEY_HEADER, uuid::Uuid::new_v4().to_string()),
+            upload: self,
+        }
+    }
+}
+
+/// Why a failed attempt may be retried; each has its own retry cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RetryCause {
+    /// A failed connection or a status listed in `retry_on`
+    Transient,
+    /// The HTTP timeout or the attempt timeout ran out
+    Timeout,
+}
 
 pub struct Client {
     http_client: HttpClient,
+    transport: Arc<dyn HttpTransport>,
     config: Config,
+    /// Point after which the command gives up, from `deadline_seconds`
+    deadline: Option<Instant>,
+    /// Configured `headers`, applied after (and able to replace) the defaults
+    custom_headers: HeaderMap,
+    /// Paces every attempt to `rate_limit_per_second`; `None` when unlimited
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl Client {
     pub fn new(config: Config) -> Result<Self> {
-        let http_client = HttpClient::builder()
+        // Advertise gzip/deflate so large log and results payloads come
+        // back compressed; reqwest decodes them transparently. Idle
+        // connections are pooled so polling and concurrent requests to the
+        // same host skip the TCP and TLS handshakes.
+        let http = &config.http;
+        let mut builder = HttpClient::builder()
             .timeout(Duration::from_secs(config.timeout_seconds))
-            .build()
-            .context("Failed to build HTTP client")?;
+            .gzip(true)
+            .deflate(true)
+            .user_agent(config.user_agent())
+            .pool_idle_timeout(Duration::from_secs(http.pool_idle_timeout_seconds))
+            .pool_max_idle_per_host(http.pool_max_idle_per_host)
+            .tcp_keepalive(
+                (http.tcp_keepalive_seconds > 0)
+                    .then(|| Duration::from_secs(http.tcp_keepalive_seconds)),
+            );
+        if http.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        let http_client = builder.build().context("Failed to build HTTP client")?;
+
+        let mut custom_headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                CliError::Configuration(format!("Invalid header name '{}': {}", name, e))
+            })?;
+            let header_value = HeaderValue::from_str(value).map_err(|e| {
+                CliError::Configuration(format!("Invalid value for header '{}': {}", name, e))
+            })?;
+            debug!(
+                "Custom header {}",
+                redact_sensitive_info(&format!("{}: {}", name, value))
+            );
+            custom_headers.insert(header_name, header_value);
+        }
+
+        let transport: Arc<dyn HttpTransport> = match &config.replay_file {
+            Some(path) => Arc::new(ReplayTransport::load(path)?),
+            None => Arc::new(ReqwestTransport::new(http_client.clone())),
+        };
 
         Ok(Client {
+            transport,
             http_client,
+            deadline: config
+                .deadline_seconds
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+            custom_headers,
+            rate_limiter: RateLimiter::new(config.rate_limit_per_second),
             config,
         })
     }
 
+    /// Create a client that sends requests through `transport` instead of the network.
+    pub fn with_transport(config: Config, transport: Arc<dyn HttpTransport>) -> Result<Self> {
+        let mut client = Client::new(config)?;
+        client.transport = transport;
+        Ok(client)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Send a request, retrying transient failures with exponential backoff
+    /// from the `[monitor]` settings. Every attempt and every backoff sleep is
+    /// bounded by the command deadline, and every attempt by
+    /// `attempt_timeout_seconds`.
+    async fn send(&self, request: RequestBuilder, what: &str) -> Result<Response> {
+        // Streamed bodies cannot be cloned; such requests are sent once
+        let template = request.try_clone();
+        let attempt_timeout = self.config.attempt_timeout_seconds.map(Duration::from_secs);
+        self.send_with(request, what, attempt_timeout, || {
+            Ok(template.as_ref().and_then(|r| r.try_clone()))
+        })
+        .await
+    }
+
+    /// Like [`send`](Self::send), but each retry sends the request returned
+    /// by `replay`, or gives up when it returns `None`. Attempts still
+    /// waiting for a response after `attempt_timeout` are cancelled and
+    /// retried.
+    async fn send_with<F>(
+        &self,
+        request: RequestBuilder,
+        what: &str,
+        attempt_timeout: Option<Duration>,
+        mut replay: F,
+    ) -> Result<Response>
+    where
+        F: FnMut() -> Result<Option<RequestBuilder>>,
+    {
+        let network_error = |e: reqwest::Error| CliError::Network(format!("{}: {}", what, e));
+        let monitor = &self.config.monitor;
+        let mut request = request;
+        let mut attempt = 0;
+        // Retries so far per cause, each capped separately
+        let (mut transient_retries, mut timeout_retries) = (0, 0);
+
+        loop {
+            let built = request.build().map_err(network_error)?;
+
+            let (outcome, cause) = match self.attempt(built, what, attempt_timeout).await? {
+                Some(Ok(response)) => {
+                    if let Some(id) = response_request_id(&response) {
+                        crate::output::record_request_id(&id);
+                    }
+                    let cause = monitor
+                        .retry_on
+                        .contains(&response.status().as_u16())
+                        .then_some(RetryCause::Transient);
+                    (Ok(response), cause)
+                }
+                Some(Err(e)) => {
+                    let cause = if e.is_timeout() {
+                        Some(RetryCause::Timeout)
+                    } else {
+                        e.is_connect().then_some(RetryCause::Transient)
+                    };
+                    (Err(network_error(e)), cause)
+                }
+                None => {
+                    let limit = attempt_timeout.unwrap_or_default().as_secs();
+                    let error = CliError::Timeout(format!(
+                        "{}: no response within the attempt timeout of {}s",
+                        what, limit
+                    ));
+                    (Err(error), Some(RetryCause::Timeout))
+                }
+            };
+
+            let (retries, limit) = match cause {
+                Some(RetryCause::Transient) => {
+                    (&mut transient_retries, monitor.transient_retries())
+                }
+                Some(RetryCause::Timeout) => (&mut timeout_retries, monitor.timeout_retries()),
+                None => return outcome,
+            };
+            let retry = if *retries < limit { replay()? } else { None };
+            match retry {
+                Some(next) => {
+                    *retries += 1;
+                    attempt += 1;
+                    let delay = self.backoff_delay(attempt);
+                    match &outcome {
+                        Ok(response) => info!(
+                            "{}: got {}, retrying in {:?} (attempt {})",
+                            what,
+                            response.status(),
+                            delay,
+                            attempt
+                        ),
+                        Err(e) => info!("{}, retrying in {:?} (attempt {})", e, delay, attempt),
+                    }
+                    self.pause(delay).await?;
+                    request = next;
+                }
+                None => return outcome,
+            }
+        }
+    }
+
+    /// Send one attempt once the rate limiter allows it. Returns `None` when
+    /// `attempt_timeout` ran out first, and fails once the command deadline
+    /// does.
+    async fn attempt(
+        &self,
+        request: reqwest::Request,
+        what: &str,
+        attempt_timeout: Option<Duration>,
+    ) -> Result<Option<reqwest::Result<Response>>> {
+        if let Some(limiter) = &self.rate_limiter {
+            match self.remaining(what)? {
+                Some(remaining) => tokio::time::timeout(remaining, limiter.acquire())
+                    .await
+                    .map_err(|_| self.deadline_error(what))?,
+                None => limiter.acquire().await,
+            }
+        }
+
+        let call = self.transport.execute(request);
+        match (self.remaining(what)?, attempt_timeout) {
+            (Some(remaining), Some(limit)) if limit < remaining => {
+                Ok(tokio::time::timeout(limit, call).await.ok())
+            }
+            (Some(remaining), _) => tokio::time::timeout(remaining, call)
+                .await
+                .map(Some)
+                .map_err(|_| self.deadline_error(what)),
+            (None, Some(limit)) => Ok(tokio::time::timeout(limit, call).await.ok()),
+            (None, None) => Ok(Some(call.await)),
+        }
+    }
+
+    /// Backoff before retry number `attempt` (1-based): doubles from
+    /// `backoff_initial_ms` up to `backoff_max_ms`, then with jitter enabled
+    /// a random 50-100% of that is used.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let monitor = &self.config.monitor;
+        let exponential = monitor
+            .backoff_initial_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(32))
+            .min(monitor.backoff_max_ms);
+
+        let millis = if monitor.jitter && exponential > 1 {
+            let half = exponential / 2;
+            let entropy = uuid::Uuid::new_v4().as_u128() as u64;
+            half + entropy % (exponential - half + 1)
+        } else {
+            exponential
+        };
+        Duration::from_millis(millis)
+    }
+
+    /// Time left before the command deadline, or `None` without a deadline.
+    /// Errors once the deadline has passed.
+    fn remaining(&self, what: &str) -> Result<Option<Duration>> {
+        match self.deadline {
+            None => Ok(None),
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    Err(self.deadline_error(what))
+                } else {
+                    Ok(Some(remaining))
+                }
+            }
+        }
+    }
+
+    fn deadline_error(&self, what: &str) -> CliError {
+        CliError::Timeout(format!(
+            "{}: command deadline of {} seconds exceeded",
+            what,
+            self.config.deadline_seconds.unwrap_or_default()
+        ))
+    }
+
+    /// Sleep between polls or retries. Fails with a timeout instead of
+    /// sleeping past the command deadline.
+    pub async fn pause(&self, duration: Duration) -> Result<()> {
+        match self.remaining("Waiting")? {
+            Some(remaining) if remaining <= duration => {
+                tokio::time::sleep(remaining).await;
+                Err(self.deadline_error("Waiting"))
+            }
+            _ => {
+                tokio::time::sleep(duration).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Paths of the API endpoints under the configured roots.
+    pub fn endpoints(&self) -> EndpointPaths<'_> {
+        EndpointPaths::from_config(&self.config)
+    }
+
     fn build_request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
         let url = self.config.get_api_url(path);
         debug!("Building {} request to {}", method, url);
-        
-        self.http_client
-            .request(method, &url)
+
+        self.authorize(self.http_client.request(method, &url))
+    }
+
+    /// Add the API key and configured headers to `request`
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("appian-api-key", &self.config.api_key)
             .header("Accept", "application/json")
+            .headers(self.custom_headers.clone())
+    }
+
+    fn trace_body(&self, label: &str, body: &str) {
+        if self.config.logging.trace_http {
+            debug!(target: HTTP_TRACE_TARGET, "{}: {}", label, truncate_body(&redact_sensitive_info(body)));
+        }
+    }
+
+    /// Write `body` to `--save-response`, replacing what an earlier response
+    /// of the same command wrote there.
+    fn save_response(&self, body: &str) -> Result<()> {
+        let Some(path) = &self.config.save_response else {
+            return Ok(());
+        };
+        std::fs::write(path, body).map_err(|e| {
+            CliError::FileSystem(format!(
+                "Failed to save response to {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        debug!("Saved response body to {}", path.display());
+        Ok(())
+    }
+
+    fn trace_file_part(&self, key: &str, file_name: &str, len: usize) {
+        if self.config.logging.trace_http {
+            debug!(target: HTTP_TRACE_TARGET, "Multipart part '{}': {} ({} bytes)", key, file_name, len);
+        }
+    }
+
+    /// Start a multipart upload whose `json` part is `body`.
+    #[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+    fn start_multipart<B: serde::Serialize>(
+        &self,
+        body: &B,
+        what: &str,
+    ) -> Result<MultipartUpload> {
+        let json_str = serde_json::to_string(body)
+            .with_context(|| format!("Failed to serialize {} request JSON", what))?;
+        self.trace_body("Request JSON part", &json_str);
+
+        Ok(MultipartUpload {
+            json: json_str,
+            attachments: Vec::new(),
+            files: Vec::new(),
+        })
+    }
+
+    #[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+    fn attach_file(
+        &self,
+        upload: &mut MultipartUpload,
+        key: &str,
+        path: &std::path::Path,
+        default_name: &str,
+        what: &str,
+    ) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(default_name)
+            .to_string();
+        let size_bytes = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read {} for upload", what))?
+            .len() as usize;
+        // Archives are labelled by their content, so a tar.gz is not sent as a zip
+        let content_type = crate::archive::ArchiveFormat::detect(path)
+            .ok()
+            .flatten()
+            .map(|f| f.mime_type());
+        self.trace_file_part(key, &file_name, size_bytes);
+
+        upload.files.push(FilePartSummary {
+            key: key.to_string(),
+            file_name: file_name.clone(),
+            size_bytes,
+            content_type: content_type.map(str::to_string),
+        });
+        upload.attachments.push(FileAttachment {
+            key: key.to_string(),
+            path: path.to_path_buf(),
+            file_name,
+            content_type,
+            what: what.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Enforce `max_upload_bytes` against the combined size of `files`
+    /// before anything is read into memory.
+    #[cfg(any(feature = "deploy", feature = "validate"))]
+    fn check_upload_size(&self, files: &[&std::path::Path]) -> Result<()> {
+        let Some(limit) = self.config.max_upload_bytes else {
+            return Ok(());
+        };
+
+        let mut total: u64 = 0;
+        for path in files {
+            let metadata = std::fs::metadata(path)
+                .with_context(|| format!("Failed to read metadata for {}", path.display()))?;
+            total += metadata.len();
+        }
+
+        if total > limit {
+            return Err(CliError::Validation(format!(
+                "Upload of {} file(s) totals {} bytes, which exceeds the configured limit of {} bytes (max_upload_bytes)",
+                files.len(),
+                total,
+                limit
+            )));
+        }
+        Ok(())
+    }
+
+    #[cfg(any(feature = "export", feature = "deploy", feature = "validate"))]
+    async fn send_prepared<T: DeserializeOwned>(
+        &self,
+        prepared: PreparedRequest,
+        what: &'static str,
+    ) -> Result<T> {
+        // Retries rebuild the form from the files, under the same idempotency
+        // key. Uploads can take longer than any single poll, so the attempt
+        // timeout does not apply to them.
+        let response = self
+            .send_with(prepared.request()?, what, None, || {
+                prepared.request().map(Some)
+            })
+            .await?;
+        self.handle_response(response).await
     }
 
     async fn handle_response<T: DeserializeOwned>(&self, response: Response) -> Result<T> {
         let status = response.status();
         let url = response.url().to_string();
-        
+        let request_id = response_request_id(&response);
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let body = self.handle_response_text(response).await?;
+
+        // Gateways and some endpoints answer 200 with HTML or plain text
+        if let Some(ref non_json) = content_type
+            .as_ref()
+            .filter(|ct| !ct.to_ascii_lowercase().contains("json"))
+        {
+            return Err(CliError::UnexpectedResponse {
+                status: status.as_u16(),
+                message: with_request_id(
+                    format!(
+                        "Expected a JSON response from {} but got HTTP {} with '{}': {}",
+                        url,
+                        status.as_u16(),
+                        non_json,
+                        body_snippet(&body)
+                    ),
+                    request_id.as_deref(),
+                ),
+                content_type,
+            });
+        }
+
+        serde_json::from_str::<T>(&body).map_err(|e| match &self.config.replay_file {
+            Some(path) => CliError::InvalidArgument(format!(
+                "Saved response in replay file {} does not match the expected {}: {} (body: {})",
+                path.display(),
+                // The type name without module paths, e.g. `Vec<Package>`
+                std::any::type_name::<T>()
+                    .split_inclusive(['<', '>', ',', ' '])
+                    .map(|part| part.rsplit("::").next().unwrap_or(part))
+                    .collect::<String>(),
+                e,
+                body_snippet(&body)
+            )),
+            None => CliError::UnexpectedResponse {
+                status: status.as_u16(),
+                message: with_request_id(
+                    format!(
+                        "Failed to parse response JSON from {} (HTTP {}): {} (body: {})",
+                        url,
+                        status.as_u16(),
+                        e,
+                        body_snippet(&body)
+                    ),
+                    request_id.as_deref(),
+                ),
+                content_type,
+            },
+        })
+    }
+
+    /// The body of a successful response as sent, without checking its
+    /// content type or parsing it. Error statuses map to errors exactly as
+    /// in [`Self::handle_response`].
+    async fn handle_response_text(&self, response: Response) -> Result<String> {
+        let status = response.status();
+        let url = response.url().to_string();
+        let request_id = response_request_id(&response);
+
         debug!("Response status: {} from {}", status, url);
 
         if status.is_success() {
-            let json_result = response.json::<T>().await;
-            json_result.map_err(|e| CliError::Api {
-                status: 500,
-                message: format!("Failed to parse response JSON: {}", e),
-            })
+            let body = response
+                .text()
+                .await
+                .context("Failed to read response body")?;
+            self.trace_body("Response body", &body);
+            self.save_response(&body)?;
+            Ok(body)
         } else {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            self.trace_body("Response body", &error_text);
             error!("API error {}: {}", status, error_text);
-            
+            let request_id = request_id.as_deref();
+
             match status {
-                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                    Err(CliError::Authentication(format!("Authentication failed: {}", error_text)))
-                }
-                StatusCode::NOT_FOUND => {
-                    Err(CliError::Api {
-                        status: status.as_u16(),
-                        message: format!("Resource not found: {}", error_text),
-                    })
-                }
-                StatusCode::REQUEST_TIMEOUT => {
-                    Err(CliError::Timeout(format!("Request timeout: {}", error_text)))
-                }
-                _ if status.is_server_error() => {
-                    Err(CliError::Api {
-                        status: status.as_u16(),
-                        message: format!("Server error: {}", error_text),
-                    })
-                }
-                _ => {
-                    Err(CliError::Api {
-                        status: status.as_u16(),
-                        message: error_text,
-                    })
-                }
+                // API keys cannot be refreshed, so a 401 is final and is
+                // never retried; a token-based auth mode would refresh once
+                // here before giving up
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(CliError::Authentication(
+                    with_request_id(format!("Authentication failed: {}", error_text), request_id),
+                )),
+                StatusCode::NOT_FOUND => Err(CliError::Api {
+                    status: status.as_u16(),
+                    message: with_request_id(
+                        format!("Resource not found: {}", error_text),
+                        request_id,
+                    ),
+                }),
+                StatusCode::REQUEST_TIMEOUT => Err(CliError::Timeout(format!(
+                    "Request timeout: {}",
+                    error_text
+                ))),
+                _ if status.is_server_error() => Err(CliError::Api {
+                    status: status.as_u16(),
+                    message: with_request_id(format!("Server error: {}", error_text), request_id),
+                }),
+                _ => Err(CliError::Api {
+                    status: status.as_u16(),
+                    message: with_request_id(error_text, request_id),
+                }),
             }
         }
     }
@@ -85,46 +737,58 @@ impl Client {
     #[cfg(feature = "get_packages")]
     pub async fn get_packages(&self, app_uuids: &[String]) -> Result<Vec<Package>> {
         info!("Fetching packages for applications: {:?}", app_uuids);
-        
-        let mut request = self.build_request(reqwest::Method::GET, "/deployment/v2/packages");
-        
+
+        let mut request = self.build_request(reqwest::Method::GET, &self.endpoints().packages());
+
         if !app_uuids.is_empty() {
             let uuids_param = app_uuids.join(",");
             request = request.query(&[("app_uuids", uuids_param)]);
         }
 
-        let response = request.send().await.context("Failed to send request")?;
-        let response: PackageListResponse = self.handle_response(response).await?;
-        Ok(response.packages)
+        let response = self.send(request, "Failed to send request").await?;
+        let packages: PackageList = self.handle_response(response).await?;
+        Ok(packages.into_packages())
     }
 
-    #[cfg(feature = "export")]
-    pub async fn export_multipart(&self, request: &ExportRequest) -> Result<ExportResponse> {
-        use reqwest::multipart::{Form, Part};
+    /// Estimate an export of `uuids` from package metadata, without starting
+    /// it. A package export needs the full package list to find its ids.
+    #[cfg(all(feature = "export", feature = "get_packages"))]
+    pub async fn estimate_export(
+        &self,
+        export_type: &str,
+        uuids: &[uuid::Uuid],
+    ) -> Result<ExportEstimate> {
+        info!("Estimating {} export of {:?}", export_type, uuids);
 
-        info!("Initiating export: exportType={}, uuids={:?}", request.export_type, request.uuids);
+        let app_uuids: Vec<String> = match export_type {
+            "package" => Vec::new(),
+            _ => uuids.iter().map(|u| u.to_string()).collect(),
+        };
+        let packages = self.get_packages(&app_uuids).await?;
+        Ok(ExportEstimate::from_packages(export_type, uuids, packages))
+    }
 
-        // Build JSON part
-        let json_str = serde_json::to_string(request)
-            .context("Failed to serialize export request JSON")?;
-        let json_part = Part::text(json_str)
-            .mime_str("application/json")
-            .ok();
+    #[cfg(feature = "export")]
+    pub async fn export_multipart(&self, request: &ExportRequest) -> Result<ExportResponse> {
+        let prepared = self.prepare_export(request)?;
+        self.send_prepared(prepared, "Failed to send export request")
+            .await
+    }
 
-        let mut form = Form::new();
-        if let Some(part) = json_part {
-            form = form.part("json", part);
-        }
+    /// Assemble the export request without sending it.
+    #[cfg(feature = "export")]
+    pub fn prepare_export(&self, request: &ExportRequest) -> Result<PreparedRequest> {
+        info!(
+            "Initiating export: exportType={}, uuids={:?}",
+            request.export_type, request.uuids
+        );
 
-        let response = self
-            .build_request(reqwest::Method::POST, "/suite/deployment-management/v2/deployments")
-            .header("Action-Type", "export")
-            .multipart(form)
-            .send()
-            .await
-            .context("Failed to send export request")?;
+        let upload = self.start_multipart(request, "export")?;
+        let builder = self
+            .build_request(reqwest::Method::POST, &self.endpoints().exports())
+            .header("Action-Type", "export");
 
-        self.handle_response(response).await
+        Ok(upload.finish(builder))
     }
 
     #[cfg(feature = "deploy")]
@@ -144,13 +808,11 @@ impl Client {
             "packageFileName": package_zip_name,
         });
 
-        let response = self
-            .build_request(reqwest::Method::POST, "/deployment/v2/deployments")
+        let request = self
+            .build_request(reqwest::Method::POST, &self.endpoints().imports())
             .header("Action-Type", "import")
-            .json(&request_body)
-            .send()
-            .await
-            .context("Failed to send deploy request")?;
+            .json(&request_body);
+        let response = self.send(request, "Failed to send deploy request").await?;
 
         self.handle_response(response).await
     }
@@ -159,160 +821,534 @@ impl Client {
     pub async fn deploy_package_multipart(
         &self,
         request: &DeploymentRequest,
-        package_file: &std::path::Path,
+        package_file: Option<&std::path::Path>,
         customization_file: Option<&std::path::Path>,
         admin_console_file: Option<&std::path::Path>,
         plugins_file: Option<&std::path::Path>,
         database_scripts: Option<&[std::path::PathBuf]>,
     ) -> Result<DeployResponse> {
-        use reqwest::multipart::{Form, Part};
+        let prepared = self.prepare_deploy(
+            request,
+            package_file,
+            customization_file,
+            admin_console_file,
+            plugins_file,
+            database_scripts,
+        )?;
+        self.send_prepared(prepared, "Failed to send multipart deploy request")
+            .await
+    }
 
+    /// Assemble the multipart deployment request without sending it. The
+    /// package part is left out of a plug-ins-only deployment.
+    #[cfg(feature = "deploy")]
+    pub fn prepare_deploy(
+        &self,
+        request: &DeploymentRequest,
+        package_file: Option<&std::path::Path>,
+        customization_file: Option<&std::path::Path>,
+        admin_console_file: Option<&std::path::Path>,
+        plugins_file: Option<&std::path::Path>,
+        database_scripts: Option<&[std::path::PathBuf]>,
+    ) -> Result<PreparedRequest> {
         info!("Deploying (multipart) package: {}", request.name);
 
-        // Build JSON part
-        let json_str = serde_json::to_string(request)
-            .context("Failed to serialize deployment request JSON")?;
-        let json_part = Part::text(json_str)
-            .mime_str("application/json")
-            .ok();
+        let mut attachments: Vec<&std::path::Path> = package_file.into_iter().collect();
+        attachments.extend(customization_file);
+        attachments.extend(admin_console_file);
+        attachments.extend(plugins_file);
+        attachments.extend(
+            database_scripts
+                .unwrap_or_default()
+                .iter()
+                .map(|p| p.as_path()),
+        );
+        self.check_upload_size(&attachments)?;
 
-        let mut form = Form::new();
-        if let Some(part) = json_part {
-            form = form.part("json", part);
+        let mut upload = self.start_multipart(request, "deployment")?;
+        if let Some(path) = package_file {
+            self.attach_file(
+                &mut upload,
+                "packageFileName",
+                path,
+                "package.zip",
+                "package file",
+            )?;
         }
 
-        // Attach files
-        let pkg_name = package_file
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("package.zip")
-            .to_string();
-        let pkg_bytes = std::fs::read(package_file)
-            .context("Failed to read package file for upload")?;
-        let pkg_part = Part::bytes(pkg_bytes).file_name(pkg_name);
-        form = form.part("packageFileName", pkg_part);
-
         if let Some(path) = customization_file {
-            let fname = path.file_name().and_then(|n| n.to_str()).unwrap_or("customization.properties").to_string();
-            let bytes = std::fs::read(path).context("Failed to read customization file for upload")?;
-            let part = Part::bytes(bytes).file_name(fname);
-            form = form.part("customizationFileName", part);
+            self.attach_file(
+                &mut upload,
+                "customizationFileName",
+                path,
+                "customization.properties",
+                "customization file",
+            )?;
         }
 
         if let Some(path) = admin_console_file {
-            let fname = path.file_name().and_then(|n| n.to_str()).unwrap_or("admin-console-settings.zip").to_string();
-            let bytes = std::fs::read(path).context("Failed to read Admin Console settings file for upload")?;
-            let part = Part::bytes(bytes).file_name(fname);
-            form = form.part("adminConsoleSettingsFileName", part);
+            self.attach_file(
+                &mut upload,
+                "adminConsoleSettingsFileName",
+                path,
+                "admin-console-settings.zip",
+                "Admin Console settings file",
+            )?;
         }
 
         if let Some(path) = plugins_file {
-            let fname = path.file_name().and_then(|n| n.to_str()).unwrap_or("plugins.zip").to_string();
-            let bytes = std::fs::read(path).context("Failed to read plugins file for upload")?;
-            let part = Part::bytes(bytes).file_name(fname);
-            form = form.part("pluginsFileName", part);
+            self.attach_file(
+                &mut upload,
+                "pluginsFileName",
+                path,
+                "plugins.zip",
+                "plugins file",
+            )?;
         }
 
         if let Some(scripts) = database_scripts {
             for (idx, script_path) in scripts.iter().enumerate() {
                 let key = format!("databaseScript{}", idx + 1);
-                let fname = script_path.file_name().and_then(|n| n.to_str()).unwrap_or("script.sql").to_string();
-                let bytes = std::fs::read(script_path).context("Failed to read database script file for upload")?;
-                let part = Part::bytes(bytes).file_name(fname);
-                form = form.part(key, part);
+                self.attach_file(
+                    &mut upload,
+                    &key,
+                    script_path,
+                    "script.sql",
+                    "database script file",
+                )?;
             }
         }
 
-        let response = self
-            .build_request(reqwest::Method::POST, "/deployment/v2/deployments")
-            .header("Action-Type", "import")
-            .multipart(form)
-            .send()
-            .await
-            .context("Failed to send multipart deploy request")?;
+        let builder = self
+            .build_request(reqwest::Method::POST, &self.endpoints().imports())
+            .header("Action-Type", "import");
 
-        self.handle_response(response).await
+        Ok(upload.finish(builder))
+    }
+
+    /// List the data sources a deployment can target. Older Appian versions
+    /// have no such endpoint; their 404 gets a message saying so.
+    #[cfg(feature = "deploy")]
+    pub async fn list_data_sources(&self) -> Result<Vec<DataSource>> {
+        debug!("Listing data sources");
+
+        let request = self.build_request(reqwest::Method::GET, &self.endpoints().data_sources());
+        let response = self.send(request, "Failed to list data sources").await?;
+
+        match self.handle_response::<DataSourceListResponse>(response).await {
+            Ok(response) => Ok(response.data_sources),
+            Err(CliError::Api { status: 404, message }) => Err(CliError::Api {
+                status: 404,
+                message: format!(
+                    "This Appian site does not provide the data sources endpoint; older Appian versions lack it. \
+                     Pass the data source UUID to --data-source instead ({})",
+                    message
+                ),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resolve a data source name to its UUID. Inputs that already parse as a
+    /// UUID are returned unchanged without querying the server.
+    #[cfg(feature = "deploy")]
+    pub async fn resolve_data_source(&self, name: &str) -> Result<String> {
+        if uuid::Uuid::parse_str(name).is_ok() {
+            return Ok(name.to_string());
+        }
+
+        info!("Resolving data source by name: {}", name);
+        let data_sources = self.list_data_sources().await?;
+        match_data_source(name, &data_sources)
+    }
+
+    /// Deployments on the site that have not finished, including ones
+    /// waiting for review. `None` when the site does not list deployments
+    /// (`404`, `405` or `501`); any other error status is returned as an
+    /// API error without being logged, since callers may carry on without
+    /// the listing.
+    #[cfg(feature = "deploy")]
+    pub async fn active_deployments(
+        &self,
+    ) -> Result<Option<Vec<crate::models::DeploymentSummary>>> {
+        debug!("Listing deployments in progress");
+
+        let path = self.endpoints().imports();
+        let request = self
+            .build_request(reqwest::Method::GET, &path)
+            .query(&[("status", "IN_PROGRESS")]);
+        let response = self.send(request, "Failed to list deployments").await?;
+        let status = response.status();
+        if matches!(status.as_u16(), 404 | 405 | 501) {
+            debug!("Deployment listing not supported ({})", status);
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(CliError::Api {
+                status: status.as_u16(),
+                message: body_snippet(&body),
+            });
+        }
+
+        // A server that ignores the filter lists finished deployments too
+        let deployments: crate::models::DeploymentList = self.handle_response(response).await?;
+        Ok(Some(
+            deployments
+                .into_deployments()
+                .into_iter()
+                .filter(|d| d.is_active())
+                .collect(),
+        ))
     }
 
     #[cfg(any(feature = "status", feature = "monitor"))]
-    pub async fn get_deployment_status(&self, deployment_uuid: &str) -> Result<DeploymentStatusResponse> {
+    pub async fn get_deployment_status(
+        &self,
+        deployment_uuid: &str,
+    ) -> Result<DeploymentStatusResponse> {
         debug!("Getting deployment status for: {}", deployment_uuid);
-        
-        let path = format!("/deployment/v2/deployments/{}", deployment_uuid);
+
+        let path = self.endpoints().deployments(deployment_uuid);
+        let request = self.build_request(reqwest::Method::GET, &path);
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
-            .await
-            .context("Failed to get deployment status")?;
+            .send(request, "Failed to get deployment status")
+            .await?;
 
         self.handle_response(response).await
     }
 
-    #[cfg(any(feature = "export", feature = "monitor"))]
-    pub async fn get_export_status(&self, export_uuid: &str) -> Result<ExportResponse> {
+    #[cfg(any(feature = "export", feature = "status", feature = "monitor"))]
+    pub async fn get_export_status(&self, export_uuid: &str) -> Result<ExportDeploymentResults> {
         debug!("Getting export status for: {}", export_uuid);
         
-        let path = format!("/suite/deployment-management/v2/deployments/{}", export_uuid);
-        let response = self
+        let path = self.endpoints().results(export_uuid);
+        let request = self.build_request(reqwest::Method::GET, &path);
+        let response = self.send(request, "Failed to get export status").await?;
+
+        self.handle_response(response).await
+    }
+
+    /// Long-poll an export's status: the `wait` query parameter asks the
+    /// server to hold the request until the status changes or `wait` has
+    /// passed. Each attempt may take `wait` longer than the configured
+    /// timeouts allow. A server without long polling ignores the parameter
+    /// and answers at once.
+    #[cfg(any(feature = "export", feature = "status", feature = "monitor"))]
+    pub async fn get_export_status_waiting(
+        &self,
+        export_uuid: &str,
+        wait: Duration,
+    ) -> Result<ExportDeploymentResults> {
+        debug!(
+            "Long-polling export status for: {} (wait {}s)",
+            export_uuid,
+            wait.as_secs()
+        );
+
+        let path = self.endpoints().results(export_uuid);
+        let request = self
             .build_request(reqwest::Method::GET, &path)
-            .send()
-            .await
-            .context("Failed to get export status")?;
+            .query(&[("wait", wait.as_secs().to_string())])
+            .timeout(wait + Duration::from_secs(self.config.timeout_seconds));
+        let template = request.try_clone();
+        let attempt_timeout = self
+            .config
+            .attempt_timeout_seconds
+            .map(|secs| wait + Duration::from_secs(secs));
+        let response = self
+            .send_with(
+                request,
+                "Failed to get export status",
+                attempt_timeout,
+                || Ok(template.as_ref().and_then(|r| r.try_clone())),
+            )
+            .await?;
 
         self.handle_response(response).await
     }
 
-    #[cfg(any(feature = "status", feature = "monitor", feature = "download", feature = "logs", feature = "deploy"))]
-    pub async fn get_deployment_results(&self, deployment_uuid: &str) -> Result<crate::models::DeploymentResults> {
+    /// Ask the server to stop a running export. Sites without the cancel
+    /// action answer 404, 405 or 501; those get a message saying so.
+    #[cfg(feature = "export")]
+    pub async fn cancel_export(&self, export_uuid: &str) -> Result<()> {
+        info!("Cancelling export: {}", export_uuid);
+
+        let request = self.build_request(
+            reqwest::Method::POST,
+            &self.endpoints().cancel_export(export_uuid),
+        );
+        let response = self.send(request, "Failed to cancel export").await?;
+
+        match self.handle_response_text(response).await {
+            Ok(_) => Ok(()),
+            Err(CliError::Api {
+                status: status @ (404 | 405 | 501),
+                message,
+            }) => Err(CliError::Api {
+                status,
+                message: format!(
+                    "This Appian site does not support cancelling exports ({})",
+                    message
+                ),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(any(
+        feature = "status",
+        feature = "monitor",
+        feature = "download",
+        feature = "logs",
+        feature = "deploy"
+    ))]
+    pub async fn get_deployment_results(
+        &self,
+        deployment_uuid: &str,
+    ) -> Result<crate::models::DeploymentResults> {
         debug!("Getting deployment results for: {}", deployment_uuid);
 
-        let path = format!("/suite/deployment-management/v2/deployments/{}", deployment_uuid);
+        let path = self.endpoints().results(deployment_uuid);
+        let request = self.build_request(reqwest::Method::GET, &path);
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
-            .await
-            .context("Failed to get deployment results")?;
+            .send(request, "Failed to get deployment results")
+            .await?;
 
         self.handle_response(response).await
     }
 
+    /// Fetch one page of deployment logs starting at entry `offset`.
+    /// Callers follow `has_more` to collect the remaining pages.
     #[cfg(feature = "logs")]
     pub async fn get_deployment_logs(
         &self,
         deployment_id: &str,
-        tail: Option<usize>,
+        offset: usize,
     ) -> Result<LogsResponse> {
-        debug!("Getting deployment logs for: {}", deployment_id);
-        
-        let path = format!("/deployment/v2/deployments/{}/log", deployment_id);
-        let mut request = self.build_request(reqwest::Method::GET, &path);
-        
-        if let Some(tail_param) = tail {
-            request = request.query(&[("tail", tail_param.to_string())]);
-        }
+        debug!(
+            "Getting deployment logs for: {} (offset {})",
+            deployment_id, offset
+        );
+
+        let path = self.endpoints().logs(deployment_id);
+        let request = self
+            .build_request(reqwest::Method::GET, &path)
+            .query(&[("offset", offset.to_string())]);
 
-        let response = request.send().await.context("Failed to get deployment logs")?;
+        let response = self.send(request, "Failed to get deployment logs").await?;
+        self.handle_response(response).await
+    }
+
+    /// Fetch the entries logged after `cursor`, a `nextCursor` from an
+    /// earlier page. A server without cursor support ignores the parameter
+    /// and answers without a `nextCursor`.
+    #[cfg(feature = "logs")]
+    pub async fn get_deployment_logs_since(
+        &self,
+        deployment_id: &str,
+        cursor: &str,
+    ) -> Result<LogsResponse> {
+        debug!(
+            "Getting deployment logs for: {} (cursor {})",
+            deployment_id, cursor
+        );
+
+        let path = self.endpoints().logs(deployment_id);
+        let request = self
+            .build_request(reqwest::Method::GET, &path)
+            .query(&[("cursor", cursor)]);
+
+        let response = self.send(request, "Failed to get deployment logs").await?;
         self.handle_response(response).await
     }
 
     #[cfg(feature = "download")]
     pub async fn download_artifact(&self, artifact_id: &str) -> Result<Vec<u8>> {
-        info!("Downloading artifact: {}", artifact_id);
-        
-        let path = format!("/deployment/v2/artifacts/{}", artifact_id);
+        let mut bytes = Vec::new();
+        self.download_artifact_to(artifact_id, &mut bytes).await?;
+        info!("Artifact downloaded successfully: {} bytes", bytes.len());
+        Ok(bytes)
+    }
+
+    /// Stream an artifact into `out` chunk by chunk and return its size,
+    /// without holding it in memory.
+    #[cfg(feature = "download")]
+    pub async fn download_artifact_to(
+        &self,
+        artifact_id: &str,
+        out: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        info!("Streaming artifact: {}", artifact_id);
+
+        let path = self.endpoints().artifacts(artifact_id);
         let request = self.build_request(reqwest::Method::GET, &path);
-        let response = request.send().await.context("Failed to download artifact")?;
-        
+        let response = self
+            .send(clone_get(&request), "Failed to download artifact")
+            .await?;
+
         if !response.status().is_success() {
             return Err(CliError::Api {
                 status: response.status().as_u16(),
                 message: format!("Failed to download artifact: {}", response.status()),
             });
         }
-        
-        let bytes = response.bytes().await.context("Failed to read response bytes")?;
-        info!("Artifact downloaded successfully: {} bytes", bytes.len());
-        Ok(bytes.to_vec())
+
+        self.copy_download(response, &request, "Failed to download artifact", out)
+            .await
+    }
+
+    /// Stream the file at `link`, such as a package zip linked from export
+    /// results, into `out` chunk by chunk and return its size. Relative
+    /// links are resolved against the base URL. Credentials are only sent
+    /// to the configured Appian site, so a link to another host must not
+    /// need them.
+    #[cfg(feature = "deploy")]
+    pub async fn download_url_to(&self, link: &str, out: &mut impl std::io::Write) -> Result<u64> {
+        info!("Downloading: {}", link);
+
+        let request = self.link_request(link)?;
+        let response = self
+            .send(clone_get(&request), "Failed to download file")
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(CliError::Api {
+                status: response.status().as_u16(),
+                message: format!("Failed to download {}: {}", link, response.status()),
+            });
+        }
+
+        self.copy_download(response, &request, "Failed to download file", out)
+            .await
+    }
+
+    /// Copy a successful download into `out` and return its size. When the
+    /// connection drops before the advertised `Content-Length` has arrived,
+    /// the rest is requested again with a `Range` header, up to the
+    /// transient retry budget. A server that ignores the range resends the
+    /// whole file, and the part already written is skipped.
+    #[cfg(any(feature = "download", feature = "deploy"))]
+    async fn copy_download(
+        &self,
+        mut response: Response,
+        request: &RequestBuilder,
+        what: &str,
+        out: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        let expected = response.content_length();
+        let mut size = 0u64;
+        let mut resumes = 0;
+
+        loop {
+            let mut skip = if response.status() == StatusCode::PARTIAL_CONTENT {
+                0
+            } else {
+                size
+            };
+            let interrupted = loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        let skipped = skip.min(chunk.len() as u64);
+                        skip -= skipped;
+                        out.write_all(&chunk[skipped as usize..])?;
+                        size += chunk.len() as u64 - skipped;
+                    }
+                    Ok(None) => break None,
+                    Err(e) => break Some(e),
+                }
+            };
+
+            let expected = match (expected, interrupted) {
+                (Some(expected), _) if size < expected => expected,
+                (None, Some(e)) => return Err(CliError::Network(format!("{}: {}", what, e))),
+                _ => return Ok(size),
+            };
+            if resumes >= self.config.monitor.transient_retries() {
+                return Err(CliError::Network(format!(
+                    "{}: connection closed after {} of {} bytes, {} resume attempt(s) did not complete the download",
+                    what, size, expected, resumes
+                )));
+            }
+            resumes += 1;
+            let delay = self.backoff_delay(resumes);
+            info!(
+                "{}: got {} of {} bytes, resuming in {:?} (attempt {})",
+                what, size, expected, delay, resumes
+            );
+            self.pause(delay).await?;
+
+            let resume =
+                clone_get(request).header(reqwest::header::RANGE, format!("bytes={}-", size));
+            response = self.send(resume, what).await?;
+            if !response.status().is_success() {
+                return Err(CliError::Api {
+                    status: response.status().as_u16(),
+                    message: format!(
+                        "{}: resuming at byte {} got {}",
+                        what,
+                        size,
+                        response.status()
+                    ),
+                });
+            }
+            let content_range = response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok());
+            if response.status() == StatusCode::PARTIAL_CONTENT
+                && !content_range
+                    .is_some_and(|range| range.starts_with(&format!("bytes {}-", size)))
+            {
+                return Err(CliError::Network(format!(
+                    "{}: asked to resume at byte {} but got range {}",
+                    what,
+                    size,
+                    content_range.unwrap_or("(none)")
+                )));
+            }
+        }
+    }
+
+    /// GET request for a link that may be relative to the base URL. Only
+    /// links to the configured Appian site carry credentials.
+    #[cfg(any(feature = "download", feature = "deploy"))]
+    fn link_request(&self, link: &str) -> Result<RequestBuilder> {
+        let base = url::Url::parse(&self.config.base_url).map_err(|e| {
+            CliError::Configuration(format!(
+                "Invalid base URL '{}': {}",
+                self.config.base_url, e
+            ))
+        })?;
+        let target = base.join(link).map_err(|e| {
+            CliError::InvalidArgument(format!("Invalid artifact URL '{}': {}", link, e))
+        })?;
+
+        Ok(if target.origin() == base.origin() {
+            self.authorize(self.http_client.get(target))
+        } else {
+            self.http_client.get(target)
+        })
+    }
+
+    /// POST `payload` to the configured `notify.webhook`. The webhook is a
+    /// third party, so neither the API key nor custom headers are sent.
+    #[cfg(feature = "monitor")]
+    pub async fn post_webhook(&self, payload: &serde_json::Value) -> Result<()> {
+        let Some(webhook) = self.config.notify.webhook.as_deref() else {
+            return Ok(());
+        };
+        debug!("Notifying webhook: {}", webhook);
+
+        let request = self.http_client.post(webhook).json(payload);
+        let response = self.send(request, "Failed to notify webhook").await?;
+        if !response.status().is_success() {
+            return Err(CliError::Api {
+                status: response.status().as_u16(),
+                message: format!("Webhook {} answered {}", webhook, response.status()),
+            });
+        }
+        Ok(())
     }
 
     #[cfg(feature = "validate")]
@@ -341,98 +1377,739 @@ impl Client {
         customization_file: Option<&std::path::Path>,
         admin_console_file: Option<&std::path::Path>,
     ) -> Result<InspectionResponse> {
-        use reqwest::multipart::{Form, Part};
-
-        info!("Initiating inspection for package: {}", request.package_file_name);
+        let prepared = self.prepare_inspection(
+            request,
+            package_file,
+            customization_file,
+            admin_console_file,
+        )?;
+        self.send_prepared(prepared, "Failed to send inspection request")
+            .await
+    }
 
-        // Build JSON part
-        let json_str = serde_json::to_string(request)
-            .context("Failed to serialize inspection request JSON")?;
-        let json_part = Part::text(json_str)
-            .mime_str("application/json")
-            .ok();
+    /// Assemble the inspection request without sending it.
+    #[cfg(feature = "validate")]
+    pub fn prepare_inspection(
+        &self,
+        request: &InspectionRequest,
+        package_file: &std::path::Path,
+        customization_file: Option<&std::path::Path>,
+        admin_console_file: Option<&std::path::Path>,
+    ) -> Result<PreparedRequest> {
+        info!(
+            "Initiating inspection for package: {}",
+            request.package_file_name
+        );
 
-        let mut form = Form::new();
-        if let Some(part) = json_part {
-            form = form.part("json", part);
-        }
+        let mut attachments = vec![package_file];
+        attachments.extend(customization_file);
+        attachments.extend(admin_console_file);
+        self.check_upload_size(&attachments)?;
 
         // Attach files with arbitrary keys as allowed by API
-        let pkg_name = package_file
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("package.zip")
-            .to_string();
-        let pkg_bytes = std::fs::read(package_file)
-            .context("Failed to read package file for upload")?;
-        let pkg_part = Part::bytes(pkg_bytes).file_name(pkg_name);
-        form = form.part("zipFile", pkg_part);
+        let mut upload = self.start_multipart(request, "inspection")?;
+        self.attach_file(
+            &mut upload,
+            "zipFile",
+            package_file,
+            "package.zip",
+            "package file",
+        )?;
 
         if let Some(path) = customization_file {
-            let fname = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("customization.properties")
-                .to_string();
-            let bytes = std::fs::read(path)
-                .context("Failed to read customization file for upload")?;
-            let part = Part::bytes(bytes).file_name(fname);
-            form = form.part("ICF", part);
+            self.attach_file(
+                &mut upload,
+                "ICF",
+                path,
+                "customization.properties",
+                "customization file",
+            )?;
         }
 
         if let Some(path) = admin_console_file {
-            let fname = path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("admin-console-settings.zip")
-                .to_string();
-            let bytes = std::fs::read(path)
-                .context("Failed to read Admin Console settings file for upload")?;
-            let part = Part::bytes(bytes).file_name(fname);
-            form = form.part("adminConsole", part);
+            self.attach_file(
+                &mut upload,
+                "adminConsole",
+                path,
+                "admin-console-settings.zip",
+                "Admin Console settings file",
+            )?;
         }
 
-        let response = self
-            .build_request(reqwest::Method::POST, "/suite/deployment-management/v2/inspections")
-            .multipart(form)
-            .send()
-            .await
-            .context("Failed to send inspection request")?;
+        let builder = self.build_request(reqwest::Method::POST, &self.endpoints().new_inspection());
 
-        self.handle_response(response).await
+        Ok(upload.finish(builder))
+    }
+
+    /// GET `path` and return the body unparsed, for `--raw`.
+    pub async fn get_raw(&self, path: &str) -> Result<String> {
+        debug!("Getting raw response from: {}", path);
+
+        let request = self.build_request(reqwest::Method::GET, path);
+        let response = self.send(request, "Failed to send request").await?;
+
+        self.handle_response_text(response).await
     }
 
     #[cfg(feature = "validate")]
     pub async fn get_inspection_results(&self, inspection_uuid: &str) -> Result<InspectionResults> {
         debug!("Getting inspection results for: {}", inspection_uuid);
 
-        let path = format!("/suite/deployment-management/v2/inspections/{}", inspection_uuid);
+        let path = self.endpoints().inspections(inspection_uuid);
+        let request = self.build_request(reqwest::Method::GET, &path);
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
-            .await
-            .context("Failed to get inspection results")?;
+            .send(request, "Failed to get inspection results")
+            .await?;
 
         self.handle_response(response).await
     }
 }
 
+/// The first request id header the server sent, if any
+fn response_request_id(response: &Response) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| response.headers().get(*name))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// Whitespace-collapsed prefix of a response body for error messages
+fn body_snippet(body: &str) -> String {
+    const SNIPPET_LIMIT: usize = 200;
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(SNIPPET_LIMIT) {
+        Some((cut, _)) => format!("{}...", &collapsed[..cut]),
+        None if collapsed.is_empty() => "<empty body>".to_string(),
+        None => collapsed,
+    }
+}
+
+/// Copy of a bodyless download request, for sending it again
+#[cfg(any(feature = "download", feature = "deploy"))]
+fn clone_get(request: &RequestBuilder) -> RequestBuilder {
+    request
+        .try_clone()
+        .expect("download requests have no body to stream")
+}
+
+fn truncate_body(body: &str) -> String {
+    if body.len() <= TRACE_BODY_LIMIT {
+        return body.to_string();
+    }
+
+    let mut end = TRACE_BODY_LIMIT;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}... ({} more bytes truncated)",
+        &body[..end],
+        body.len() - end
+    )
+}
+
+#[cfg(feature = "deploy")]
+fn match_data_source(name: &str, data_sources: &[DataSource]) -> Result<String> {
+    let matches: Vec<&DataSource> = data_sources
+        .iter()
+        .filter(|ds| ds.name.eq_ignore_ascii_case(name))
+        .collect();
+
+    let available = || {
+        data_sources
+            .iter()
+            .map(|ds| ds.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match matches.as_slice() {
+        [single] => Ok(single.uuid.clone()),
+        [] => Err(CliError::InvalidArgument(format!(
+            "Data source '{}' not found. Available data sources: {}",
+            name,
+            available()
+        ))),
+        _ => Err(CliError::InvalidArgument(format!(
+            "Data source '{}' is ambiguous ({} matches). Available data sources: {}",
+            name,
+            matches.len(),
+            available()
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transport::{MockResponse, MockTransport};
 
-    #[test]
-    fn test_client_creation() {
-        let config = Config {
+    fn test_config() -> Config {
+        Config {
             base_url: "https://test.example.com".to_string(),
             api_key: "test-key".to_string(),
             timeout_seconds: 30,
-            logging: crate::config::LoggingConfig::default(),
-            download: crate::config::DownloadConfig::default(),
-            monitor: crate::config::MonitorConfig::default(),
-        };
+            monitor: crate::config::MonitorConfig {
+                backoff_initial_ms: 1,
+                backoff_max_ms: 1,
+                ..Default::default()
+            },
+            ..Config::default()
+        }
+    }
 
-        let client = Client::new(config).unwrap();
+    async fn respond(canned: MockResponse) -> Result<serde_json::Value> {
+        let transport = Arc::new(MockTransport::new(vec![canned]));
+        let client = Client::with_transport(test_config(), transport).unwrap();
+        let response = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/test"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+        client.handle_response(response).await
+    }
+
+    #[test]
+    fn test_client_creation() {
+        let client = Client::new(test_config()).unwrap();
         assert_eq!(client.config.base_url, "https://test.example.com");
     }
-}
\ No newline at end of file
+
+    #[cfg(any(feature = "status", feature = "monitor"))]
+    #[tokio::test]
+    async fn test_replay_mismatch_names_the_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = dir.path().join("status.json");
+        std::fs::write(&replay, r#"{"status": "COMPLETED"}"#).unwrap();
+        let client = Client::new(Config {
+            replay_file: Some(replay),
+            ..test_config()
+        })
+        .unwrap();
+
+        let err = client.get_deployment_status("d1").await.unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err
+            .to_string()
+            .contains("expected DeploymentStatusResponse"));
+    }
+
+    #[cfg(any(feature = "status", feature = "monitor"))]
+    #[tokio::test]
+    async fn test_saved_response_survives_parse_failure_and_replays() {
+        let dir = tempfile::tempdir().unwrap();
+        let saved = dir.path().join("saved.json");
+        let body = r#"{"status": "COMPLETED"}"#;
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(200, body)]));
+        let config = Config {
+            save_response: Some(saved.clone()),
+            ..test_config()
+        };
+        let client = Client::with_transport(config, transport).unwrap();
+
+        assert!(client.get_deployment_status("d1").await.is_err());
+        assert_eq!(std::fs::read_to_string(&saved).unwrap(), body);
+
+        let replayed = Client::new(Config {
+            replay_file: Some(saved),
+            ..test_config()
+        })
+        .unwrap();
+        let err = replayed.get_deployment_status("d1").await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not match the expected DeploymentStatusResponse"));
+    }
+
+    #[tokio::test]
+    async fn test_handle_response_success() {
+        let value = respond(MockResponse::json(200, r#"{"ok": true}"#))
+            .await
+            .unwrap();
+        assert_eq!(value["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_handle_response_rejects_non_json_success() {
+        let html = MockResponse {
+            status: 200,
+            body: "<html>\n  <body>Sign in to continue</body>\n</html>".to_string(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "text/html; charset=utf-8".to_string(),
+            )],
+        };
+        let err = respond(html.with_header("X-Request-Id", "req-html"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "unexpected_response");
+        assert_eq!(err.exit_code(), 5);
+        assert_eq!(err.request_id(), Some("req-html"));
+        let json = err.to_json();
+        assert_eq!(json["error"]["status"], 200);
+        assert_eq!(json["error"]["content_type"], "text/html; charset=utf-8");
+        match err {
+            CliError::UnexpectedResponse {
+                status, message, ..
+            } => {
+                assert_eq!(status, 200);
+                assert!(message.contains("text/html"));
+                assert!(message.contains("<html> <body>Sign in to continue"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_response_reports_unparseable_json() {
+        let response =
+            MockResponse::json(200, r#"{"ok": tru"#).with_header("X-Request-Id", "req-bad");
+        let err = respond(response).await.unwrap_err();
+        assert_eq!(err.kind(), "unexpected_response");
+        assert_eq!(err.exit_code(), 5);
+        assert_eq!(err.request_id(), Some("req-bad"));
+        assert!(matches!(
+            err,
+            CliError::UnexpectedResponse { status: 200, .. }
+        ));
+        assert!(
+            err.to_string().contains("Failed to parse response JSON"),
+            "{}",
+            err
+        );
+        assert_eq!(err.to_json()["error"]["status"], 200);
+    }
+
+    #[tokio::test]
+    async fn test_handle_response_error_mapping() {
+        let err = respond(MockResponse::json(401, "bad key"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::Authentication(_)));
+
+        let err = respond(MockResponse::json(404, "missing"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::Api { status: 404, ref message } if message.contains("not found"))
+        );
+
+        let err = respond(MockResponse::json(429, "slow down"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::Api { status: 429, .. }));
+
+        let err = respond(MockResponse::json(503, "unavailable"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::Api { status: 503, ref message } if message.contains("Server error"))
+        );
+        assert_eq!(err.exit_code(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_propagated() {
+        let response = MockResponse::json(500, "boom").with_header("X-Appian-Request-Id", "req-42");
+        let err = respond(response).await.unwrap_err();
+        assert_eq!(err.request_id(), Some("req-42"));
+        assert!(err.to_string().ends_with("[request ID: req-42]"));
+        assert_eq!(err.to_json()["error"]["request_id"], "req-42");
+
+        let response = MockResponse::json(403, "denied").with_header("X-Request-Id", "req-7");
+        let err = respond(response).await.unwrap_err();
+        assert!(matches!(err, CliError::Authentication(_)));
+        assert_eq!(err.request_id(), Some("req-7"));
+
+        // Successful responses are recorded too, for the audit log and --include-request-ids
+        respond(MockResponse::json(200, "{}").with_header("X-Request-Id", "req-ok"))
+            .await
+            .unwrap();
+        let ids = crate::output::request_ids();
+        assert!(
+            ["req-42", "req-7", "req-ok"]
+                .iter()
+                .all(|id| ids.iter().any(|seen| seen == id)),
+            "{:?}",
+            ids
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_transport() {
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(200, "{}")]));
+        let client = Client::with_transport(test_config(), transport.clone()).unwrap();
+        let _: serde_json::Value = client
+            .handle_response(
+                client
+                    .send(
+                        client.build_request(reqwest::Method::GET, "/x"),
+                        "Test request",
+                    )
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, "https://test.example.com/x");
+        assert_eq!(requests[0].headers["appian-api-key"], "test-key");
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_is_not_retried_with_api_key() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockResponse::json(401, "expired"),
+            MockResponse::json(200, "{}"),
+        ]));
+        let client = Client::with_transport(test_config(), transport.clone()).unwrap();
+        let response = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+        let err = client
+            .handle_response::<serde_json::Value>(response)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, CliError::Authentication(_)));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[cfg(feature = "monitor")]
+    #[tokio::test]
+    async fn test_webhook_gets_no_credentials() {
+        let mut config = test_config();
+        config
+            .headers
+            .insert("X-Tenant".to_string(), "acme".to_string());
+        config.notify.webhook = Some("https://hooks.example.com/appian".to_string());
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(204, "")]));
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        client
+            .post_webhook(&serde_json::json!({"uuid": "d", "success": true}))
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].method, reqwest::Method::POST);
+        assert_eq!(requests[0].url, "https://hooks.example.com/appian");
+        assert!(!requests[0].headers.contains_key("appian-api-key"));
+        assert!(!requests[0].headers.contains_key("x-tenant"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_headers_are_sent() {
+        let mut config = test_config();
+        config
+            .headers
+            .insert("X-Tenant".to_string(), "acme".to_string());
+        config.headers.insert(
+            "Accept".to_string(),
+            "application/vnd.appian+json".to_string(),
+        );
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(200, "{}")]));
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+
+        let headers = &transport.requests()[0].headers;
+        assert_eq!(headers["x-tenant"], "acme");
+        assert_eq!(headers.get_all("accept").iter().count(), 1);
+        assert_eq!(headers["accept"], "application/vnd.appian+json");
+        assert_eq!(headers["appian-api-key"], "test-key");
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried_within_deadline() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockResponse::json(503, "unavailable"),
+            MockResponse::json(429, "slow down"),
+            MockResponse::json(200, r#"{"ok": true}"#),
+        ]));
+        let client = Client::with_transport(test_config(), transport.clone()).unwrap();
+        let response = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(transport.requests().len(), 3);
+
+        let config = Config {
+            deadline_seconds: Some(0),
+            ..test_config()
+        };
+        let client = Client::with_transport(config, transport).unwrap();
+        let err = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CliError::Timeout(_)));
+
+        let err = client.pause(Duration::from_secs(60)).await.unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+
+        // Statuses outside retry_on fail on the first attempt
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(
+            503,
+            "unavailable",
+        )]));
+        let mut config = test_config();
+        config.monitor.retry_on = vec![502];
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        let response = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_share_the_rate_limit() {
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(200, "{}")]));
+        let config = Config {
+            rate_limit_per_second: 2,
+            ..test_config()
+        };
+        let client = Arc::new(Client::with_transport(config, transport.clone()).unwrap());
+
+        let started = Instant::now();
+        let mut tasks = tokio::task::JoinSet::new();
+        for _ in 0..6 {
+            let client = client.clone();
+            tasks.spawn(async move {
+                client
+                    .send(
+                        client.build_request(reqwest::Method::GET, "/x"),
+                        "Test request",
+                    )
+                    .await
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            joined.unwrap().unwrap();
+        }
+        // Two pass at once, the other four follow at two a second
+        assert!(started.elapsed() >= Duration::from_secs(2));
+        assert_eq!(transport.requests().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_transient_retries_have_their_own_cap() {
+        let transport = Arc::new(MockTransport::new(vec![
+            MockResponse::json(503, "unavailable"),
+            MockResponse::json(503, "unavailable"),
+            MockResponse::json(200, r#"{"ok": true}"#),
+        ]));
+        let mut config = test_config();
+        config.monitor.max_retries_transient = Some(1);
+        config.monitor.max_retries_timeout = Some(5);
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        let response = client
+            .send(
+                client.build_request(reqwest::Method::GET, "/x"),
+                "Test request",
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(transport.requests().len(), 2);
+
+        let monitor = crate::config::MonitorConfig {
+            max_retries: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            (monitor.transient_retries(), monitor.timeout_retries()),
+            (4, 4)
+        );
+    }
+
+    #[cfg(feature = "export")]
+    #[test]
+    fn test_preview_masks_auth_headers() {
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(200, "{}")]));
+        let client = Client::with_transport(test_config(), transport.clone()).unwrap();
+        let request = ExportRequest {
+            uuids: vec![uuid::Uuid::nil()],
+            export_type: "package".to_string(),
+            name: Some("Nightly".to_string()),
+            description: None,
+        };
+
+        let preview = client.prepare_export(&request).unwrap().preview().unwrap();
+        assert_eq!(preview.method, "POST");
+        assert_eq!(
+            preview.url,
+            "https://test.example.com/suite/deployment-management/v2/deployments"
+        );
+        assert_eq!(preview.headers["authorization"], "***REDACTED***");
+        assert_eq!(preview.headers["appian-api-key"], "***REDACTED***");
+        assert_eq!(preview.headers["action-type"], "export");
+        assert_eq!(preview.json.unwrap()["name"], "Nightly");
+        assert!(transport.requests().is_empty());
+    }
+
+    #[cfg(feature = "validate")]
+    #[tokio::test]
+    async fn test_uploads_are_rebuilt_for_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("pkg.zip");
+        std::fs::write(&package, b"PACKAGE").unwrap();
+        let request = InspectionRequest {
+            admin_console_settings_file_name: None,
+            package_file_name: "pkg.zip".to_string(),
+            customization_file_name: None,
+        };
+
+        let transport = Arc::new(MockTransport::new(vec![
+            MockResponse::json(503, "unavailable"),
+            MockResponse::json(
+                200,
+                r#"{"uuid": "00000000-0000-0000-0000-000000000000", "url": "https://test.example.com/i/1"}"#,
+            ),
+        ]));
+        let client = Client::with_transport(test_config(), transport.clone()).unwrap();
+        client
+            .inspect_package(&request, &package, None, None)
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        let key = &requests[0].headers[IDEMPOTENCY_KEY_HEADER];
+        assert_eq!(&requests[1].headers[IDEMPOTENCY_KEY_HEADER], key);
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_file_parts_are_labelled_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::new(test_config()).unwrap();
+        for (name, contents, content_type) in [
+            ("pkg.zip", &b"PK\x03\x04rest"[..], Some("application/zip")),
+            (
+                "pkg.tar.gz",
+                &[0x1f, 0x8b, 0x08, 0x00][..],
+                Some("application/gzip"),
+            ),
+            ("pkg.bin", &b"PACKAGE"[..], None),
+        ] {
+            let package = dir.path().join(name);
+            std::fs::write(&package, contents).unwrap();
+            let request = InspectionRequest {
+                admin_console_settings_file_name: None,
+                package_file_name: name.to_string(),
+                customization_file_name: None,
+            };
+            let preview = client
+                .prepare_inspection(&request, &package, None, None)
+                .unwrap()
+                .preview()
+                .unwrap();
+            assert_eq!(preview.files[0].file_name, name);
+            assert_eq!(preview.files[0].content_type.as_deref(), content_type);
+        }
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_upload_size_limit_sums_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("pkg.zip");
+        let icf = dir.path().join("import.properties");
+        std::fs::write(&package, [0u8; 8]).unwrap();
+        std::fs::write(&icf, [0u8; 8]).unwrap();
+        let request = InspectionRequest {
+            admin_console_settings_file_name: None,
+            package_file_name: "pkg.zip".to_string(),
+            customization_file_name: Some("import.properties".to_string()),
+        };
+
+        let limited = Config {
+            max_upload_bytes: Some(12),
+            ..test_config()
+        };
+        let client = Client::new(limited).unwrap();
+        // Each file fits on its own; together they exceed the limit
+        assert!(client
+            .prepare_inspection(&request, &package, None, None)
+            .is_ok());
+        let err = client
+            .prepare_inspection(&request, &package, Some(&icf), None)
+            .err()
+            .unwrap();
+        assert!(matches!(err, CliError::Validation(_)));
+
+        let unlimited = Client::new(test_config()).unwrap();
+        assert!(unlimited
+            .prepare_inspection(&request, &package, Some(&icf), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body("short"), "short");
+
+        let long = "x".repeat(TRACE_BODY_LIMIT + 10);
+        let truncated = truncate_body(&long);
+        assert!(truncated.starts_with(&"x".repeat(TRACE_BODY_LIMIT)));
+        assert!(truncated.ends_with("(10 more bytes truncated)"));
+    }
+
+    #[cfg(feature = "deploy")]
+    #[test]
+    fn test_match_data_source() {
+        let data_sources = vec![
+            DataSource {
+                uuid: "11111111-1111-1111-1111-111111111111".to_string(),
+                name: "jdbc/Appian".to_string(),
+                data_source_type: None,
+            },
+            DataSource {
+                uuid: "22222222-2222-2222-2222-222222222222".to_string(),
+                name: "jdbc/Reporting".to_string(),
+                data_source_type: None,
+            },
+            DataSource {
+                uuid: "33333333-3333-3333-3333-333333333333".to_string(),
+                name: "JDBC/Reporting".to_string(),
+                data_source_type: None,
+            },
+        ];
+
+        assert_eq!(
+            match_data_source("jdbc/appian", &data_sources).unwrap(),
+            "11111111-1111-1111-1111-111111111111"
+        );
+
+        let err = match_data_source("jdbc/Missing", &data_sources).unwrap_err();
+        assert!(matches!(err, CliError::InvalidArgument(ref m) if m.contains("jdbc/Appian")));
+
+        let err = match_data_source("jdbc/reporting", &data_sources).unwrap_err();
+        assert!(matches!(err, CliError::InvalidArgument(ref m) if m.contains("ambiguous")));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{output, Result};
+use serde::Serialize;
+
+/// What this build of the CLI can do, for tools that wrap it
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// Cargo features compiled in
+    pub features: Vec<&'static str>,
+    /// Values `--format` accepts for at least one command, including
+    /// renderers added with [`crate::render::register`]
+    pub formats: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn current() -> Self {
+        let features = [
+            ("get_packages", cfg!(feature = "get_packages")),
+            ("export", cfg!(feature = "export")),
+            ("validate", cfg!(feature = "validate")),
+            ("deploy", cfg!(feature = "deploy")),
+            ("status", cfg!(feature = "status")),
+            ("monitor", cfg!(feature = "monitor")),
+            ("download", cfg!(feature = "download")),
+            ("logs", cfg!(feature = "logs")),
+            ("rustls-tls", cfg!(feature = "rustls-tls")),
+            ("native-tls", cfg!(feature = "native-tls")),
+        ];
+
+        let mut formats = vec!["text", "json"];
+        // get-inspection and get-deployment-results
+        if cfg!(any(feature = "validate", feature = "status")) {
+            formats.extend(["junit", "markdown"]);
+        }
+        if cfg!(feature = "logs") {
+            formats.push("csv");
+        }
+
+        Capabilities {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            features: features
+                .into_iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name)
+                .collect(),
+            formats: formats
+                .into_iter()
+                .map(str::to_string)
+                .chain(crate::render::registered_formats())
+                .collect(),
+        }
+    }
+}
+
+/// Print the version, compiled-in features and output formats as JSON.
+/// Needs neither a configuration nor the network.
+pub fn execute() -> Result<()> {
+    output::json(&Capabilities::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_follow_compiled_features() {
+        let capabilities = Capabilities::current();
+        assert_eq!(capabilities.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            capabilities.features.contains(&"export"),
+            cfg!(feature = "export")
+        );
+        assert_eq!(
+            capabilities.features.contains(&"logs"),
+            cfg!(feature = "logs")
+        );
+        assert_eq!(
+            capabilities.formats.contains(&"csv".to_string()),
+            cfg!(feature = "logs")
+        );
+        assert!(capabilities
+            .formats
+            .starts_with(&["text".to_string(), "json".to_string()]));
+    }
+}
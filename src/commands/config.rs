@@ -0,0 +1,71 @@
+use crate::config::{find_config_file, Config};
+use crate::error::CliError;
+use crate::{output, render, Result};
+use colored::*;
+use std::path::PathBuf;
+
+/// Check config files without contacting the server, listing every
+/// problem with the field it concerns. With no files given, the nearest
+/// `appian-config.toml` is checked. Fails if any problem is found.
+pub fn validate(config_files: &[PathBuf], format: Option<String>) -> Result<()> {
+    render::check_builtin(format.as_deref(), "config validate")?;
+    let files: Vec<PathBuf> = if config_files.is_empty() {
+        std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_config_file(&dir))
+            .into_iter()
+            .collect()
+    } else {
+        config_files.to_vec()
+    };
+    if files.is_empty() {
+        return Err(CliError::Configuration(
+            "No config file to validate: pass --config-file or add an appian-config.toml"
+                .to_string(),
+        ));
+    }
+
+    let problems = Config::check_files(&files);
+    let names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+
+    match format.as_deref() {
+        Some("json") => {
+            output::json(&serde_json::json!({
+                "files": names,
+                "valid": problems.is_empty(),
+                "problems": problems,
+            }))?;
+        }
+        _ if problems.is_empty() => {
+            println!(
+                "{} {}",
+                "✓ Configuration is valid:".green(),
+                names.join(", ")
+            );
+        }
+        _ => {
+            println!(
+                "{} {}",
+                "✗ Configuration has problems:".red().bold(),
+                names.join(", ")
+            );
+            for problem in &problems {
+                let field = if problem.field.is_empty() {
+                    "(file)"
+                } else {
+                    problem.field.as_str()
+                };
+                println!("  {}: {}", field.bold(), problem.message);
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::Configuration(format!(
+            "{} problem(s) found",
+            problems.len()
+        )))
+    }
+}
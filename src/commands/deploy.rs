@@ -1,151 +1,1876 @@
-use crate::{client::Client, Config, Result};
+use crate::checksum::Sha256Writer;
+use crate::client::RequestPreview;
+use crate::models::{
+    DatabaseScript, DeployResponse, DeploymentRequest, ImportErrorEntry, ValidationViolation,
+    ViolationSeverity,
+};
+#[cfg(feature = "validate")]
+use crate::models::{InspectionOperationStatus, InspectionResults};
+use crate::polling::WaitOptions;
+use crate::{client::Client, output, render, Config, Result};
 use colored::*;
-
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
+/// Inputs for a deployment, mirroring the `deploy` command's flags.
+#[derive(Debug, Clone, Default)]
+pub struct DeployArgs {
+    /// Package zip; may be omitted for a plug-ins-only deployment
+    pub package_zip_name: Option<PathBuf>,
+    pub name: String,
+    pub description: Option<String>,
+    pub rollback_on_failure: bool,
+    pub customization_file: Option<PathBuf>,
+    pub admin_console_file: Option<PathBuf>,
+    /// Reference to Admin Console settings already on the server; an
+    /// alternative to uploading `admin_console_file`
+    pub admin_console_ref: Option<String>,
+    pub plugins_file: Option<PathBuf>,
+    pub data_source: Option<String>,
+    /// Scripts executed in argument order
+    pub database_scripts: Option<Vec<PathBuf>>,
+    /// `PATH:ORDER` pairs with explicit execution order
+    pub database_script: Vec<String>,
+    pub strict_order: bool,
+    /// Treat pre-flight warnings as errors
+    pub strict: bool,
+    /// `KEY=VALUE` labels recorded with the deployment's local output
+    pub labels: Vec<String>,
+    /// Inspect the package first and refuse to deploy when the inspection
+    /// finds more than this many errors
+    pub inspect_first: Option<u32>,
+    /// How often to poll the `inspect_first` inspection and how long to
+    /// wait for it; the deploy's own `--interval` and `--timeout`
+    pub inspect_wait: WaitOptions,
+    /// Deploy even while another deployment is running on the site
+    pub force: bool,
+}
+
+/// Result of the local pre-flight checks for a deployment.
+#[derive(Debug, Clone)]
+pub struct DeployPlan {
+    /// Database scripts sorted by execution order
+    pub database_scripts: Vec<(PathBuf, u32)>,
+    pub labels: BTreeMap<String, String>,
+    /// Every file that will be uploaded, checked up front
+    pub files: Vec<InputFile>,
+    /// Problems the server may reject, such as an unexpected extension
+    pub warnings: Vec<ValidationViolation>,
+}
+
+/// A file the deployment uploads, with its size at planning time.
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    /// What the file is, e.g. "Package" or "Database script 2"
+    pub role: String,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Check that every input file exists, is a readable regular file and is
+/// not empty. All problems are collected into one validation error so a
+/// multi-file deployment can be fixed in one go; an unexpected extension
+/// only adds a warning.
+fn check_input_files(
+    inputs: &[(String, &Path, &[&str])],
+    warnings: &mut Vec<ValidationViolation>,
+) -> Result<Vec<InputFile>> {
+    let mut problems = Vec::new();
+    let mut files = Vec::new();
+
+    for (role, path, extensions) in inputs {
+        let problem = match std::fs::metadata(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some("not found".to_string()),
+            Err(e) => Some(format!("cannot be read: {}", e)),
+            Ok(metadata) if !metadata.is_file() => Some("is not a file".to_string()),
+            Ok(metadata) if metadata.len() == 0 => Some("is empty".to_string()),
+            Ok(metadata) => match std::fs::File::open(path) {
+                Err(e) => Some(format!("cannot be read: {}", e)),
+                Ok(_) => {
+                    files.push(InputFile {
+                        role: role.clone(),
+                        path: path.to_path_buf(),
+                        size: metadata.len(),
+                    });
+                    None
+                }
+            },
+        };
+        if let Some(problem) = problem {
+            problems.push(format!("{} {} {}", role, path.display(), problem));
+            continue;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !extensions.contains(&extension.as_str()) {
+            warnings.push(warning(
+                "WRONG_EXTENSION",
+                format!(
+                    "{} {} does not have a {} extension",
+                    role,
+                    path.display(),
+                    extensions
+                        .iter()
+                        .map(|e| format!(".{}", e))
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                ),
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(files)
+    } else {
+        Err(crate::error::CliError::Validation(format!(
+            "{} input file problem(s):\n  {}",
+            problems.len(),
+            problems.join("\n  ")
+        )))
+    }
+}
+
+fn warning(code: &str, message: String) -> ValidationViolation {
+    ValidationViolation {
+        severity: ViolationSeverity::Warning,
+        code: code.to_string(),
+        message,
+    }
+}
+
+/// Run the local pre-flight checks without contacting the server. Warnings
+/// are returned in the plan, or as a validation error when `strict` is set.
+pub fn plan(args: &DeployArgs) -> Result<DeployPlan> {
+    if args.package_zip_name.is_none() && args.plugins_file.is_none() {
+        return Err(crate::error::CliError::InvalidArgument(
+            "Nothing to deploy: give --package-zip-name, --plugins-file or both".to_string(),
+        ));
+    }
+    if args.inspect_first.is_some() && args.package_zip_name.is_none() {
+        return Err(crate::error::CliError::InvalidArgument(
+            "--inspect-first needs a package to inspect (--package-zip-name)".to_string(),
+        ));
+    }
+    if args.admin_console_ref.is_some() && args.admin_console_file.is_some() {
+        return Err(crate::error::CliError::InvalidArgument(
+            "Give either --admin-console-file or --admin-console-ref, not both".to_string(),
+        ));
+    }
+    if args
+        .admin_console_ref
+        .as_deref()
+        .is_some_and(|r| r.trim().is_empty())
+    {
+        return Err(crate::error::CliError::InvalidArgument(
+            "--admin-console-ref must not be empty".to_string(),
+        ));
+    }
+    let database_scripts = resolve_database_scripts(
+        args.database_scripts.clone(),
+        &args.database_script,
+        args.strict_order,
+    )?;
+    let labels = parse_labels(&args.labels)?;
+
+    let mut warnings = Vec::new();
+
+    // Checked before anything is uploaded, so a deploy cannot fail partway
+    let optional_files = [
+        ("Package", &args.package_zip_name, &["zip", "tgz", "gz"][..]),
+        (
+            "Customization file",
+            &args.customization_file,
+            &["properties"][..],
+        ),
+        (
+            "Admin Console settings file",
+            &args.admin_console_file,
+            &["zip"][..],
+        ),
+        ("Plug-ins file", &args.plugins_file, &["zip"][..]),
+    ];
+    let mut inputs: Vec<(String, &Path, &[&str])> = optional_files
+        .iter()
+        .filter_map(|(role, path, extensions)| {
+            path.as_deref()
+                .map(|path| (role.to_string(), path, *extensions))
+        })
+        .collect();
+    inputs.extend(database_scripts.iter().map(|(path, order)| {
+        (
+            format!("Database script {}", order),
+            path.as_path(),
+            &["sql", "ddl"][..],
+        )
+    }));
+    let files = check_input_files(&inputs, &mut warnings)?;
+
+    // Scripts without a data source are rejected server-side with an unhelpful error
+    if !database_scripts.is_empty() && args.data_source.is_none() {
+        warnings.push(warning(
+            "MISSING_DATA_SOURCE",
+            "Database scripts were supplied without --data-source; the server will not know where to run them"
+                .to_string(),
+        ));
+    }
+
+    if args.strict && !warnings.is_empty() {
+        let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+        return Err(crate::error::CliError::Validation(format!(
+            "Pre-flight checks failed under --strict: {}",
+            warnings.join("; ")
+        )));
+    }
+
+    Ok(DeployPlan {
+        database_scripts,
+        labels,
+        files,
+        warnings,
+    })
+}
+
+// Same defaults as the `monitor` command
+#[cfg(feature = "monitor")]
+const WAIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+#[cfg(feature = "monitor")]
+const WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Start the deployment of `args`, using the `plan` that [`plan`] made for
+/// them so the input files are not checked again. Pre-flight warnings are
+/// not reported here.
+pub async fn run(client: &Client, args: &DeployArgs, plan: &DeployPlan) -> Result<DeployResponse> {
+    match args.package_zip_name {
+        Some(ref package) => info!(
+            "Starting deployment: {} with package {}",
+            args.name,
+            package.display()
+        ),
+        None => info!("Starting plug-ins-only deployment: {}", args.name),
+    }
+
+    let (request_json, script_paths) = deployment_request(client, args, plan, true).await?;
+    client
+        .deploy_package_multipart(
+            &request_json,
+            args.package_zip_name.as_deref(),
+            args.customization_file.as_deref(),
+            args.admin_console_file.as_deref(),
+            args.plugins_file.as_deref(),
+            if script_paths.is_empty() {
+                None
+            } else {
+                Some(script_paths.as_slice())
+            },
+        )
+        .await
+}
+
+/// Describe the deployment request without sending it. Data source names are
+/// passed through as given, since resolving them would call the API.
+pub async fn preview(
+    client: &Client,
+    args: &DeployArgs,
+    plan: &DeployPlan,
+) -> Result<RequestPreview> {
+    let (request_json, script_paths) = deployment_request(client, args, plan, false).await?;
+    client
+        .prepare_deploy(
+            &request_json,
+            args.package_zip_name.as_deref(),
+            args.customization_file.as_deref(),
+            args.admin_console_file.as_deref(),
+            args.plugins_file.as_deref(),
+            if script_paths.is_empty() {
+                None
+            } else {
+                Some(script_paths.as_slice())
+            },
+        )?
+        .preview()
+}
+
+/// Build the JSON part and the ordered script paths for a deployment.
+async fn deployment_request(
+    client: &Client,
+    args: &DeployArgs,
+    plan: &DeployPlan,
+    resolve_data_source: bool,
+) -> Result<(DeploymentRequest, Vec<PathBuf>)> {
+    let package_name = args
+        .package_zip_name
+        .as_ref()
+        .map(|package| {
+            package
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| {
+                    crate::error::CliError::InvalidArgument("Invalid package file name".to_string())
+                })
+        })
+        .transpose()?;
+
+    // Build JSON request object per API v2
+    let mut db_scripts_json: Vec<DatabaseScript> = vec![];
+    for (path, order) in &plan.database_scripts {
+        let fname = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| {
+            crate::error::CliError::InvalidArgument("Invalid database script file name".to_string())
+        })?;
+        db_scripts_json.push(DatabaseScript {
+            file_name: fname.to_string(),
+            order_id: order.to_string(),
+        });
+    }
+    let script_paths: Vec<PathBuf> = plan
+        .database_scripts
+        .iter()
+        .map(|(p, _)| p.clone())
+        .collect();
+
+    // Map a human-friendly data source name to the UUID the API expects
+    let data_source = match args.data_source {
+        Some(ref ds) if resolve_data_source => Some(client.resolve_data_source(ds).await?),
+        Some(ref ds) => Some(ds.clone()),
+        None => None,
+    };
+
+    let customization_file_name = args
+        .customization_file
+        .as_ref()
+        .and_then(|p| p.file_name().and_then(|n| n.to_str()))
+        .map(|s| s.to_string());
+    let admin_console_file_name = args
+        .admin_console_file
+        .as_ref()
+        .and_then(|p| p.file_name().and_then(|n| n.to_str()))
+        .map(|s| s.to_string());
+    let plugins_file_name = args
+        .plugins_file
+        .as_ref()
+        .and_then(|p| p.file_name().and_then(|n| n.to_str()))
+        .map(|s| s.to_string());
+
+    let request_json = DeploymentRequest {
+        name: args.name.clone(),
+        description: args.description.clone(),
+        admin_console_settings_file_name: admin_console_file_name,
+        admin_console_settings_ref: args
+            .admin_console_ref
+            .as_deref()
+            .map(|r| r.trim().to_string()),
+        package_file_name: package_name,
+        customization_file_name,
+        plugins_file_name,
+        data_source,
+        database_scripts: if db_scripts_json.is_empty() {
+            None
+        } else {
+            Some(db_scripts_json)
+        },
+    };
+
+    Ok((request_json, script_paths))
+}
+
 pub async fn execute(
     config: Config,
-    package_zip_name: std::path::PathBuf,
-    name: String,
-    description: Option<String>,
+    args: DeployArgs,
     dry_run: bool,
-    rollback_on_failure: bool,
-    customization_file: Option<std::path::PathBuf>,
-    admin_console_file: Option<std::path::PathBuf>,
-    plugins_file: Option<std::path::PathBuf>,
-    data_source: Option<String>,
-    database_scripts: Option<Vec<std::path::PathBuf>>,
+    print_request: bool,
+    wait: Option<WaitOptions>,
+    fail_on_pending: bool,
     format: Option<String>,
 ) -> Result<()> {
-    if !package_zip_name.exists() {
-        return Err(crate::error::CliError::InvalidArgument(format!(
-            "Package file not found: {}",
-            package_zip_name.display()
-        )));
-    }
+    let plan = plan(&args)?;
 
     if dry_run {
         info!("Dry run mode - validating deployment parameters");
-        println!("{}", "Dry run validation successful".green());
-        println!("Package: {}", package_zip_name.display());
-        println!("Deployment name: {}", name);
-        println!("Description: {:?}", description);
-        println!("Rollback on failure: {}", rollback_on_failure);
-        if let Some(ref cf) = customization_file { println!("Customization file: {}", cf.display()); }
-        if let Some(ref acf) = admin_console_file { println!("Admin Console settings: {}", acf.display()); }
-        if let Some(ref pf) = plugins_file { println!("Plugins file: {}", pf.display()); }
-        if let Some(ref ds) = data_source { println!("Data source: {}", ds); }
-        if let Some(ref scripts) = database_scripts {
-            println!("Database scripts (order):");
-            for (i, s) in scripts.iter().enumerate() { println!("  {}. {}", i+1, s.display()); }
+        output::status("Dry run validation successful".green());
+        match args.package_zip_name {
+            Some(ref package) => output::status(format!("Package: {}", package.display())),
+            None => output::status("Package: none (plug-ins only)"),
+        }
+        output::status(format!("Deployment name: {}", args.name));
+        output::status(format!("Description: {:?}", args.description));
+        output::status(format!("Rollback on failure: {}", args.rollback_on_failure));
+        if let Some(ref cf) = args.customization_file {
+            output::status(format!("Customization file: {}", cf.display()));
+        }
+        if let Some(ref acf) = args.admin_console_file {
+            output::status(format!("Admin Console settings: {}", acf.display()));
+        }
+        if let Some(ref acr) = args.admin_console_ref {
+            output::status(format!(
+                "Admin Console settings: reference {} (not uploaded)",
+                acr.trim()
+            ));
+        }
+        if let Some(ref pf) = args.plugins_file {
+            output::status(format!("Plugins file: {}", pf.display()));
+        }
+        if let Some(ref ds) = args.data_source {
+            output::status(format!("Data source: {}", ds));
+        }
+        if !plan.database_scripts.is_empty() {
+            output::status("Database scripts (order):");
+            for (path, order) in &plan.database_scripts {
+                output::status(format!("  {}. {}", order, path.display()));
+            }
+        }
+        if !plan.labels.is_empty() {
+            output::status("Labels:");
+            for (key, value) in &plan.labels {
+                output::status(format!("  {}={}", key, value));
+            }
+        }
+        output::status("Input files checked:");
+        for file in &plan.files {
+            output::status(format!(
+                "  ✓ {}: {} ({} bytes)",
+                file.role,
+                file.path.display(),
+                file.size
+            ));
+        }
+        if !plan.warnings.is_empty() {
+            output::status("Pre-flight warnings:".yellow());
+            for warning in &plan.warnings {
+                output::status(format!("  - {}", warning));
+            }
         }
         return Ok(());
     }
 
+    for warning in &plan.warnings {
+        output::warning(warning);
+    }
+
     let client = Client::new(config)?;
-    
-    info!("Starting deployment: {} with package {}", name, package_zip_name.display());
-    println!("{}", "Starting deployment...".cyan());
-    
-    let package_name = package_zip_name
-        .file_name()
-        .and_then(|n| n.to_str())
-        .ok_or_else(|| crate::error::CliError::InvalidArgument(
-            "Invalid package file name".to_string()
-        ))?;
-
-    // Validate optional files
-    if let Some(ref path) = customization_file {
-        if !path.exists() {
+
+    if print_request {
+        return output::request_preview(&preview(&client, &args, &plan).await?, format.as_deref());
+    }
+
+    if let Some(max_errors) = args.inspect_first {
+        inspect_first(&client, &args, max_errors).await?;
+    }
+
+    // Checked last, so a deployment started during --inspect-first is caught
+    if args.force {
+        info!("--force given; not checking for deployments in progress");
+    } else {
+        check_no_active_deployment(&client).await?;
+    }
+
+    output::record_labels(&plan.labels);
+    output::status("Starting deployment...".cyan());
+
+    let response = run(&client, &args, &plan).await?;
+    if !plan.labels.is_empty() {
+        info!("Deployment {} labels: {:?}", response.uuid, plan.labels);
+    }
+
+    output::status("Deployment initiated successfully".green());
+    output::identifier("Deployment UUID", response.uuid);
+    output::status(format!("Status URL: {}", response.url));
+    output::status(format!("Status: {}", response.status.yellow()));
+
+    if let Some(options) = wait {
+        // The final status replaces the initiation response in JSON output
+        let format = if output::is_quiet() {
+            None
+        } else {
+            format.as_deref()
+        };
+        return wait_for_deployment(
+            &client,
+            &response,
+            options,
+            fail_on_pending,
+            format,
+            &plan.labels,
+        )
+        .await;
+    }
+
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    render::renderer(format.as_deref()).render_deploy_result(&response, &plan.labels)
+}
+
+/// Refuse to start while another deployment is running on the site, since
+/// overlapping imports can leave it inconsistent. Sites that do not list
+/// deployments are not checked, and a listing that cannot be read only
+/// warns.
+async fn check_no_active_deployment(client: &Client) -> Result<()> {
+    let active = match client.active_deployments().await {
+        Ok(Some(active)) => active,
+        Ok(None) => {
+            info!("This site does not list deployments; skipping the check for deployments in progress");
+            return Ok(());
+        }
+        Err(e) => {
+            output::warning(format!(
+                "Could not check for deployments in progress ({}); deploying anyway",
+                e
+            ));
+            return Ok(());
+        }
+    };
+    if active.is_empty() {
+        return Ok(());
+    }
+
+    let names: Vec<String> = active
+        .iter()
+        .map(|d| match &d.name {
+            Some(name) => format!("{} ('{}', {})", d.uuid, name, d.status),
+            None => format!("{} ({})", d.uuid, d.status),
+        })
+        .collect();
+    Err(crate::error::CliError::Validation(format!(
+        "Another deployment is in progress on this site: {}. Wait for it to finish, or pass --force to deploy anyway",
+        names.join(", ")
+    )))
+}
+
+/// Files downloaded from an export's results for `deploy --from-export`.
+#[derive(Debug, Clone)]
+pub struct ExportArtifacts {
+    pub package_zip: PathBuf,
+    pub customization_file: Option<PathBuf>,
+    pub plugins_file: Option<PathBuf>,
+}
+
+/// Download the package zip, and the customization and plug-ins files when
+/// present, of a completed export into `dir`.
+#[cfg(feature = "download")]
+pub async fn fetch_export_artifacts(
+    client: &Client,
+    export_uuid: &str,
+    dir: &std::path::Path,
+) -> Result<ExportArtifacts> {
+    use crate::models::{DeploymentResults, ExportStatus};
+
+    let export = match client.get_deployment_results(export_uuid).await? {
+        DeploymentResults::Export(export) => export,
+        DeploymentResults::Import(_) => {
             return Err(crate::error::CliError::InvalidArgument(format!(
-                "Customization file not found: {}",
-                path.display()
-            )));
+                "{} is a deployment, not an export",
+                export_uuid
+            )))
+        }
+    };
+    match export.status {
+        ExportStatus::InProgress => {
+            return Err(crate::error::CliError::InvalidArgument(format!(
+                "Export {} is still in progress",
+                export_uuid
+            )))
+        }
+        ExportStatus::Failed => {
+            return Err(crate::error::CliError::InvalidArgument(format!(
+                "Export {} failed",
+                export_uuid
+            )))
+        }
+        ExportStatus::Canceled => {
+            return Err(crate::error::CliError::InvalidArgument(format!(
+                "Export {} was canceled",
+                export_uuid
+            )))
+        }
+        ExportStatus::CompletedWithErrors | ExportStatus::CompletedWithExportErrors => {
+            output::warning(format!(
+                "Export {} completed with errors; deploying what it produced",
+                export_uuid
+            ));
         }
+        ExportStatus::Completed => {}
     }
-    if let Some(ref path) = admin_console_file {
-        if !path.exists() {
+
+    let package_link = export.package_zip.as_deref().ok_or_else(|| {
+        crate::error::CliError::InvalidArgument(format!(
+            "Export {} has no package zip",
+            export_uuid
+        ))
+    })?;
+
+    let download = |link: String, fallback: &'static str| async move {
+        let path = dir.join(link_file_name(&link, fallback));
+        let file = std::fs::File::create(&path).map_err(|e| {
+            crate::error::CliError::FileSystem(format!(
+                "Failed to create {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let mut out = std::io::BufWriter::new(file);
+        let size = client.download_url_to(&link, &mut out).await?;
+        std::io::Write::flush(&mut out).map_err(|e| {
+            crate::error::CliError::FileSystem(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+        output::status(format!("  {} ({} bytes)", path.display(), size).dimmed());
+        Ok::<_, crate::error::CliError>(path)
+    };
+
+    let package_zip = download(package_link.to_string(), "package.zip").await?;
+    let customization_file = match export.customization_file {
+        Some(link) => Some(download(link, "customization.properties").await?),
+        None => None,
+    };
+    let plugins_file = match export.plugins_zip {
+        Some(link) => Some(download(link, "plugins.zip").await?),
+        None => None,
+    };
+
+    Ok(ExportArtifacts {
+        package_zip,
+        customization_file,
+        plugins_file,
+    })
+}
+
+/// Deploy the artifacts of `export_uuid`. They are downloaded into a
+/// temporary directory that is removed afterwards unless `keep_artifacts`
+/// is set. Files given explicitly in `args` take precedence.
+#[cfg(feature = "download")]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_from_export(
+    config: Config,
+    export_uuid: String,
+    keep_artifacts: bool,
+    mut args: DeployArgs,
+    dry_run: bool,
+    print_request: bool,
+    wait: Option<WaitOptions>,
+    fail_on_pending: bool,
+    format: Option<String>,
+) -> Result<()> {
+    let client = Client::new(config.clone())?;
+    let dir = tempfile::Builder::new()
+        .prefix("appian-export-")
+        .tempdir()
+        .map_err(|e| {
+            crate::error::CliError::FileSystem(format!(
+                "Failed to create temporary directory: {}",
+                e
+            ))
+        })?;
+
+    output::status(format!("Downloading artifacts of export {}...", export_uuid).cyan());
+    let fetched = fetch_export_artifacts(&client, &export_uuid, dir.path()).await;
+
+    let result = match fetched {
+        Ok(artifacts) => {
+            args.package_zip_name = Some(artifacts.package_zip);
+            args.customization_file = args.customization_file.or(artifacts.customization_file);
+            args.plugins_file = args.plugins_file.or(artifacts.plugins_file);
+            execute(
+                config,
+                args,
+                dry_run,
+                print_request,
+                wait,
+                fail_on_pending,
+                format,
+            )
+            .await
+        }
+        Err(err) => Err(err),
+    };
+
+    if keep_artifacts {
+        output::status(format!("Export artifacts kept in {}", dir.keep().display()));
+    }
+    result
+}
+
+#[cfg(not(feature = "download"))]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_from_export(
+    _config: Config,
+    _export_uuid: String,
+    _keep_artifacts: bool,
+    _args: DeployArgs,
+    _dry_run: bool,
+    _print_request: bool,
+    _wait: Option<WaitOptions>,
+    _fail_on_pending: bool,
+    _format: Option<String>,
+) -> Result<()> {
+    Err(crate::error::CliError::InvalidArgument(
+        "--from-export requires a build with the download feature".to_string(),
+    ))
+}
+
+/// The objects that failed to import in `deployment_uuid`, from its
+/// results. Only deployments that completed with import errors qualify; the
+/// list is empty when the server did not report which objects failed.
+pub fn failed_objects(
+    deployment_uuid: &str,
+    results: crate::models::DeploymentResults,
+) -> Result<Vec<ImportErrorEntry>> {
+    use crate::models::{DeploymentResults, ImportDeploymentStatus};
+
+    let import = match results {
+        DeploymentResults::Import(import) => import,
+        DeploymentResults::Export(_) => {
             return Err(crate::error::CliError::InvalidArgument(format!(
-                "Admin Console settings file not found: {}",
-                path.display()
+                "{} is an export, not a deployment",
+                deployment_uuid
+            )))
+        }
+    };
+    match import.status {
+        ImportDeploymentStatus::CompletedWithImportErrors => Ok(import.summary.objects.errors),
+        ImportDeploymentStatus::InProgress | ImportDeploymentStatus::PendingReview => {
+            Err(crate::error::CliError::InvalidArgument(format!(
+                "Deployment {} has not finished ({:?})",
+                deployment_uuid, import.status
+            )))
+        }
+        status => Err(crate::error::CliError::InvalidArgument(format!(
+            "Deployment {} finished {:?}; --retry-failed only applies to deployments that completed with import errors",
+            deployment_uuid, status
+        ))),
+    }
+}
+
+/// The UUIDs in `uuids` that have no file in the package zip at `package`,
+/// sorted. Object files are named after their UUID (see
+/// [`crate::archive::object_uuid`]).
+pub fn missing_objects(package: &Path, uuids: &HashSet<&str>) -> Result<Vec<String>> {
+    let file = std::fs::File::open(package).map_err(|e| {
+        crate::error::CliError::FileSystem(format!(
+            "Failed to read package {}: {}",
+            package.display(),
+            e
+        ))
+    })?;
+    let archive = zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| {
+        crate::error::CliError::Validation(format!(
+            "Failed to read package {}: {}",
+            package.display(),
+            e
+        ))
+    })?;
+    let found: HashSet<&str> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && !name.starts_with("META-INF/"))
+        .map(crate::archive::object_uuid)
+        .collect();
+    let mut missing: Vec<String> = uuids
+        .difference(&found)
+        .map(|uuid| uuid.to_string())
+        .collect();
+    missing.sort();
+    Ok(missing)
+}
+
+/// Redeploy the package of `deployment_uuid`, given as
+/// `args.package_zip_name`, after it completed with import errors. The API
+/// cannot import individual objects (a package is always imported whole,
+/// and precedents and dependencies resolve within it), so the full package
+/// is deployed again once the failed objects are confirmed to be in it.
+/// Database scripts ran in the first deployment and are refused unless
+/// `rerun_database_scripts` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_retry_failed(
+    config: Config,
+    deployment_uuid: String,
+    args: DeployArgs,
+    rerun_database_scripts: bool,
+    dry_run: bool,
+    print_request: bool,
+    wait: Option<WaitOptions>,
+    fail_on_pending: bool,
+    format: Option<String>,
+) -> Result<()> {
+    let package = args.package_zip_name.clone().filter(|p| p != Path::new("-")).ok_or_else(|| {
+        crate::error::CliError::InvalidArgument(
+            "--retry-failed needs the package file that was deployed (--package-zip-name), not stdin".to_string(),
+        )
+    })?;
+    let has_scripts = args
+        .database_scripts
+        .as_ref()
+        .is_some_and(|s| !s.is_empty())
+        || !args.database_script.is_empty();
+    if has_scripts && !rerun_database_scripts {
+        return Err(crate::error::CliError::InvalidArgument(format!(
+            "Database scripts already ran in deployment {}; drop them from the retry or pass --rerun-database-scripts",
+            deployment_uuid
+        )));
+    }
+
+    let client = Client::new(config.clone())?;
+    output::status(format!("Reading results of deployment {}...", deployment_uuid).cyan());
+    let failed = failed_objects(
+        &deployment_uuid,
+        client.get_deployment_results(&deployment_uuid).await?,
+    )?;
+    let mut uuids = HashSet::new();
+    let failed: Vec<&ImportErrorEntry> = failed
+        .iter()
+        .filter(|e| uuids.insert(e.object_uuid.as_str()))
+        .collect();
+    let missing = missing_objects(&package, &uuids)?;
+    if !missing.is_empty() {
+        return Err(crate::error::CliError::Validation(format!(
+            "{} failed object(s) of deployment {} are not in {}: {}. Pass the package that deployment imported",
+            missing.len(),
+            deployment_uuid,
+            package.display(),
+            missing.join(", ")
+        )));
+    }
+
+    if failed.is_empty() {
+        output::warning(format!(
+            "Deployment {} does not list the objects that failed to import",
+            deployment_uuid
+        ));
+    } else {
+        output::status(format!(
+            "Objects that failed to import in deployment {}:",
+            deployment_uuid
+        ));
+        for entry in &failed {
+            output::status(format!("  {} ({})", entry.object_name, entry.object_uuid).dimmed());
+        }
+    }
+    output::warning(
+        "The Appian API cannot retry individual objects; redeploying the whole package",
+    );
+    execute(
+        config,
+        args,
+        dry_run,
+        print_request,
+        wait,
+        fail_on_pending,
+        format,
+    )
+    .await
+}
+
+/// Parse a `--package-url`. Only `https` is accepted, so the package cannot
+/// be replaced in transit.
+pub fn check_package_url(link: &str) -> Result<url::Url> {
+    let parsed = url::Url::parse(link).map_err(|e| {
+        crate::error::CliError::InvalidArgument(format!("Invalid --package-url '{}': {}", link, e))
+    })?;
+    if parsed.scheme() != "https" {
+        return Err(crate::error::CliError::InvalidArgument(format!(
+            "--package-url must use https, not '{}'",
+            parsed.scheme()
+        )));
+    }
+    Ok(parsed)
+}
+
+/// Normalize a `--package-sha256` value to lowercase hex.
+pub fn check_sha256(value: &str) -> Result<String> {
+    crate::checksum::parse_sha256(value, "--package-sha256")
+}
+
+/// A URL for messages: no credentials and no query string, which may hold
+/// an access token.
+fn display_url(link: &url::Url) -> String {
+    format!("{}{}", link.origin().ascii_serialization(), link.path())
+}
+
+/// Stream the package at `link` into `dir` and check it against `sha256`
+/// (lowercase hex) when given. Returns the downloaded file.
+pub async fn fetch_package(
+    client: &Client,
+    link: &url::Url,
+    sha256: Option<&str>,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let path = dir.join(link_file_name(link.as_str(), "package.zip"));
+    let file = std::fs::File::create(&path).map_err(|e| {
+        crate::error::CliError::FileSystem(format!("Failed to create {}: {}", path.display(), e))
+    })?;
+    let mut out = Sha256Writer::new(std::io::BufWriter::new(file));
+
+    let size = client.download_url_to(link.as_str(), &mut out).await?;
+    std::io::Write::flush(&mut out)?;
+
+    let (_, digest) = out.finish();
+    if let Some(expected) = sha256 {
+        if digest != expected {
+            return Err(crate::error::CliError::Validation(format!(
+                "Checksum mismatch for {}: expected sha256 {}, got {}",
+                display_url(link),
+                expected,
+                digest
             )));
         }
     }
-    if let Some(ref path) = plugins_file {
-        if !path.exists() {
+    output::status(format!("  {} ({} bytes, sha256 {})", path.display(), size, digest).dimmed());
+    Ok(path)
+}
+
+/// Deploy the package at `package_url`. It is downloaded into a temporary
+/// directory that is removed afterwards. A failed download is reported as
+/// the download stage (exit code 12) so it is not mistaken for a failed
+/// deployment.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_from_url(
+    config: Config,
+    package_url: String,
+    package_sha256: Option<String>,
+    mut args: DeployArgs,
+    dry_run: bool,
+    print_request: bool,
+    wait: Option<WaitOptions>,
+    fail_on_pending: bool,
+    format: Option<String>,
+) -> Result<()> {
+    use crate::error::Stage;
+
+    let link = check_package_url(&package_url)?;
+    let sha256 = package_sha256.as_deref().map(check_sha256).transpose()?;
+    let client = Client::new(config.clone())?;
+    let dir = tempfile::Builder::new()
+        .prefix("appian-package-")
+        .tempdir()
+        .map_err(|e| {
+            crate::error::CliError::FileSystem(format!(
+                "Failed to create temporary directory: {}",
+                e
+            ))
+        })?;
+
+    output::status(format!("Downloading package from {}...", display_url(&link)).cyan());
+    let package = fetch_package(&client, &link, sha256.as_deref(), dir.path())
+        .await
+        .map_err(|e| Stage::Download.wrap(e))?;
+
+    args.package_zip_name = Some(package);
+    execute(
+        config,
+        args,
+        dry_run,
+        print_request,
+        wait,
+        fail_on_pending,
+        format,
+    )
+    .await
+}
+
+/// Copy a package streamed on `input` into `dir` as `name`, the file name
+/// the upload will carry. `name` must be a plain file name.
+pub fn read_package(input: &mut impl std::io::Read, name: &str, dir: &Path) -> Result<PathBuf> {
+    let invalid = name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']);
+    if invalid {
+        return Err(crate::error::CliError::InvalidArgument(format!(
+            "--package-name must be a file name such as app.zip, not '{}'",
+            name
+        )));
+    }
+
+    let path = dir.join(name);
+    let file = std::fs::File::create(&path).map_err(|e| {
+        crate::error::CliError::FileSystem(format!("Failed to create {}: {}", path.display(), e))
+    })?;
+    let mut out = std::io::BufWriter::new(file);
+    let size = std::io::copy(input, &mut out)
+        .and_then(|size| std::io::Write::flush(&mut out).map(|()| size))
+        .map_err(|e| {
+            crate::error::CliError::FileSystem(format!(
+                "Failed to read the package from stdin: {}",
+                e
+            ))
+        })?;
+    if size == 0 {
+        return Err(crate::error::CliError::InvalidArgument(
+            "No package data on stdin (--package-zip-name -)".to_string(),
+        ));
+    }
+    output::status(format!("  {} ({} bytes from stdin)", name, size).dimmed());
+    Ok(path)
+}
+
+/// Deploy a package read from stdin (`--package-zip-name -`), uploaded as
+/// `package_name`. It is buffered in a temporary directory, since retries
+/// upload it again, and the directory is removed afterwards.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_from_stdin(
+    config: Config,
+    package_name: Option<String>,
+    mut args: DeployArgs,
+    dry_run: bool,
+    print_request: bool,
+    wait: Option<WaitOptions>,
+    fail_on_pending: bool,
+    format: Option<String>,
+) -> Result<()> {
+    let package_name = package_name.ok_or_else(|| {
+        crate::error::CliError::InvalidArgument(
+            "--package-name is required when reading the package from stdin (--package-zip-name -)"
+                .to_string(),
+        )
+    })?;
+    let dir = tempfile::Builder::new()
+        .prefix("appian-package-")
+        .tempdir()
+        .map_err(|e| {
+            crate::error::CliError::FileSystem(format!(
+                "Failed to create temporary directory: {}",
+                e
+            ))
+        })?;
+
+    output::status("Reading package from stdin...".cyan());
+    let package = read_package(&mut std::io::stdin().lock(), &package_name, dir.path())?;
+
+    args.package_zip_name = Some(package);
+    execute(
+        config,
+        args,
+        dry_run,
+        print_request,
+        wait,
+        fail_on_pending,
+        format,
+    )
+    .await
+}
+
+/// File name for a downloaded artifact: the link's last path segment, or
+/// `fallback` when it has none usable.
+fn link_file_name(link: &str, fallback: &str) -> String {
+    let segment = link
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(link)
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    let sanitized: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+    if sanitized.is_empty() {
+        fallback.to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Inspect the deployment's package and wait for the results. Fails with a
+/// validation error when the inspection fails or finds more than
+/// `max_errors` errors, so the package is never deployed.
+#[cfg(feature = "validate")]
+pub async fn inspect_first(
+    client: &Client,
+    args: &DeployArgs,
+    max_errors: u32,
+) -> Result<InspectionResults> {
+    use crate::commands::{inspect, inspection_results};
+
+    let package = args.package_zip_name.as_deref().ok_or_else(|| {
+        crate::error::CliError::InvalidArgument(
+            "--inspect-first needs a package to inspect".to_string(),
+        )
+    })?;
+    output::status(format!("Inspecting {} before deploying...", package.display()).cyan());
+
+    let response = inspect::run(
+        client,
+        package,
+        args.customization_file.as_deref(),
+        args.admin_console_file.as_deref(),
+    )
+    .await?;
+    let uuid = response.uuid.to_string();
+    output::status(format!("Inspection UUID: {}", uuid));
+
+    let results = inspection_results::wait(client, &uuid, args.inspect_wait).await?;
+    output::status("");
+    check_inspection(&uuid, &results, max_errors)?;
+
+    output::status(
+        format!(
+            "✓ Inspection passed ({} errors, {} warnings)",
+            results.summary.problems.total_errors, results.summary.problems.total_warnings
+        )
+        .green(),
+    );
+    Ok(results)
+}
+
+#[cfg(not(feature = "validate"))]
+pub async fn inspect_first(_client: &Client, _args: &DeployArgs, _max_errors: u32) -> Result<()> {
+    Err(crate::error::CliError::InvalidArgument(
+        "--inspect-first requires a build with the validate feature".to_string(),
+    ))
+}
+
+/// Whether a finished inspection allows the deployment to go ahead.
+#[cfg(feature = "validate")]
+fn check_inspection(uuid: &str, results: &InspectionResults, max_errors: u32) -> Result<()> {
+    let problems = &results.summary.problems;
+    if matches!(results.status, InspectionOperationStatus::Failed) {
+        return Err(crate::error::CliError::Validation(format!(
+            "Inspection {} failed; not deploying",
+            uuid
+        )));
+    }
+    if problems.total_errors > max_errors {
+        let mut message = format!(
+            "Inspection {} found {} errors (at most {} allowed); not deploying",
+            uuid, problems.total_errors, max_errors
+        );
+        for error in problems.errors.iter().take(5) {
+            message.push_str(&format!(
+                "\n  {}: {}",
+                error.object_name, error.error_message
+            ));
+        }
+        if problems.errors.len() > 5 {
+            message.push_str(&format!(
+                "\n  ... and {} more (see get-inspection --uuid {})",
+                problems.errors.len() - 5,
+                uuid
+            ));
+        }
+        return Err(crate::error::CliError::Validation(message));
+    }
+    Ok(())
+}
+
+/// Poll a started deployment until it finishes. Fails unless it succeeded.
+#[cfg(feature = "monitor")]
+async fn wait_for_deployment(
+    client: &Client,
+    response: &DeployResponse,
+    options: WaitOptions,
+    fail_on_pending: bool,
+    format: Option<&str>,
+    labels: &BTreeMap<String, String>,
+) -> Result<()> {
+    use crate::commands::monitor;
+
+    let deployment_uuid = response.uuid.to_string();
+    output::status("Waiting for the deployment to finish...".cyan());
+
+    let mut elapsed = 0;
+    let report = monitor::run(
+        client,
+        &deployment_uuid,
+        Some("deployment"),
+        options.interval_or(WAIT_INTERVAL),
+        options.timeout_or(WAIT_TIMEOUT),
+        |secs, report| {
+            elapsed = secs;
+            output::progress(format!("[{:4}s] Status: {}", secs, report.status_label()).dimmed());
+        },
+    )
+    .await?;
+
+    let outcome = monitor::finish(
+        &deployment_uuid,
+        &report,
+        elapsed,
+        fail_on_pending,
+        format,
+        labels,
+    );
+    let outcome = monitor::explain_rollback(client, &deployment_uuid, outcome).await;
+    monitor::notify(client, &deployment_uuid, &report, elapsed, outcome.is_ok()).await;
+    outcome
+}
+
+#[cfg(not(feature = "monitor"))]
+async fn wait_for_deployment(
+    _client: &Client,
+    _response: &DeployResponse,
+    _options: WaitOptions,
+    _fail_on_pending: bool,
+    _format: Option<&str>,
+    _labels: &BTreeMap<String, String>,
+) -> Result<()> {
+    Err(crate::error::CliError::InvalidArgument(
+        "--wait requires a build with the monitor feature".to_string(),
+    ))
+}
+
+/// Resolve the database scripts to upload, sorted by execution order.
+///
+/// `--database-scripts` assigns orders 1..n from the argument order, while
+/// `--database-script PATH:ORDER` sets each order explicitly. The two forms
+/// cannot be combined. Duplicate orders are always rejected; with
+/// `strict_order` the orders must also be contiguous starting at 1.
+fn resolve_database_scripts(
+    sequential: Option<Vec<PathBuf>>,
+    explicit: &[String],
+    strict_order: bool,
+) -> Result<Vec<(PathBuf, u32)>> {
+    let sequential = sequential.unwrap_or_default();
+    if !sequential.is_empty() && !explicit.is_empty() {
+        return Err(crate::error::CliError::InvalidArgument(
+            "--database-scripts and --database-script cannot be combined".to_string(),
+        ));
+    }
+
+    let mut scripts: Vec<(PathBuf, u32)> = if explicit.is_empty() {
+        sequential
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (p, (i + 1) as u32))
+            .collect()
+    } else {
+        explicit
+            .iter()
+            .map(|spec| parse_script_spec(spec))
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut seen = HashSet::new();
+    for (path, order) in &scripts {
+        if !seen.insert(*order) {
             return Err(crate::error::CliError::InvalidArgument(format!(
-                "Plugins file not found: {}",
+                "Duplicate database script order {} ({})",
+                order,
                 path.display()
             )));
         }
     }
-    if let Some(ref scripts) = database_scripts {
-        for s in scripts {
-            if !s.exists() {
+
+    scripts.sort_by_key(|(_, order)| *order);
+
+    if strict_order {
+        for (expected, (path, order)) in (1u32..).zip(&scripts) {
+            if *order != expected {
                 return Err(crate::error::CliError::InvalidArgument(format!(
-                    "Database script not found: {}",
-                    s.display()
+                    "Database script order has a gap: expected {} but found {} ({})",
+                    expected,
+                    order,
+                    path.display()
                 )));
             }
         }
     }
 
-    // Build JSON request object per API v2
-    let mut db_scripts_json: Vec<crate::models::DatabaseScript> = vec![];
-    if let Some(ref scripts) = database_scripts {
-        for (i, path) in scripts.iter().enumerate() {
-            let fname = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| crate::error::CliError::InvalidArgument("Invalid database script file name".to_string()))?;
-            db_scripts_json.push(crate::models::DatabaseScript {
-                file_name: fname.to_string(),
-                order_id: (i + 1).to_string(),
-            });
+    Ok(scripts)
+}
+
+/// Parse a `PATH:ORDER` pair. Splits on the last colon so Windows drive
+/// letters (`C:\db\01.sql:1`) are preserved.
+fn parse_script_spec(spec: &str) -> Result<(PathBuf, u32)> {
+    let (path, order) = spec.rsplit_once(':').ok_or_else(|| {
+        crate::error::CliError::InvalidArgument(format!(
+            "Invalid --database-script '{}': expected PATH:ORDER",
+            spec
+        ))
+    })?;
+
+    let order: u32 = order.trim().parse().map_err(|_| {
+        crate::error::CliError::InvalidArgument(format!(
+            "Invalid order '{}' in --database-script '{}'",
+            order, spec
+        ))
+    })?;
+    if order == 0 || path.is_empty() {
+        return Err(crate::error::CliError::InvalidArgument(format!(
+            "Invalid --database-script '{}': order starts at 1 and path must be non-empty",
+            spec
+        )));
+    }
+
+    Ok((PathBuf::from(path), order))
+}
+
+/// Parse `KEY=VALUE` labels. Keys start with a letter or digit and may
+/// contain letters, digits, `.`, `_` and `-`; each key may appear once.
+fn parse_labels(specs: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut labels = BTreeMap::new();
+    for spec in specs {
+        let (key, value) = spec.split_once('=').ok_or_else(|| {
+            crate::error::CliError::InvalidArgument(format!(
+                "Invalid --label '{}': expected KEY=VALUE",
+                spec
+            ))
+        })?;
+        let key = key.trim();
+        let valid_key = key.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        if !valid_key {
+            return Err(crate::error::CliError::InvalidArgument(format!(
+                "Invalid label key '{}': use letters, digits, '.', '_' or '-', starting with a letter or digit",
+                key
+            )));
+        }
+        if labels.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(crate::error::CliError::InvalidArgument(format!(
+                "Duplicate label '{}'",
+                key
+            )));
         }
     }
+    Ok(labels)
+}
 
-    let customization_file_name = customization_file.as_ref().and_then(|p| p.file_name().and_then(|n| n.to_str())).map(|s| s.to_string());
-    let admin_console_file_name = admin_console_file.as_ref().and_then(|p| p.file_name().and_then(|n| n.to_str())).map(|s| s.to_string());
-    let plugins_file_name = plugins_file.as_ref().and_then(|p| p.file_name().and_then(|n| n.to_str())).map(|s| s.to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let request_json = crate::models::DeploymentRequest {
-        name: name.clone(),
-        description: description.clone(),
-        admin_console_settings_file_name: admin_console_file_name,
-        package_file_name: Some(package_name.to_string()),
-        customization_file_name: customization_file_name,
-        plugins_file_name: plugins_file_name,
-        data_source,
-        database_scripts: if db_scripts_json.is_empty() { None } else { Some(db_scripts_json) },
-    };
+    #[test]
+    fn test_plan_requires_package_or_plugins() {
+        let err = plan(&DeployArgs::default()).unwrap_err();
+        assert!(err.to_string().contains("Nothing to deploy"));
 
-    let response = client
-        .deploy_package_multipart(
-            &request_json,
-            &package_zip_name,
-            customization_file.as_deref(),
-            admin_console_file.as_deref(),
-            plugins_file.as_deref(),
-            database_scripts.as_ref().map(|v| v.as_slice()),
+        let dir = tempfile::tempdir().unwrap();
+        let plugins = dir.path().join("plugins.zip");
+        std::fs::write(&plugins, b"PLUGINS").unwrap();
+        let args = DeployArgs {
+            plugins_file: Some(plugins),
+            ..Default::default()
+        };
+        assert!(plan(&args).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_admin_console_ref_replaces_the_upload() {
+        use crate::transport::MockTransport;
+
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("pkg.zip");
+        std::fs::write(&package, b"PACKAGE").unwrap();
+        let mut args = DeployArgs {
+            package_zip_name: Some(package),
+            name: "Release".to_string(),
+            admin_console_ref: Some(" acs-2024-q3 ".to_string()),
+            admin_console_file: Some(dir.path().join("admin.zip")),
+            ..Default::default()
+        };
+        let err = plan(&args).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: Give either --admin-console-file or --admin-console-ref, not both"
+        );
+
+        args.admin_console_file = None;
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let client =
+            Client::with_transport(config, std::sync::Arc::new(MockTransport::new(vec![])))
+                .unwrap();
+        let planned = plan(&args).unwrap();
+        let (request, _) = deployment_request(&client, &args, &planned, false)
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["adminConsoleSettingsRef"], "acs-2024-q3");
+        assert!(json.get("adminConsoleSettingsFileName").is_none());
+
+        args.admin_console_ref = Some("  ".to_string());
+        assert!(plan(&args)
+            .unwrap_err()
+            .to_string()
+            .contains("--admin-console-ref must not be empty"));
+    }
+
+    #[tokio::test]
+    async fn test_active_deployment_blocks_deploy() {
+        use crate::transport::{MockResponse, MockTransport};
+
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let client_for = |response: MockResponse| {
+            let transport = std::sync::Arc::new(MockTransport::new(vec![response]));
+            (
+                Client::with_transport(config.clone(), transport.clone()).unwrap(),
+                transport,
+            )
+        };
+
+        // A server that ignores the status filter also lists finished deployments
+        let (client, transport) = client_for(MockResponse::json(
+            200,
+            r#"{"deployments": [
+                {"uuid": "11111111-1111-1111-1111-111111111111", "name": "Release 4", "status": "IN_PROGRESS"},
+                {"uuid": "22222222-2222-2222-2222-222222222222", "status": "SUCCEEDED"}
+            ]}"#,
+        ));
+        let err = check_no_active_deployment(&client).await.unwrap_err();
+        assert!(matches!(err, crate::error::CliError::Validation(_)));
+        assert!(
+            err.to_string()
+                .contains("11111111-1111-1111-1111-111111111111 ('Release 4', IN_PROGRESS)"),
+            "{}",
+            err
+        );
+        assert!(
+            err.to_string().contains("--force") && !err.to_string().contains("2222"),
+            "{}",
+            err
+        );
+        let request = &transport.requests()[0];
+        assert_eq!(request.method, "GET");
+        assert!(
+            request
+                .url
+                .ends_with("/deployment/v2/deployments?status=IN_PROGRESS"),
+            "{}",
+            request.url
+        );
+
+        // Statuses outside DeploymentStatus do not break the listing
+        let (client, _) = client_for(MockResponse::json(
+            200,
+            r#"[{"uuid": "22222222-2222-2222-2222-222222222222", "status": "COMPLETED_WITH_IMPORT_ERRORS"},
+                {"uuid": "33333333-3333-3333-3333-333333333333", "status": "SOMETHING_NEW"}]"#,
+        ));
+        assert!(client
+            .active_deployments()
+            .await
+            .unwrap()
+            .unwrap()
+            .is_empty());
+        check_no_active_deployment(&client).await.unwrap();
+
+        // A listing that cannot be read only warns
+        let (client, _) = client_for(MockResponse::json(200, r#"{"items": "unexpected"}"#));
+        assert!(client.active_deployments().await.is_err());
+        check_no_active_deployment(&client).await.unwrap();
+        let (client, _) = client_for(MockResponse::json(403, r#"{"error": "Forbidden"}"#));
+        assert!(matches!(
+            client.active_deployments().await,
+            Err(crate::error::CliError::Api { status: 403, .. })
+        ));
+        check_no_active_deployment(&client).await.unwrap();
+
+        // Sites without a deployment listing are not checked
+        let (client, _) = client_for(MockResponse::json(
+            405,
+            r#"{"error": "Method Not Allowed"}"#,
+        ));
+        check_no_active_deployment(&client).await.unwrap();
+    }
+
+    #[test]
+    fn test_retry_failed_checks_the_package() {
+        use crate::models::DeploymentResults;
+        use std::io::Write;
+
+        let results = |status: &str, errors: &str| -> DeploymentResults {
+            serde_json::from_str(&format!(
+                r#"{{"summary": {{
+                    "databaseScripts": 0,
+                    "adminConsoleSettings": {{"total": 0, "imported": 0, "failed": 0, "skipped": 0}},
+                    "plugins": {{"total": 0, "imported": 0, "skipped": 0}},
+                    "objects": {{"total": 3, "imported": 1, "failed": 2, "skipped": 0, "errors": [{}]}},
+                    "deploymentLogUrl": "https://example.com/log"
+                }}, "status": "{}"}}"#,
+                errors, status
+            ))
+            .unwrap()
+        };
+        let errors = r#"{"errorMessage": "Missing dependency", "objectName": "Guide", "objectUuid": "_a-0002"},
+                        {"errorMessage": "Precedent missing", "objectName": "Guide", "objectUuid": "_a-0002"},
+                        {"errorMessage": "Missing dependency", "objectName": "AS_rule", "objectUuid": "_a-0003"}"#;
+
+        let failed =
+            failed_objects("d-1", results("COMPLETED_WITH_IMPORT_ERRORS", errors)).unwrap();
+        assert_eq!(failed.len(), 3);
+        assert!(
+            failed_objects("d-1", results("COMPLETED_WITH_IMPORT_ERRORS", ""))
+                .unwrap()
+                .is_empty()
+        );
+        let err = failed_objects("d-1", results("COMPLETED", "")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("finished Completed; --retry-failed only applies"),
+            "{}",
+            err
+        );
+        let err = failed_objects("d-1", results("IN_PROGRESS", "")).unwrap_err();
+        assert!(err.to_string().contains("has not finished"), "{}", err);
+
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("app.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&package).unwrap());
+        for (name, contents) in [
+            ("META-INF/MANIFEST.MF", "Manifest-Version: 1.0\n"),
+            ("content/_a-0001.xml", "<rule/>"),
+            ("content/_a-0002.xml", "<document/>"),
+            ("content/_a-0002.pdf", "%PDF"),
+            ("content/_a-0003.v2.xml", "<rule/>"),
+        ] {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let uuids: HashSet<&str> = ["_a-0002", "_a-0003.v2"].into_iter().collect();
+        assert!(missing_objects(&package, &uuids).unwrap().is_empty());
+        let uuids: HashSet<&str> = ["_a-0003", "_a-0009", "MANIFEST"].into_iter().collect();
+        assert_eq!(
+            missing_objects(&package, &uuids).unwrap(),
+            ["MANIFEST", "_a-0003", "_a-0009"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_failed_refuses_database_scripts() {
+        let args = DeployArgs {
+            package_zip_name: Some(PathBuf::from("app.zip")),
+            database_script: vec!["schema.sql:1".to_string()],
+            ..Default::default()
+        };
+        let err = execute_retry_failed(
+            Config::default(),
+            "d-1".to_string(),
+            args,
+            false,
+            false,
+            false,
+            None,
+            false,
+            None,
         )
-        .await?;
-    
-    println!("{}", "Deployment initiated successfully".green());
-    println!("Deployment UUID: {}", response.uuid.to_string().cyan());
-    println!("Status URL: {}", response.url);
-    println!("Status: {}", response.status.yellow());
-    
-    match format.as_deref() {
-        Some("json") => {
-            let json_output = serde_json::to_string_pretty(&response)?;
-            println!("{}", json_output);
-        }
-        _ => {
-            println!("\n{}", "Deployment Details:".bold());
-            println!("  {}: {}", "Deployment UUID".dimmed(), response.uuid);
-            println!("  {}: {}", "Status".dimmed(), response.status);
-            println!("  {}: {}", "Results URL".dimmed(), response.url);
-            println!("\n{}", "Use 'status' or 'monitor' commands to track progress".dimmed());
-        }
-    }
-    
-    Ok(())
-}
\ No newline at end of file
+        .await
+        .unwrap_err();
+        assert!(matches!(err, crate::error::CliError::InvalidArgument(_)));
+        assert!(
+            err.to_string().contains("--rerun-database-scripts"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_package_checks_url_and_checksum() {
+        use crate::transport::{MockResponse, MockTransport};
+
+        assert!(check_package_url("http://repo.example.com/pkg.zip")
+            .unwrap_err()
+            .to_string()
+            .contains("https"));
+        assert!(check_package_url("not a url").is_err());
+        assert!(check_sha256("abc").is_err());
+
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let transport = std::sync::Arc::new(MockTransport::new(vec![
+            MockResponse::json(200, "PACKAGE"),
+            MockResponse::json(200, "PACKAGE"),
+        ]));
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        let link =
+            check_package_url("https://repo.example.com/builds/app.zip?token=secret").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let wrong = check_sha256(&"0".repeat(64)).unwrap();
+        let err = fetch_package(&client, &link, Some(&wrong), dir.path())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Checksum mismatch for https://repo.example.com/builds/app.zip:"));
+
+        // sha256 of "PACKAGE", given in upper case
+        let sha256 =
+            check_sha256("19B723BC37C15FB257166F057EB49C85ED9758ACD1EAE99186609E97482D9E70")
+                .unwrap();
+        let path = fetch_package(&client, &link, Some(&sha256), dir.path())
+            .await
+            .unwrap();
+        assert_eq!(path, dir.path().join("app.zip"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"PACKAGE");
+        // The artifact repository is another host, so no API key is sent
+        assert!(!transport.requests()[0]
+            .headers
+            .contains_key("appian-api-key"));
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    fn test_check_inspection() {
+        let results: InspectionResults = serde_json::from_value(serde_json::json!({
+            "status": "COMPLETED",
+            "summary": {
+                "adminConsoleSettingsExpected": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                "objectsExpected": {"total": 3, "imported": 0, "failed": 0, "skipped": 0},
+                "problems": {
+                    "totalErrors": 2,
+                    "totalWarnings": 1,
+                    "errors": [{"errorMessage": "Missing dependency", "objectName": "Rule A", "objectUuid": "a"}]
+                }
+            }
+        }))
+        .unwrap();
+
+        let err = check_inspection("i", &results, 0).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err
+            .to_string()
+            .contains("found 2 errors (at most 0 allowed)"));
+        assert!(err.to_string().contains("Rule A: Missing dependency"));
+        assert!(check_inspection("i", &results, 2).is_ok());
+
+        let failed = InspectionResults {
+            status: InspectionOperationStatus::Failed,
+            ..results
+        };
+        assert!(check_inspection("i", &failed, 10)
+            .unwrap_err()
+            .to_string()
+            .contains("failed"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let plugins = dir.path().join("plugins.zip");
+        std::fs::write(&plugins, b"PLUGINS").unwrap();
+        let args = DeployArgs {
+            plugins_file: Some(plugins),
+            inspect_first: Some(0),
+            ..Default::default()
+        };
+        assert!(plan(&args)
+            .unwrap_err()
+            .to_string()
+            .contains("--inspect-first"));
+    }
+
+    #[cfg(feature = "validate")]
+    #[tokio::test(start_paused = true)]
+    async fn test_inspect_first_uses_the_deploy_wait_options() {
+        use crate::transport::{MockResponse, MockTransport};
+
+        let in_progress = r#"{"status": "IN_PROGRESS", "summary": {
+            "adminConsoleSettingsExpected": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+            "objectsExpected": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+            "problems": {"totalErrors": 0, "totalWarnings": 0}
+        }}"#;
+        let transport = std::sync::Arc::new(MockTransport::new(vec![
+            MockResponse::json(
+                200,
+                r#"{"uuid": "11111111-1111-1111-1111-111111111111", "url": "https://example.com/i"}"#,
+            ),
+            MockResponse::json(200, in_progress),
+        ]));
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let client = Client::with_transport(config, transport.clone()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("app.zip");
+        std::fs::write(&package, b"PK\x05\x06").unwrap();
+        let args = DeployArgs {
+            package_zip_name: Some(package),
+            inspect_wait: WaitOptions {
+                interval: Some(std::time::Duration::from_secs(5)),
+                timeout: Some(std::time::Duration::from_secs(12)),
+            },
+            ..Default::default()
+        };
+
+        let err = inspect_first(&client, &args, 0).await.unwrap_err();
+        assert!(
+            matches!(err, crate::error::CliError::Timeout(_)),
+            "{:?}",
+            err
+        );
+        // The upload, then polls at 0s, 5s and 10s before the 12s timeout
+        assert_eq!(transport.requests().len(), 4);
+    }
+
+    #[test]
+    fn test_plan_reports_every_bad_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let args = DeployArgs {
+            package_zip_name: Some(write("pkg.zip", b"PACKAGE")),
+            customization_file: Some(write("import.properties", b"")),
+            plugins_file: Some(dir.path().join("missing.zip")),
+            database_scripts: Some(vec![write("seed.txt", b"insert"), dir.path().to_path_buf()]),
+            ..Default::default()
+        };
+
+        let err = plan(&args).unwrap_err();
+        assert!(matches!(err, crate::error::CliError::Validation(_)));
+        let message = err.to_string();
+        assert!(message.contains("3 input file problem(s)"));
+        assert!(message.contains("import.properties is empty"));
+        assert!(message.contains("missing.zip not found"));
+        assert!(message.contains("Database script 2"));
+        assert!(message.contains("is not a file"));
+
+        let args = DeployArgs {
+            package_zip_name: Some(write("pkg.zip", b"PACKAGE")),
+            database_scripts: Some(vec![write("seed.txt", b"insert")]),
+            data_source: Some("ds".to_string()),
+            ..Default::default()
+        };
+        let plan = plan(&args).unwrap();
+        assert_eq!(plan.files.len(), 2);
+        assert_eq!(plan.files[0].size, 7);
+        assert_eq!(plan.warnings.len(), 1);
+        assert_eq!(plan.warnings[0].code, "WRONG_EXTENSION");
+        assert!(plan.warnings[0]
+            .message
+            .contains("seed.txt does not have a .sql or .ddl extension"));
+    }
+
+    #[test]
+    fn test_plan_warns_about_unexpected_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"CONTENT").unwrap();
+            path
+        };
+        let mut args = DeployArgs {
+            package_zip_name: Some(write("pkg.zip")),
+            customization_file: Some(write("import.zip")),
+            admin_console_file: Some(write("settings.properties")),
+            plugins_file: Some(write("plugins.properties")),
+            database_scripts: Some(vec![write("seed.sql"), write("schema.DDL")]),
+            data_source: Some("ds".to_string()),
+            ..Default::default()
+        };
+
+        let warnings = plan(&args).unwrap().warnings;
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(warnings.len(), 3, "{:?}", messages);
+        assert!(warnings.iter().all(|w| w.code == "WRONG_EXTENSION"));
+        assert!(
+            messages[0].starts_with("Customization file")
+                && messages[0].ends_with("a .properties extension")
+        );
+        assert!(
+            messages[1].starts_with("Admin Console settings file")
+                && messages[1].ends_with("a .zip extension")
+        );
+        assert!(messages[2].starts_with("Plug-ins file"));
+
+        args.strict = true;
+        let err = plan(&args).unwrap_err();
+        assert!(matches!(err, crate::error::CliError::Validation(_)));
+        assert!(err
+            .to_string()
+            .contains("plugins.properties does not have a .zip extension (WRONG_EXTENSION)"));
+    }
+
+    #[test]
+    fn test_read_package_from_stream() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = read_package(&mut &b"PACKAGE"[..], "app.zip", dir.path()).unwrap();
+        assert_eq!(path, dir.path().join("app.zip"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"PACKAGE");
+
+        assert!(read_package(&mut &b""[..], "empty.zip", dir.path())
+            .unwrap_err()
+            .to_string()
+            .contains("No package data"));
+        for name in ["", "..", "../app.zip", "dist/app.zip"] {
+            assert!(
+                read_package(&mut &b"PACKAGE"[..], name, dir.path()).is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_database_scripts_order() {
+        let explicit = vec![
+            "b.sql:2".to_string(),
+            "a.sql:1".to_string(),
+            r"C:\db\c.sql:3".to_string(),
+        ];
+        let scripts = resolve_database_scripts(None, &explicit, true).unwrap();
+        assert_eq!(scripts[0], (PathBuf::from("a.sql"), 1));
+        assert_eq!(scripts[1], (PathBuf::from("b.sql"), 2));
+        assert_eq!(scripts[2], (PathBuf::from(r"C:\db\c.sql"), 3));
+
+        let sequential = Some(vec![PathBuf::from("x.sql"), PathBuf::from("y.sql")]);
+        let scripts = resolve_database_scripts(sequential, &[], true).unwrap();
+        assert_eq!(scripts[1], (PathBuf::from("y.sql"), 2));
+    }
+
+    #[test]
+    fn test_resolve_database_scripts_validation() {
+        let dup = vec!["a.sql:1".to_string(), "b.sql:1".to_string()];
+        assert!(resolve_database_scripts(None, &dup, false).is_err());
+
+        let gap = vec!["a.sql:1".to_string(), "b.sql:3".to_string()];
+        assert!(resolve_database_scripts(None, &gap, false).is_ok());
+        assert!(resolve_database_scripts(None, &gap, true).is_err());
+
+        let mixed = Some(vec![PathBuf::from("x.sql")]);
+        assert!(resolve_database_scripts(mixed, &["a.sql:1".to_string()], false).is_err());
+
+        assert!(parse_script_spec("a.sql").is_err());
+        assert!(parse_script_spec("a.sql:0").is_err());
+    }
+
+    #[cfg(feature = "download")]
+    #[test]
+    fn test_link_file_name() {
+        assert_eq!(
+            link_file_name("https://site/artifacts/My App.zip?token=1", "package.zip"),
+            "My_App.zip"
+        );
+        assert_eq!(
+            link_file_name("https://site/artifacts/", "package.zip"),
+            "package.zip"
+        );
+        assert_eq!(link_file_name("../..", "plugins.zip"), "plugins.zip");
+    }
+
+    #[test]
+    fn test_parse_labels() {
+        let labels =
+            parse_labels(&["pipeline=1234".to_string(), "git.sha=abc=def".to_string()]).unwrap();
+        assert_eq!(labels["pipeline"], "1234");
+        assert_eq!(labels["git.sha"], "abc=def");
+
+        assert!(parse_labels(&["pipeline".to_string()]).is_err());
+        assert!(parse_labels(&["=x".to_string()]).is_err());
+        assert!(parse_labels(&["-a=x".to_string()]).is_err());
+        assert!(parse_labels(&["a b=x".to_string()]).is_err());
+        assert!(parse_labels(&["a=1".to_string(), "a=2".to_string()]).is_err());
+    }
+}
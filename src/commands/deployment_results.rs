@@ -1,119 +1,173 @@
-use crate::{client::Client, Config, Result};
+use crate::models::{DeploymentResults, DeploymentStatus, ImportDeploymentStatus};
+use crate::polling::WaitOptions;
+use crate::{client::Client, output, polling, render, Config, Result};
 use colored::*;
 use std::time::Duration;
-use tokio::time::sleep;
 use tracing::info;
 
+/// Interval and limit for `--poll` and `--follow` without `--interval` or
+/// `--timeout`
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Fetch the results of an import or export deployment.
+pub async fn run(client: &Client, deployment_uuid: &str) -> Result<DeploymentResults> {
+    info!("Getting deployment results for: {}", deployment_uuid);
+
+    client.get_deployment_results(deployment_uuid).await
+}
+
+/// `max_entries` caps the failed objects listed in text output; `Some(0)`
+/// prints only the counts. JSON output is never capped. With `raw`, the
+/// results body is printed as received instead. `wait` sets the interval
+/// and timeout of `poll` and `follow`.
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     config: Config,
     deployment_uuid: String,
     format: Option<String>,
     poll: bool,
+    follow: bool,
+    wait: WaitOptions,
+    max_entries: Option<usize>,
+    raw: bool,
 ) -> Result<()> {
     let client = Client::new(config)?;
 
-    info!("Getting deployment results for: {}", deployment_uuid);
-
     if poll {
-        println!("{}", "Polling until terminal status...".bold().cyan());
-        let interval = Duration::from_secs(10);
-        let timeout = Duration::from_secs(600); // 10 minutes
-        let start = std::time::Instant::now();
+        output::status("Polling until terminal status...".bold().cyan());
+        let status = polling::poll_until(
+            &client,
+            &format!("Deployment {}", deployment_uuid),
+            wait.interval_or(POLL_INTERVAL),
+            wait.timeout_or(POLL_TIMEOUT),
+            || client.get_deployment_status(&deployment_uuid),
+            |status| {
+                status.status.is_terminal()
+                    || matches!(status.status, DeploymentStatus::PendingReview)
+            },
+            |_, status| {
+                if !status.status.is_terminal() {
+                    output::status(format!(
+                        "Status: {}{}",
+                        output::styled_status(&status.status),
+                        " (waiting)".dimmed()
+                    ));
+                }
+            },
+        )
+        .await?;
+        output::status(format!(
+            "{} {}",
+            "Terminal status:".bold(),
+            output::styled_status(&status.status)
+        ));
+    }
 
-        loop {
-            if start.elapsed() > timeout {
-                return Err(crate::error::CliError::Timeout(format!(
-                    "Deployment {} did not reach a terminal status within {} seconds",
-                    deployment_uuid,
-                    timeout.as_secs()
-                )));
-            }
+    if raw {
+        output::raw(
+            &client
+                .get_raw(&client.endpoints().results(&deployment_uuid))
+                .await?,
+        );
+        return Ok(());
+    }
 
-            let status = client.get_deployment_status(&deployment_uuid).await?;
-            if status.status.is_terminal() {
-                println!("{} {:?}", "Terminal status:".green().bold(), status.status);
-                break;
-            } else {
-                println!("Status: {:?}{}", status.status, " (waiting)".dimmed());
-            }
+    let results = if follow {
+        follow_results(&client, &deployment_uuid, wait).await?
+    } else {
+        run(&client, &deployment_uuid).await?
+    };
 
-            sleep(interval).await;
-        }
-    }
+    let summary_line = match &results {
+        DeploymentResults::Import(import) => (
+            output::status_name(&import.status),
+            import.summary.objects.failed as u64
+                + import.summary.admin_console_settings.failed as u64,
+            import.status.is_terminal() && !import.status.is_success(),
+        ),
+        DeploymentResults::Export(export) => (
+            output::status_name(&export.status),
+            0,
+            export.status.is_terminal() && !export.status.is_success(),
+        ),
+    };
 
-    let results = client.get_deployment_results(&deployment_uuid).await?;
+    render::renderer(format.as_deref()).render_deployment_results(
+        &deployment_uuid,
+        &results,
+        max_entries,
+    )?;
 
-    match format.as_deref() {
-        Some("json") => {
-            let json_output = serde_json::to_string_pretty(&results)?;
-            println!("{}", json_output);
-        }
-        _ => {
-            println!("{}", "Deployment Results:".bold().green());
-            match results {
-                crate::models::DeploymentResults::Import(import) => {
-                    println!("  {}: {:?}", "Status".dimmed(), import.status);
-                    println!("  {}: {}", "Deployment Log".dimmed(), import.summary.deployment_log_url);
-                    println!("  {}:", "Admin Console Settings".dimmed());
-                    println!(
-                        "    total={}, imported={}, failed={}, skipped={}",
-                        import.summary.admin_console_settings.total,
-                        import.summary.admin_console_settings.imported,
-                        import.summary.admin_console_settings.failed,
-                        import.summary.admin_console_settings.skipped
-                    );
-                    println!("  {}:", "Objects".dimmed());
-                    println!(
-                        "    total={}, imported={}, failed={}, skipped={}",
-                        import.summary.objects.total,
-                        import.summary.objects.imported,
-                        import.summary.objects.failed,
-                        import.summary.objects.skipped
-                    );
-                    println!("  {}:", "Plugins".dimmed());
-                    println!(
-                        "    total={}, imported={}, skipped={}",
-                        import.summary.plugins.total,
-                        import.summary.plugins.imported,
-                        import.summary.plugins.skipped
-                    );
-                    println!("  {}: {}", "Database Scripts".dimmed(), import.summary.database_scripts);
-                }
-                crate::models::DeploymentResults::Export(export) => {
-                    println!("  {}: {:?}", "Status".dimmed(), export.status);
-                    if let Some(url) = &export.deployment_log_url {
-                        println!("  {}: {}", "Deployment Log".dimmed(), url);
-                    }
-                    if let Some(pkg) = &export.package_zip {
-                        println!("  {}: {}", "Package Zip".dimmed(), pkg);
-                    }
-                    if let Some(plugins) = &export.plugins_zip {
-                        println!("  {}: {}", "Plugins Zip".dimmed(), plugins);
-                    }
-                    if let Some(cf) = &export.customization_file {
-                        println!("  {}: {}", "Customization File".dimmed(), cf);
-                    }
-                    if let Some(cft) = &export.customization_file_template {
-                        println!("  {}: {}", "Customization File Template".dimmed(), cft);
-                    }
-                    if !export.database_scripts.is_empty() {
-                        println!("  {}:", "Database Scripts".dimmed());
-                        for s in &export.database_scripts {
-                            println!(
-                                "    • {} (order {}): {}",
-                                s.file_name,
-                                s.order_id,
-                                s.url
-                            );
-                        }
-                    }
-                    if let Some(ds) = &export.data_source {
-                        println!("  {}: {}", "Data Source".dimmed(), ds);
-                    }
-                }
+    let (status, errors, failed) = summary_line;
+    output::result_line(&status, errors, 0, failed);
+    Ok(())
+}
+
+/// Re-fetch results until terminal, printing a progress line whenever the
+/// status or summary counts change. Returns the final results.
+async fn follow_results(
+    client: &Client,
+    deployment_uuid: &str,
+    wait: WaitOptions,
+) -> Result<DeploymentResults> {
+    output::status(
+        "Following deployment results until terminal status..."
+            .bold()
+            .cyan(),
+    );
+    let mut last_line = String::new();
+
+    let results = polling::poll_until(
+        client,
+        &format!("Deployment {}", deployment_uuid),
+        wait.interval_or(POLL_INTERVAL),
+        wait.timeout_or(POLL_TIMEOUT),
+        || run(client, deployment_uuid),
+        results_terminal,
+        |secs, results| {
+            let line = progress_line(results);
+            if line != last_line {
+                output::status(format!("{} {}", format!("[{:4}s]", secs).dimmed(), line));
+                last_line = line;
             }
+        },
+    )
+    .await?;
+    output::status("");
+    Ok(results)
+}
+
+fn progress_line(results: &DeploymentResults) -> String {
+    match results {
+        DeploymentResults::Import(import) => {
+            let objects = &import.summary.objects;
+            let admin = &import.summary.admin_console_settings;
+            format!(
+                "Status: {:?} | objects total={}, imported={}, failed={}, skipped={} | admin settings total={}, imported={}, failed={}, skipped={}",
+                import.status,
+                objects.total,
+                objects.imported,
+                objects.failed,
+                objects.skipped,
+                admin.total,
+                admin.imported,
+                admin.failed,
+                admin.skipped
+            )
         }
+        DeploymentResults::Export(export) => format!("Status: {:?}", export.status),
     }
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Whether `--follow` can stop: the results are final or waiting on a reviewer
+fn results_terminal(results: &DeploymentResults) -> bool {
+    match results {
+        // A deployment pending review will not move until someone approves it
+        DeploymentResults::Import(import) => {
+            import.status.is_terminal()
+                || matches!(import.status, ImportDeploymentStatus::PendingReview)
+        }
+        DeploymentResults::Export(export) => export.status.is_terminal(),
+    }
+}
@@ -1,27 +1,186 @@
-use crate::{client::Client, Config, Result};
+use crate::checksum::{parse_sha256, Sha256Writer};
+use crate::error::CliError;
+use crate::models::DeploymentResults;
+use crate::{client::Client, output, render, Config, Result};
 use colored::*;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
-pub async fn execute(
-    config: Config,
-    deployment_uuid: String,
-    output: Option<PathBuf>,
+/// Where a downloaded artifact was written and how large it was.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadResult {
+    pub deployment_uuid: String,
+    pub output_path: PathBuf,
+    pub size_bytes: usize,
+}
+
+/// Outcome of `--verify-only`: the artifact's size and digest, compared
+/// against the expected one.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyResult {
+    pub deployment_uuid: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    pub expected_sha256: String,
+    #[serde(rename = "match")]
+    pub matches: bool,
+}
+
+/// Tokens accepted by `--name-template`
+const TEMPLATE_TOKENS: [&str; 5] = ["uuid", "name", "date", "status", "ext"];
+
+/// Values substituted into a `--name-template`.
+#[derive(Debug, Clone)]
+pub struct NameTokens {
+    pub uuid: String,
+    pub name: String,
+    pub date: String,
+    pub status: String,
+    pub ext: String,
+}
+
+impl NameTokens {
+    /// Resolve the tokens for an export. `name`, `status` and `ext` come
+    /// from the export's results (the package zip's file name and the
+    /// export status), so they are only fetched when `template` uses them.
+    pub async fn resolve(client: &Client, deployment_uuid: &str, template: &str) -> Result<Self> {
+        let mut tokens = NameTokens {
+            uuid: deployment_uuid.to_string(),
+            name: deployment_uuid.to_string(),
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            status: String::new(),
+            ext: "zip".to_string(),
+        };
+
+        if ["{name}", "{status}", "{ext}"]
+            .iter()
+            .any(|token| template.contains(token))
+        {
+            let results = client.get_deployment_results(deployment_uuid).await?;
+            let (status, package_zip) = match &results {
+                DeploymentResults::Export(export) => (
+                    serde_json::to_value(export.status)?,
+                    export.package_zip.clone(),
+                ),
+                DeploymentResults::Import(import) => (serde_json::to_value(&import.status)?, None),
+            };
+            tokens.status = status.as_str().unwrap_or_default().to_string();
+
+            // The package zip is a URL or path; its last segment names the file
+            if let Some(file_name) = package_zip
+                .as_deref()
+                .and_then(|zip| zip.rsplit('/').next())
+            {
+                let file_name = file_name.split(['?', '#']).next().unwrap_or(file_name);
+                let path = Path::new(file_name);
+                if let Some(stem) = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .filter(|s| !s.is_empty())
+                {
+                    tokens.name = stem.to_string();
+                }
+                if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
+                    tokens.ext = ext.to_string();
+                }
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn get(&self, token: &str) -> Option<&str> {
+        match token {
+            "uuid" => Some(&self.uuid),
+            "name" => Some(&self.name),
+            "date" => Some(&self.date),
+            "status" => Some(&self.status),
+            "ext" => Some(&self.ext),
+            _ => None,
+        }
+    }
+}
+
+/// Expand `{token}`s in `template` and make the result safe to use as a
+/// file name: anything other than letters, digits, `.`, `-` and `_`
+/// becomes `_`, and leading dots are dropped.
+pub fn render_name_template(template: &str, tokens: &NameTokens) -> Result<String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find('}').ok_or_else(|| {
+            CliError::InvalidArgument(format!("Unclosed '{{' in --name-template '{}'", template))
+        })?;
+        let token = &after[..end];
+        let value = tokens.get(token).ok_or_else(|| {
+            CliError::InvalidArgument(format!(
+                "Unknown token '{{{}}}' in --name-template (expected one of: {})",
+                token,
+                TEMPLATE_TOKENS.map(|t| format!("{{{}}}", t)).join(", ")
+            ))
+        })?;
+        rendered.push_str(value);
+        rest = &after[end + 1..];
+    }
+    rendered.push_str(rest);
+
+    let sanitized: String = rendered
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_start_matches('.');
+    if sanitized.is_empty() {
+        return Err(CliError::InvalidArgument(format!(
+            "--name-template '{}' produced an empty file name",
+            template
+        )));
+    }
+    Ok(sanitized.to_string())
+}
+
+/// Download an exported artifact to `output` (defaults to `<uuid>.zip`).
+/// When `output` is a directory, or ends with a path separator, the file
+/// is placed inside it. With `name_template`, the file is named from the
+/// template instead and `output`, if given, is always a directory. Missing
+/// parent directories are created.
+pub async fn run(
+    client: &Client,
+    deployment_uuid: &str,
+    output: Option<&Path>,
+    name_template: Option<&str>,
     overwrite: bool,
-    format: Option<String>,
-) -> Result<()> {
-    let client = Client::new(config)?;
-    
+) -> Result<DownloadResult> {
     info!("Downloading package: {}", deployment_uuid);
-    
+
     // Determine output path
-    let output_path = if let Some(path) = output {
-        path
+    let output_path = if let Some(template) = name_template {
+        let tokens = NameTokens::resolve(client, deployment_uuid, template).await?;
+        let file_name = render_name_template(template, &tokens)?;
+        match output {
+            Some(path) if path.exists() && !path.is_dir() => {
+                return Err(CliError::InvalidArgument(format!(
+                    "--output must be a directory when --name-template is used: {}",
+                    path.display()
+                )))
+            }
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
     } else {
-        // Default to current directory with deployment UUID as filename
-        PathBuf::from(format!("{}.zip", deployment_uuid))
+        // Default to the deployment UUID as filename
+        resolve_output_path(output, &format!("{}.zip", deployment_uuid))
     };
 
     // Check if file exists and overwrite is false
@@ -32,36 +191,281 @@ pub async fn execute(
         )));
     }
 
-    println!("{}", format!("Downloading package {}...", deployment_uuid).cyan());
-    
     // Download the package
-    let package_data = client.download_artifact(&deployment_uuid).await?;
-    
+    let package_data = client.download_artifact(deployment_uuid).await?;
+
     // Write to file
-    let mut file = File::create(&output_path).map_err(|e| {
-        crate::error::CliError::FileSystem(format!("Failed to create file: {}", e))
-    })?;
-    
-    file.write_all(&package_data).map_err(|e| {
-        crate::error::CliError::FileSystem(format!("Failed to write file: {}", e))
-    })?;
-    
-    println!("{}", format!("✓ Package downloaded to: {}", output_path.display()).green());
-    
+    let mut file = create_output_file(&output_path, overwrite)?;
+
+    file.write_all(&package_data)
+        .map_err(|e| crate::error::CliError::FileSystem(format!("Failed to write file: {}", e)))?;
+
+    Ok(DownloadResult {
+        deployment_uuid: deployment_uuid.to_string(),
+        output_path,
+        size_bytes: package_data.len(),
+    })
+}
+
+/// Stream the artifact of `deployment_uuid` and compare its SHA-256 with
+/// `expected_sha256` (lowercase hex) without writing anything to disk. A
+/// mismatch is reported in the result, not as an error.
+pub async fn verify(
+    client: &Client,
+    deployment_uuid: &str,
+    expected_sha256: &str,
+) -> Result<VerifyResult> {
+    info!("Verifying package: {}", deployment_uuid);
+
+    let mut out = Sha256Writer::new(std::io::sink());
+    let size_bytes = client
+        .download_artifact_to(deployment_uuid, &mut out)
+        .await?;
+    let (_, sha256) = out.finish();
+
+    Ok(VerifyResult {
+        deployment_uuid: deployment_uuid.to_string(),
+        size_bytes,
+        matches: sha256 == expected_sha256,
+        sha256,
+        expected_sha256: expected_sha256.to_string(),
+    })
+}
+
+/// Where to write a download named `file_name` for `--output`: inside it
+/// when it is a directory or ends with a path separator, otherwise the
+/// path itself.
+fn resolve_output_path(output: Option<&Path>, file_name: &str) -> PathBuf {
+    match output {
+        Some(path)
+            if path.is_dir() || path.as_os_str().to_string_lossy().ends_with(['/', '\\']) =>
+        {
+            path.join(file_name)
+        }
+        Some(path) => path.to_path_buf(),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Create `path` and any missing parent directories. Concurrent downloads
+/// may create the same directories; `create_dir_all` treats a directory
+/// created by someone else in the meantime as success. Without
+/// `overwrite`, the file itself is created exclusively so two downloads
+/// cannot both claim the same name.
+fn create_output_file(path: &Path, overwrite: bool) -> Result<File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            CliError::FileSystem(format!(
+                "Failed to create directory {}: {}",
+                parent.display(),
+                e
+            ))
+        })?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(path).map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => CliError::FileSystem(format!(
+            "File already exists: {}. Use --overwrite to replace.",
+            path.display()
+        )),
+        _ => CliError::FileSystem(format!("Failed to create file: {}", e)),
+    })
+}
+
+pub async fn execute(
+    config: Config,
+    deployment_uuid: String,
+    output: Option<PathBuf>,
+    name_template: Option<String>,
+    overwrite: bool,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "download-package")?;
+    // Without --output, downloads go to the configured download directory
+    let output =
+        output.or_else(|| Some(config.download.dir.clone()).filter(|dir| dir != Path::new(".")));
+    let client = Client::new(config)?;
+
+    output::status(format!("Downloading package {}...", deployment_uuid).cyan());
+
+    let result = run(
+        &client,
+        &deployment_uuid,
+        output.as_deref(),
+        name_template.as_deref(),
+        overwrite,
+    )
+    .await?;
+
+    output::status(format!("✓ Package downloaded to: {}", result.output_path.display()).green());
+
     match format.as_deref() {
         Some("json") => {
             let json_output = serde_json::json!({
-                "deployment_uuid": deployment_uuid,
-                "output_path": output_path.to_string_lossy(),
-                "size_bytes": package_data.len(),
+                "deployment_uuid": result.deployment_uuid,
+                "output_path": result.output_path.to_string_lossy(),
+                "size_bytes": result.size_bytes,
                 "success": true
             });
-            println!("{}", serde_json::to_string_pretty(&json_output)?);
+            output::json(&json_output)?;
         }
         _ => {
-            println!("Package size: {} bytes", package_data.len().to_string().cyan());
+            println!("Output: {}", result.output_path.display());
+            println!(
+                "Package size: {} bytes",
+                result.size_bytes.to_string().cyan()
+            );
         }
     }
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `download-package --verify-only`: check the artifact against
+/// `expected_sha256` and fail with a validation error on a mismatch.
+pub async fn execute_verify(
+    config: Config,
+    deployment_uuid: String,
+    expected_sha256: String,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "download-package --verify")?;
+    let expected_sha256 = parse_sha256(&expected_sha256, "--expected-sha256")?;
+    let client = Client::new(config)?;
+
+    output::status(format!("Verifying package {}...", deployment_uuid).cyan());
+    let result = verify(&client, &deployment_uuid, &expected_sha256).await?;
+
+    match format.as_deref() {
+        Some("json") => output::json(&result)?,
+        _ => {
+            println!(
+                "Package size: {} bytes",
+                result.size_bytes.to_string().cyan()
+            );
+            println!("SHA-256: {}", result.sha256);
+        }
+    }
+
+    if !result.matches {
+        return Err(CliError::Validation(format!(
+            "Checksum mismatch for package {}: expected sha256 {}, got {}",
+            deployment_uuid, result.expected_sha256, result.sha256
+        )));
+    }
+    output::status(
+        format!(
+            "✓ Package {} matches the expected checksum; nothing was written",
+            deployment_uuid
+        )
+        .green(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_name_template() {
+        let tokens = NameTokens {
+            uuid: "1234".to_string(),
+            name: "My App/Export".to_string(),
+            date: "2024-01-31".to_string(),
+            status: "COMPLETED".to_string(),
+            ext: "zip".to_string(),
+        };
+
+        assert_eq!(
+            render_name_template("{name}-{date}-{uuid}.{ext}", &tokens).unwrap(),
+            "My_App_Export-2024-01-31-1234.zip"
+        );
+        assert_eq!(
+            render_name_template("../{status}", &tokens).unwrap(),
+            "_COMPLETED"
+        );
+
+        let err = render_name_template("{version}.zip", &tokens).unwrap_err();
+        assert!(err.to_string().contains("Unknown token '{version}'"));
+        assert!(render_name_template("{name", &tokens).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_compares_the_digest() {
+        use crate::transport::{MockResponse, MockTransport};
+
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let transport =
+            std::sync::Arc::new(MockTransport::new(vec![MockResponse::json(200, "PACKAGE")]));
+        let client = Client::with_transport(config, transport).unwrap();
+
+        // sha256 of "PACKAGE"
+        let expected = "19b723bc37c15fb257166f057eb49c85ed9758acd1eae99186609e97482d9e70";
+        let result = verify(&client, "1234", expected).await.unwrap();
+        assert!(result.matches);
+        assert_eq!(result.size_bytes, 7);
+
+        let result = verify(&client, "1234", &"0".repeat(64)).await.unwrap();
+        assert!(!result.matches);
+        assert_eq!(result.sha256, expected);
+        assert_eq!(serde_json::to_value(&result).unwrap()["match"], false);
+    }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            resolve_output_path(Some(dir.path()), "1234.zip"),
+            dir.path().join("1234.zip")
+        );
+        assert_eq!(
+            resolve_output_path(Some(Path::new("new/dir/")), "1234.zip"),
+            Path::new("new/dir/1234.zip")
+        );
+        assert_eq!(
+            resolve_output_path(Some(Path::new("new/pkg.zip")), "1234.zip"),
+            Path::new("new/pkg.zip")
+        );
+        assert_eq!(resolve_output_path(None, "1234.zip"), Path::new("1234.zip"));
+    }
+
+    #[test]
+    fn test_create_output_file_creates_nested_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("some/nested/dir/pkg.zip");
+        create_output_file(&path, false).unwrap();
+        assert!(path.is_file());
+
+        let err = create_output_file(&path, false).unwrap_err();
+        assert!(err.to_string().contains("File already exists"));
+        create_output_file(&path, true).unwrap();
+    }
+
+    #[test]
+    fn test_create_output_file_concurrent_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared = dir.path().join("a/b/c");
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let path = shared.join(format!("{}.zip", i));
+                std::thread::spawn(move || create_output_file(&path, false).map(|_| ()))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        assert_eq!(std::fs::read_dir(&shared).unwrap().count(), 8);
+    }
+}
@@ -1,17 +1,21 @@
-use crate::{client::Client, Config, Result};
+use crate::error::CliError;
+use crate::models::{ExportDeploymentResults, ExportRequest, ExportResponse, StatusReport};
+use crate::{client::Client, output, render, Config, Result};
 use colored::*;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tracing::info;
 
-
-pub async fn execute(
-    config: Config,
-    uuids: Vec<String>,
-    export_type: String,
+/// Validate export arguments and build the API request.
+pub fn build_request(
+    uuids: &[String],
+    export_type: &str,
     name: Option<String>,
     description: Option<String>,
-    dry_run: bool,
-    format: Option<String>,
-) -> Result<()> {
+) -> Result<ExportRequest> {
     if uuids.is_empty() {
         return Err(crate::error::CliError::InvalidArgument(
             "At least one --uuid must be provided".to_string()
@@ -31,21 +35,6 @@ pub async fn execute(
         ));
     }
 
-    if dry_run {
-        info!("Dry run mode - validating export parameters");
-        println!("{}", "Dry run validation successful".green());
-        println!("Export type: {}", export_type);
-        println!("UUIDs: {:?}", uuids);
-        println!("Name: {:?}", name);
-        println!("Description: {:?}", description);
-        return Ok(());
-    }
-
-    let client = Client::new(config)?;
-    
-    info!("Starting export operation");
-    println!("{}", "Starting export...".cyan());
-    
     // Parse UUIDs
     let parsed_uuids: Vec<uuid::Uuid> = uuids
         .iter()
@@ -54,32 +43,514 @@ pub async fn execute(
         )
         .collect::<std::result::Result<Vec<_>, crate::error::CliError>>()?;
 
-    let request = crate::models::ExportRequest {
+    Ok(ExportRequest {
         uuids: parsed_uuids,
-        export_type: export_type.clone(),
+        export_type,
         name,
         description,
-    };
-
-    let response = client.export_multipart(&request).await?;
-    
-    println!("{}", "Export initiated successfully".green());
-    println!("Export UUID: {}", response.uuid.to_string().cyan());
-    println!("Status: {}", format!("{:?}", response.status).yellow());
-    println!("Details URL: {}", response.url);
-    
+    })
+}
+
+/// Initiate an export.
+pub async fn run(client: &Client, request: &ExportRequest) -> Result<ExportResponse> {
+    info!("Starting export operation");
+
+    client.export_multipart(request).await
+}
+
+/// Cancel a running export and return its status afterwards. An export that
+/// already finished is left alone and reported as an invalid argument.
+pub async fn cancel(client: &Client, export_uuid: &str) -> Result<ExportDeploymentResults> {
+    let current = client.get_export_status(export_uuid).await?;
+    if current.status.is_terminal() {
+        return Err(CliError::InvalidArgument(format!(
+            "Export {} already finished with status {:?}; there is nothing to cancel",
+            export_uuid, current.status
+        )));
+    }
+
+    client.cancel_export(export_uuid).await?;
+    client.get_export_status(export_uuid).await
+}
+
+/// Build one single-package export request per UUID for a bulk export.
+pub fn build_package_requests(
+    uuids: &[String],
+    name: Option<String>,
+    description: Option<String>,
+) -> Result<Vec<ExportRequest>> {
+    uuids
+        .iter()
+        .map(|uuid| {
+            build_request(
+                std::slice::from_ref(uuid),
+                "package",
+                name.clone(),
+                description.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Outcome of one package in a bulk export. `result` is `None` when the
+/// export was never started because an earlier one failed.
+pub struct BulkExportOutcome {
+    pub package_uuid: uuid::Uuid,
+    pub result: Option<Result<ExportResponse>>,
+}
+
+/// Start one export per request, at most `concurrency` at a time. Unless
+/// `continue_on_error` is set, exports not yet started are skipped after
+/// the first failure. Outcomes are returned in request order.
+pub async fn run_bulk(
+    client: Arc<Client>,
+    requests: Vec<ExportRequest>,
+    concurrency: usize,
+    continue_on_error: bool,
+) -> Result<Vec<BulkExportOutcome>> {
+    info!("Starting bulk export of {} packages", requests.len());
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let failed = Arc::new(AtomicBool::new(false));
+    let mut tasks = JoinSet::new();
+
+    for (index, request) in requests.into_iter().enumerate() {
+        let (client, semaphore, failed) = (client.clone(), semaphore.clone(), failed.clone());
+        tasks.spawn(async move {
+            let package_uuid = request.uuids[0];
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("export semaphore is never closed");
+            if failed.load(Ordering::SeqCst) && !continue_on_error {
+                return (
+                    index,
+                    BulkExportOutcome {
+                        package_uuid,
+                        result: None,
+                    },
+                );
+            }
+
+            let result = run(&client, &request).await;
+            if result.is_err() {
+                failed.store(true, Ordering::SeqCst);
+            }
+            (
+                index,
+                BulkExportOutcome {
+                    package_uuid,
+                    result: Some(result),
+                },
+            )
+        });
+    }
+
+    let mut outcomes = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        outcomes.push(joined.map_err(|e| CliError::Unknown(format!("Export task failed: {}", e)))?);
+    }
+    outcomes.sort_by_key(|(index, _)| *index);
+    Ok(outcomes.into_iter().map(|(_, outcome)| outcome).collect())
+}
+
+/// One row of the bulk export summary, as printed in JSON output
+#[derive(Debug, Serialize)]
+struct BulkExportRow {
+    package_uuid: String,
+    export_uuid: Option<String>,
+    status: String,
+    error: Option<String>,
+}
+
+impl From<&BulkExportOutcome> for BulkExportRow {
+    fn from(outcome: &BulkExportOutcome) -> Self {
+        let (export_uuid, status, error) = match &outcome.result {
+            Some(Ok(response)) => (
+                Some(response.uuid.to_string()),
+                format!("{:?}", response.status),
+                None,
+            ),
+            Some(Err(e)) => (None, "ERROR".to_string(), Some(e.to_string())),
+            None => (None, "SKIPPED".to_string(), None),
+        };
+        BulkExportRow {
+            package_uuid: outcome.package_uuid.to_string(),
+            export_uuid,
+            status,
+            error,
+        }
+    }
+}
+
+/// Print an estimate of the export's size for `--dry-run --estimate`,
+/// derived from the metadata of the packages it would include.
+#[cfg(feature = "get_packages")]
+async fn print_estimate(
+    config: Config,
+    export_type: &str,
+    uuids: &[uuid::Uuid],
+    format: Option<&str>,
+) -> Result<()> {
+    let client = Client::new(config)?;
+    let estimate = client.estimate_export(export_type, uuids).await?;
+
+    if format == Some("json") {
+        return output::json(&estimate);
+    }
+
+    println!("\n{}", "Export Estimate:".bold());
+    println!("  {}: {}", "Packages".dimmed(), estimate.packages.len());
+    for package in &estimate.packages {
+        let objects = package
+            .object_count
+            .map_or("?".to_string(), |n| n.to_string());
+        let size = package
+            .size_bytes
+            .map_or("?".to_string(), output::format_bytes);
+        println!(
+            "    {} {} ({} objects, {})",
+            "•".cyan(),
+            package.name,
+            objects,
+            size
+        );
+    }
+    let unknown = || "unknown".yellow().to_string();
+    println!(
+        "  {}: {}",
+        "Objects".dimmed(),
+        estimate
+            .object_count
+            .map_or_else(unknown, |n| n.to_string())
+    );
+    println!(
+        "  {}: {}",
+        "Estimated size".dimmed(),
+        estimate
+            .size_bytes
+            .map_or_else(unknown, output::format_bytes)
+    );
+    for uuid in &estimate.unmatched {
+        output::warning(format!("No package found for {}", uuid));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "get_packages"))]
+async fn print_estimate(
+    _config: Config,
+    _export_type: &str,
+    _uuids: &[uuid::Uuid],
+    _format: Option<&str>,
+) -> Result<()> {
+    Err(CliError::InvalidArgument(
+        "--estimate requires a build with the get_packages feature".to_string(),
+    ))
+}
+
+/// Print the outcome of a bulk package export built with
+/// [`build_package_requests`]. Fails with the first export error, after
+/// every outcome has been reported.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_bulk(
+    config: Config,
+    requests: Vec<ExportRequest>,
+    dry_run: bool,
+    estimate: bool,
+    print_request: bool,
+    concurrency: usize,
+    continue_on_error: bool,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "export")?;
+    if print_request {
+        let client = Client::new(config)?;
+        let previews = requests
+            .iter()
+            .map(|request| client.prepare_export(request)?.preview())
+            .collect::<Result<Vec<_>>>()?;
+        if format.as_deref() == Some("json") {
+            return output::json(&previews);
+        }
+        for preview in &previews {
+            output::request_preview(preview, None)?;
+            println!();
+        }
+        return Ok(());
+    }
+
+    if dry_run {
+        output::status("Dry run validation successful".green());
+        output::status(format!(
+            "{} package exports, {} at a time",
+            requests.len(),
+            concurrency.max(1)
+        ));
+        for request in &requests {
+            output::status(format!("  {}", request.uuids[0]));
+        }
+        if estimate {
+            let uuids: Vec<uuid::Uuid> = requests.iter().map(|r| r.uuids[0]).collect();
+            return print_estimate(config, "package", &uuids, format.as_deref()).await;
+        }
+        return Ok(());
+    }
+
+    let client = Arc::new(Client::new(config)?);
+    output::status(format!("Starting {} package exports...", requests.len()).cyan());
+
+    let mut outcomes = run_bulk(client, requests, concurrency, continue_on_error).await?;
+    let rows: Vec<BulkExportRow> = outcomes.iter().map(BulkExportRow::from).collect();
+
+    if output::is_quiet() {
+        for row in &rows {
+            if let Some(export_uuid) = &row.export_uuid {
+                println!("{}", export_uuid);
+            }
+        }
+    } else if format.as_deref() == Some("json") {
+        let failed = rows.iter().filter(|row| row.export_uuid.is_none()).count();
+        output::json(&serde_json::json!({
+            "exports": rows,
+            "succeeded": rows.len() - failed,
+            "failed": failed,
+        }))?;
+    } else {
+        println!("\n{}", "Package Exports:".bold());
+        println!(
+            "  {:<36}  {:<36}  {}",
+            "Package UUID".dimmed(),
+            "Export UUID".dimmed(),
+            "Status".dimmed()
+        );
+        for row in &rows {
+            let status = match row.status.as_str() {
+                "ERROR" => row.status.red(),
+                "SKIPPED" => row.status.dimmed(),
+                _ => row.status.green(),
+            };
+            println!(
+                "  {:<36}  {:<36}  {}",
+                row.package_uuid,
+                row.export_uuid.as_deref().unwrap_or("-"),
+                status
+            );
+        }
+        for row in &rows {
+            if let Some(error) = &row.error {
+                println!("  {} {}: {}", "✗".red(), row.package_uuid, error);
+            }
+        }
+    }
+
+    match outcomes
+        .iter_mut()
+        .find_map(|outcome| match outcome.result.take() {
+            Some(Err(e)) => Some(e),
+            _ => None,
+        }) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Print the outcome of an export built with [`build_request`].
+pub async fn execute(
+    config: Config,
+    request: ExportRequest,
+    dry_run: bool,
+    estimate: bool,
+    print_request: bool,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "export")?;
+    if print_request {
+        let client = Client::new(config)?;
+        let preview = client.prepare_export(&request)?.preview()?;
+        return output::request_preview(&preview, format.as_deref());
+    }
+
+    if dry_run {
+        info!("Dry run mode - validating export parameters");
+        output::status("Dry run validation successful".green());
+        output::status(format!("Export type: {}", request.export_type));
+        let uuids: Vec<String> = request.uuids.iter().map(|u| u.to_string()).collect();
+        output::status(format!("UUIDs: {:?}", uuids));
+        output::status(format!("Name: {:?}", request.name));
+        output::status(format!("Description: {:?}", request.description));
+        if estimate {
+            return print_estimate(
+                config,
+                &request.export_type,
+                &request.uuids,
+                format.as_deref(),
+            )
+            .await;
+        }
+        return Ok(());
+    }
+
+    let client = Client::new(config)?;
+
+    output::status("Starting export...".cyan());
+
+    let response = run(&client, &request).await?;
+
+    output::status("Export initiated successfully".green());
+    output::identifier("Export UUID", response.uuid);
+    output::status(format!(
+        "Status: {}",
+        output::styled_status(&response.status)
+    ));
+    output::status(format!("Details URL: {}", response.url));
+
+    if output::is_quiet() {
+        return Ok(());
+    }
+
     match format.as_deref() {
         Some("json") => {
-            let json_output = serde_json::to_string_pretty(&response)?;
-            println!("{}", json_output);
+            output::json(&response)?;
         }
         _ => {
             println!("\n{}", "Export Details:".bold());
             println!("  {}: {}", "Export UUID".dimmed(), response.uuid);
-            println!("  {}: {:?}", "Status".dimmed(), response.status);
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&response.status)
+            );
             println!("  {}: {}", "Details URL".dimmed(), response.url);
         }
     }
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `export cancel`: cancel the export and print its resulting status.
+pub async fn execute_cancel(
+    config: Config,
+    export_uuid: String,
+    format: Option<String>,
+) -> Result<()> {
+    let client = Client::new(config)?;
+
+    output::status(format!("Cancelling export {}...", export_uuid).cyan());
+    let status = cancel(&client, &export_uuid).await?;
+    output::status("Cancel requested".green());
+
+    render::renderer(format.as_deref()).render_status(&export_uuid, &StatusReport::Export(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MockResponse, MockTransport};
+
+    fn export_response(uuid: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"uuid": "{}", "url": "https://example.com/exports/1", "status": "IN_PROGRESS"}}"#,
+                uuid
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_bulk_export_fans_out_and_stops_on_error() {
+        let packages = vec![
+            "11111111-1111-1111-1111-111111111111".to_string(),
+            "22222222-2222-2222-2222-222222222222".to_string(),
+            "33333333-3333-3333-3333-333333333333".to_string(),
+        ];
+        let requests = build_package_requests(&packages, None, None).unwrap();
+        assert!(requests
+            .iter()
+            .all(|r| r.uuids.len() == 1 && r.export_type == "package"));
+
+        let responses = || {
+            vec![
+                export_response("aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa"),
+                MockResponse::json(500, "boom"),
+                export_response("cccccccc-cccc-cccc-cccc-cccccccccccc"),
+            ]
+        };
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+
+        let transport = Arc::new(MockTransport::new(responses()));
+        let client = Arc::new(Client::with_transport(config.clone(), transport).unwrap());
+        let outcomes = run_bulk(client, requests.clone(), 1, true).await.unwrap();
+        assert!(matches!(outcomes[0].result, Some(Ok(_))));
+        assert!(matches!(outcomes[1].result, Some(Err(_))));
+        assert_eq!(
+            outcomes[2]
+                .result
+                .as_ref()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .uuid
+                .to_string(),
+            "cccccccc-cccc-cccc-cccc-cccccccccccc"
+        );
+
+        let transport = Arc::new(MockTransport::new(responses()));
+        let client = Arc::new(Client::with_transport(config, transport.clone()).unwrap());
+        let outcomes = run_bulk(client, requests, 1, false).await.unwrap();
+        assert!(outcomes[2].result.is_none());
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_checks_status_first() {
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let status =
+            |status: &str| MockResponse::json(200, &format!(r#"{{"status": "{}"}}"#, status));
+
+        let transport = Arc::new(MockTransport::new(vec![
+            status("IN_PROGRESS"),
+            MockResponse::json(200, "{}"),
+            status("CANCELED"),
+        ]));
+        let client = Client::with_transport(config.clone(), transport.clone()).unwrap();
+        let canceled = cancel(&client, "e1").await.unwrap();
+        assert!(matches!(
+            canceled.status,
+            crate::models::ExportStatus::Canceled
+        ));
+        let requests = transport.requests();
+        assert_eq!(requests[1].method, reqwest::Method::POST);
+        assert!(requests[1]
+            .url
+            .ends_with("/suite/deployment-management/v2/deployments/e1/cancel"));
+
+        // A finished export is never sent a cancel
+        let transport = Arc::new(MockTransport::new(vec![status("COMPLETED")]));
+        let client = Client::with_transport(config.clone(), transport.clone()).unwrap();
+        assert_eq!(cancel(&client, "e1").await.unwrap_err().exit_code(), 2);
+        assert_eq!(transport.requests().len(), 1);
+
+        let transport = Arc::new(MockTransport::new(vec![
+            status("IN_PROGRESS"),
+            MockResponse::json(405, "{}"),
+        ]));
+        let client = Client::with_transport(config, transport).unwrap();
+        let err = cancel(&client, "e1").await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not support cancelling exports"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use crate::commands::download_package::{self, DownloadResult};
+use crate::commands::{export, monitor};
+use crate::duration::format_elapsed;
+use crate::error::{CliError, Stage};
+use crate::models::{ExportDeploymentResults, ExportRequest, ExportResponse, StatusReport};
+use crate::{client::Client, output, Config, Result};
+use colored::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Where and how to save the package once the export has finished.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    pub output: Option<PathBuf>,
+    pub name_template: Option<String>,
+    pub overwrite: bool,
+}
+
+/// Outcome of every stage of a successful `export-and-download`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineResult {
+    /// The export as it was started
+    pub export: ExportResponse,
+    /// The export's final results
+    pub completed: ExportDeploymentResults,
+    pub wait_seconds: u64,
+    pub download: DownloadResult,
+}
+
+/// Start an export, poll it until it finishes and download its package.
+///
+/// Errors are wrapped in the [`Stage`] that failed. An export that completed
+/// with errors fails the wait stage unless `allow_export_errors` is set, in
+/// which case its package is downloaded anyway. `on_poll` sees every status
+/// with the elapsed seconds.
+pub async fn run<F>(
+    client: &Client,
+    request: &ExportRequest,
+    download: &DownloadOptions,
+    interval: Duration,
+    timeout: Duration,
+    allow_export_errors: bool,
+    on_poll: F,
+) -> Result<PipelineResult>
+where
+    F: FnMut(u64, &StatusReport),
+{
+    let started = export::run(client, request)
+        .await
+        .map_err(|e| Stage::Export.wrap(e))?;
+    let export_uuid = started.uuid.to_string();
+    info!("Export {} started; waiting for it to finish", export_uuid);
+
+    let mut wait_seconds = 0;
+    let mut on_poll = on_poll;
+    let report = monitor::run(
+        client,
+        &export_uuid,
+        Some("export"),
+        interval,
+        timeout,
+        |secs, report| {
+            wait_seconds = secs;
+            on_poll(secs, report)
+        },
+    )
+    .await
+    .map_err(|e| Stage::Wait.wrap(e))?;
+
+    let completed = match report {
+        StatusReport::Export(export) => export,
+        StatusReport::Deployment(_) => unreachable!("export polling returns export statuses"),
+    };
+    match monitor::check_outcome(
+        &export_uuid,
+        &StatusReport::Export(completed.clone()),
+        false,
+    ) {
+        Err(CliError::CompletedWithErrors(message)) if allow_export_errors => {
+            output::warning(format!(
+                "{}; downloading it anyway (--allow-export-errors)",
+                message
+            ));
+        }
+        outcome => outcome.map_err(|e| Stage::Wait.wrap(e))?,
+    }
+
+    let download = download_package::run(
+        client,
+        &export_uuid,
+        download.output.as_deref(),
+        download.name_template.as_deref(),
+        download.overwrite,
+    )
+    .await
+    .map_err(|e| Stage::Download.wrap(e))?;
+
+    Ok(PipelineResult {
+        export: started,
+        completed,
+        wait_seconds,
+        download,
+    })
+}
+
+pub async fn execute(
+    config: Config,
+    request: ExportRequest,
+    mut download: DownloadOptions,
+    interval: Duration,
+    timeout: Duration,
+    allow_export_errors: bool,
+    format: Option<String>,
+) -> Result<()> {
+    // Without --output, downloads go to the configured download directory
+    download.output = download
+        .output
+        .or_else(|| Some(config.download.dir.clone()).filter(|dir| dir != Path::new(".")));
+    let client = Client::new(config)?;
+
+    output::status("[1/3] Starting export...".cyan());
+    let mut waiting = false;
+    let result = run(
+        &client,
+        &request,
+        &download,
+        interval,
+        timeout,
+        allow_export_errors,
+        |secs, report| {
+            // The first poll follows a successful start
+            if !std::mem::replace(&mut waiting, true) {
+                output::status(
+                    format!(
+                        "[2/3] Waiting for export (interval {}, timeout {})...",
+                        format_elapsed(interval.as_secs()),
+                        format_elapsed(timeout.as_secs())
+                    )
+                    .cyan(),
+                );
+            }
+            output::progress(format!("[{:4}s] Status: {}", secs, report.status_label()).dimmed());
+        },
+    )
+    .await?;
+
+    output::status("");
+    output::status(format!(
+        "Export UUID: {}",
+        result.export.uuid.to_string().cyan()
+    ));
+    output::status(
+        format!(
+            "[3/3] ✓ Package downloaded to: {}",
+            result.download.output_path.display()
+        )
+        .green(),
+    );
+
+    if output::is_quiet() {
+        println!("{}", result.download.output_path.display());
+        return Ok(());
+    }
+
+    match format.as_deref() {
+        Some("json") => {
+            output::json(&serde_json::json!({
+                "export": {
+                    "uuid": result.export.uuid,
+                    "url": result.export.url,
+                },
+                "wait": {
+                    "status": result.completed.status,
+                    "elapsed_seconds": result.wait_seconds,
+                },
+                "download": {
+                    "output_path": result.download.output_path.to_string_lossy(),
+                    "size_bytes": result.download.size_bytes,
+                },
+                "results": result.completed,
+                "success": true,
+            }))?;
+        }
+        _ => {
+            println!("\n{}", "Export and Download:".bold());
+            println!("  {}: {}", "Export UUID".dimmed(), result.export.uuid);
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&result.completed.status)
+            );
+            println!("  {}: {}s", "Waited".dimmed(), result.wait_seconds);
+            println!(
+                "  {}: {}",
+                "Output".dimmed(),
+                result.download.output_path.display()
+            );
+            println!(
+                "  {}: {} bytes",
+                "Package size".dimmed(),
+                result.download.size_bytes
+            );
+            output::export_details(&result.completed);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MockResponse, MockTransport};
+    use std::sync::Arc;
+
+    const EXPORT_UUID: &str = "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa";
+
+    fn export_status(status: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            &format!(
+                r#"{{"uuid": "{}", "url": "https://example.com/deployments/1", "status": "{}"}}"#,
+                EXPORT_UUID, status
+            ),
+        )
+    }
+
+    async fn pipeline(
+        responses: Vec<MockResponse>,
+        dir: &Path,
+        allow_export_errors: bool,
+    ) -> Result<PipelineResult> {
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let client =
+            Client::with_transport(config, Arc::new(MockTransport::new(responses))).unwrap();
+        let request = export::build_request(
+            &["11111111-1111-1111-1111-111111111111".to_string()],
+            "package",
+            None,
+            None,
+        )?;
+        let download = DownloadOptions {
+            output: Some(dir.to_path_buf()),
+            ..DownloadOptions::default()
+        };
+        run(
+            &client,
+            &request,
+            &download,
+            Duration::ZERO,
+            Duration::from_secs(60),
+            allow_export_errors,
+            |_, _| {},
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_downloads_completed_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = pipeline(
+            vec![
+                export_status("IN_PROGRESS"),
+                export_status("IN_PROGRESS"),
+                export_status("COMPLETED"),
+                MockResponse::json(200, "zip-bytes"),
+            ],
+            dir.path(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            result.download.output_path,
+            dir.path().join(format!("{}.zip", EXPORT_UUID))
+        );
+        assert_eq!(
+            std::fs::read(&result.download.output_path).unwrap(),
+            b"zip-bytes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stage_exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = pipeline(
+            vec![MockResponse::json(400, "bad request")],
+            dir.path(),
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::Stage {
+                stage: Stage::Export,
+                ..
+            }
+        ));
+        assert_eq!(err.exit_code(), 10);
+
+        let err = pipeline(
+            vec![export_status("IN_PROGRESS"), export_status("FAILED")],
+            dir.path(),
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.exit_code(), 11);
+
+        let with_errors = || {
+            vec![
+                export_status("IN_PROGRESS"),
+                export_status("COMPLETED_WITH_ERRORS"),
+                MockResponse::json(404, "gone"),
+            ]
+        };
+        let err = pipeline(with_errors(), dir.path(), false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CliError::Stage { stage: Stage::Wait, ref source } if matches!(**source, CliError::CompletedWithErrors(_)))
+        );
+
+        let err = pipeline(with_errors(), dir.path(), true).await.unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::Stage {
+                stage: Stage::Download,
+                ..
+            }
+        ));
+        assert_eq!(err.exit_code(), 12);
+    }
+}
@@ -1,47 +1,220 @@
-use crate::{client::Client, Config, Result};
+use crate::error::CliError;
+use crate::models::Package;
+use crate::{client::Client, output, render, text, Config, Result};
 use colored::*;
-use serde_json;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use tracing::info;
 
+/// Fetch the packages for the given applications.
+pub async fn run(client: &Client, app_uuids: &[String]) -> Result<Vec<Package>> {
+    info!("Fetching packages for applications: {:?}", app_uuids);
+
+    client.get_packages(app_uuids).await
+}
+
+/// Packages of one application, or why they could not be fetched.
+pub struct AppPackages {
+    pub app_uuid: String,
+    pub result: Result<Vec<Package>>,
+}
+
+/// Fetch each application's packages with its own request, at most
+/// `concurrency` at a time, so one invalid or forbidden application does
+/// not hide the packages of the others. Results are in request order.
+pub async fn run_per_app(
+    client: Arc<Client>,
+    app_uuids: &[String],
+    concurrency: usize,
+) -> Result<Vec<AppPackages>> {
+    info!(
+        "Fetching packages for {} applications separately",
+        app_uuids.len()
+    );
+
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (index, app_uuid) in app_uuids.iter().cloned().enumerate() {
+        let (client, semaphore) = (client.clone(), semaphore.clone());
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("package semaphore is never closed");
+            let result = run(&client, std::slice::from_ref(&app_uuid)).await;
+            (index, AppPackages { app_uuid, result })
+        });
+    }
+
+    let mut outcomes = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        outcomes
+            .push(joined.map_err(|e| CliError::Unknown(format!("Package request failed: {}", e)))?);
+    }
+    outcomes.sort_by_key(|(index, _)| *index);
+    Ok(outcomes.into_iter().map(|(_, outcome)| outcome).collect())
+}
+
+/// A package attributed to the application it was listed for
+#[derive(Debug, Serialize)]
+struct AppPackage<'a> {
+    app_uuid: &'a str,
+    #[serde(flatten)]
+    package: &'a Package,
+}
+
+/// An application whose packages could not be fetched
+#[derive(Debug, Serialize)]
+struct AppError<'a> {
+    app_uuid: &'a str,
+    kind: &'static str,
+    error: String,
+}
+
 pub async fn execute(
     config: Config,
     app_uuids: Vec<String>,
+    concurrency: usize,
     format: Option<String>,
 ) -> Result<()> {
+    if app_uuids.len() > 1 {
+        return execute_per_app(config, app_uuids, concurrency, format).await;
+    }
+
     let client = Client::new(config)?;
-    
-    info!("Fetching packages for applications: {:?}", app_uuids);
-    
-    let packages = client.get_packages(&app_uuids).await?;
-    
+    let packages = run(&client, &app_uuids).await?;
+
+    render::renderer(format.as_deref()).render_packages(&packages)
+}
+
+/// Print the packages of several applications, grouped by application, and
+/// the applications that failed. Fails with the first error, after every
+/// application has been reported.
+async fn execute_per_app(
+    config: Config,
+    app_uuids: Vec<String>,
+    concurrency: usize,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "get-packages with several --app-uuid")?;
+    let client = Arc::new(Client::new(config)?);
+    let outcomes = run_per_app(client, &app_uuids, concurrency).await?;
+
     match format.as_deref() {
         Some("json") => {
-            let json_output = serde_json::to_string_pretty(&packages)?;
-            println!("{}", json_output);
+            let packages: Vec<AppPackage> = outcomes
+                .iter()
+                .filter_map(|o| Some((o.app_uuid.as_str(), o.result.as_ref().ok()?)))
+                .flat_map(|(app_uuid, packages)| {
+                    packages
+                        .iter()
+                        .map(move |package| AppPackage { app_uuid, package })
+                })
+                .collect();
+            let errors: Vec<AppError> = outcomes
+                .iter()
+                .filter_map(|o| {
+                    let e = o.result.as_ref().err()?;
+                    Some(AppError {
+                        app_uuid: &o.app_uuid,
+                        kind: e.kind(),
+                        error: e.to_string(),
+                    })
+                })
+                .collect();
+            output::json(&serde_json::json!({ "packages": packages, "errors": errors }))?;
         }
         _ => {
-            println!("{}", "Packages:".bold().green());
-            println!("Total packages: {}", packages.len().to_string().cyan());
-            println!();
-            
-            if packages.is_empty() {
-                println!("{}", "No packages found.".yellow());
-            } else {
-                for package in &packages {
-                    println!("{} {}", "•".cyan(), package.name.bold());
-                    println!("  {}: {}", "Version".dimmed(), package.version);
-                    println!("  {}: {}", "ID".dimmed(), package.id);
-                    
-                    if !package.dependencies.is_empty() {
-                        println!("  {}: {}", "Dependencies".dimmed(), package.dependencies.join(", "));
+            println!("{}", output::tagged("Packages:".bold().green()));
+            for outcome in &outcomes {
+                let Ok(packages) = &outcome.result else {
+                    continue;
+                };
+                println!();
+                println!(
+                    "{} {} ({} packages)",
+                    "Application".bold(),
+                    outcome.app_uuid.cyan(),
+                    packages.len()
+                );
+                if packages.is_empty() {
+                    println!("  {}", "No packages found.".yellow());
+                }
+                for package in packages {
+                    text::package_entry(package);
+                }
+            }
+
+            if outcomes.iter().any(|o| o.result.is_err()) {
+                println!();
+                println!("{}", "Errors:".bold().red());
+                for outcome in &outcomes {
+                    if let Err(e) = &outcome.result {
+                        println!("  {} {}: {}", "✗".red(), outcome.app_uuid, e);
                     }
-                    
-                    println!("  {}: {}", "Created".dimmed(), package.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
-                    println!();
                 }
             }
         }
     }
-    
-    Ok(())
-}
\ No newline at end of file
+
+    match outcomes.into_iter().find_map(|o| o.result.err()) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MockResponse, MockTransport};
+
+    fn packages(ids: &[&str]) -> MockResponse {
+        let packages: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "id": id, "name": id, "version": "1", "dependencies": [],
+                    "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z"
+                })
+            })
+            .collect();
+        MockResponse::json(200, &serde_json::Value::from(packages).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_per_app_requests_isolate_failures() {
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let transport = Arc::new(MockTransport::new(vec![
+            packages(&["p1", "p2"]),
+            MockResponse::json(403, "forbidden"),
+            packages(&["p3"]),
+        ]));
+        let client = Arc::new(Client::with_transport(config, transport.clone()).unwrap());
+        let apps = ["app-1", "app-2", "app-3"].map(String::from);
+
+        let outcomes = run_per_app(client, &apps, 1).await.unwrap();
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|o| o.app_uuid.as_str())
+                .collect::<Vec<_>>(),
+            apps
+        );
+        assert_eq!(outcomes[0].result.as_ref().unwrap().len(), 2);
+        assert!(matches!(
+            outcomes[1].result,
+            Err(CliError::Authentication(_))
+        ));
+        assert_eq!(outcomes[2].result.as_ref().unwrap()[0].id, "p3");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].url.ends_with("app_uuids=app-2"));
+    }
+}
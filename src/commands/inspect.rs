@@ -1,15 +1,18 @@
-use crate::{client::Client, Config, Result};
+use crate::archive::{self, ArchiveFormat};
+use crate::client::RequestPreview;
+use crate::models::{InspectionRequest, InspectionResponse};
+use crate::{client::Client, output, render, Config, Result};
 use colored::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::info;
 
-pub async fn execute(
-    config: Config,
-    package_path: PathBuf,
-    customization_file: Option<PathBuf>,
-    admin_console_file: Option<PathBuf>,
-    format: Option<String>,
-) -> Result<()> {
+/// Check that the package and optional files exist and run local package
+/// validation. Errors if the package is invalid.
+pub async fn validate_inputs(
+    package_path: &Path,
+    customization_file: Option<&Path>,
+    admin_console_file: Option<&Path>,
+) -> Result<crate::models::ValidationResult> {
     if !package_path.exists() {
         return Err(crate::error::CliError::FileSystem(format!(
             "Package file not found: {}",
@@ -17,7 +20,7 @@ pub async fn execute(
         )));
     }
     // Validate optional files
-    if let Some(ref path) = customization_file {
+    if let Some(path) = customization_file {
         if !path.exists() {
             return Err(crate::error::CliError::InvalidArgument(format!(
                 "Customization file not found: {}",
@@ -25,7 +28,7 @@ pub async fn execute(
             )));
         }
     }
-    if let Some(ref path) = admin_console_file {
+    if let Some(path) = admin_console_file {
         if !path.exists() {
             return Err(crate::error::CliError::InvalidArgument(format!(
                 "Admin Console settings file not found: {}",
@@ -35,37 +38,69 @@ pub async fn execute(
     }
 
     // Perform a quick local validation to improve UX and use helper functions
-    let validation = validate_package_file(&package_path).await?;
+    let validation = validate_package_file(package_path).await?;
     if !validation.is_valid {
-        return Err(crate::error::CliError::InvalidArgument(
-            "Package file is invalid".to_string(),
-        ));
-    }
-
-    let client = Client::new(config)?;
-    info!("Inspecting package via API: {}", package_path.display());
-    println!("{}", format!("Inspecting package: {}", package_path.display()).cyan());
-    println!(
-        "{} {}",
-        "Package size:".dimmed(),
-        format_bytes(validation.total_size).cyan()
-    );
-    if !validation.violations.is_empty() {
-        // Show non-error validations as hints before sending to API
-        let warnings: Vec<_> = validation
+        let errors: Vec<_> = validation
             .violations
             .iter()
-            .filter(|v| matches!(v.severity, crate::models::ViolationSeverity::Warning))
+            .filter(|v| matches!(v.severity, crate::models::ViolationSeverity::Error))
+            .map(|v| v.message.as_str())
             .collect();
-        if !warnings.is_empty() {
-            println!("{}", "Validation warnings:".yellow());
-            for w in warnings {
-                println!("  - {} ({})", w.message, w.code);
-            }
-        }
+        return Err(crate::error::CliError::InvalidArgument(format!(
+            "Package file is invalid: {}",
+            errors.join("; ")
+        )));
     }
 
-    // Build InspectionRequest based on provided file names
+    Ok(validation)
+}
+
+/// Validate the inputs and submit the package for inspection.
+pub async fn run(
+    client: &Client,
+    package_path: &Path,
+    customization_file: Option<&Path>,
+    admin_console_file: Option<&Path>,
+) -> Result<InspectionResponse> {
+    validate_inputs(package_path, customization_file, admin_console_file).await?;
+
+    info!("Inspecting package via API: {}", package_path.display());
+
+    let request_json = inspection_request(package_path, customization_file, admin_console_file)?;
+    client
+        .inspect_package(
+            &request_json,
+            package_path,
+            customization_file,
+            admin_console_file,
+        )
+        .await
+}
+
+/// Describe the inspection request without sending it.
+pub fn preview(
+    client: &Client,
+    package_path: &Path,
+    customization_file: Option<&Path>,
+    admin_console_file: Option<&Path>,
+) -> Result<RequestPreview> {
+    let request_json = inspection_request(package_path, customization_file, admin_console_file)?;
+    client
+        .prepare_inspection(
+            &request_json,
+            package_path,
+            customization_file,
+            admin_console_file,
+        )?
+        .preview()
+}
+
+// Build InspectionRequest based on provided file names
+fn inspection_request(
+    package_path: &Path,
+    customization_file: Option<&Path>,
+    admin_console_file: Option<&Path>,
+) -> Result<InspectionRequest> {
     let package_file_name = package_path
         .file_name()
         .and_then(|n| n.to_str())
@@ -74,36 +109,87 @@ pub async fn execute(
         ))?;
 
     let customization_file_name = customization_file
-        .as_ref()
         .and_then(|p| p.file_name().and_then(|n| n.to_str()))
         .map(|s| s.to_string());
     let admin_console_file_name = admin_console_file
-        .as_ref()
         .and_then(|p| p.file_name().and_then(|n| n.to_str()))
         .map(|s| s.to_string());
 
-    let request_json = crate::models::InspectionRequest {
+    Ok(InspectionRequest {
         admin_console_settings_file_name: admin_console_file_name,
         package_file_name: package_file_name.to_string(),
-        customization_file_name: customization_file_name,
-    };
+        customization_file_name,
+    })
+}
 
-    let response = client
-        .inspect_package(
-            &request_json,
+pub async fn execute(
+    config: Config,
+    package_path: PathBuf,
+    customization_file: Option<PathBuf>,
+    admin_console_file: Option<PathBuf>,
+    print_request: bool,
+    format: Option<String>,
+) -> Result<()> {
+    render::check_builtin(format.as_deref(), "inspect")?;
+    let validation = validate_inputs(
+        &package_path,
+        customization_file.as_deref(),
+        admin_console_file.as_deref(),
+    )
+    .await?;
+
+    let client = Client::new(config)?;
+
+    if print_request {
+        let preview = preview(
+            &client,
             &package_path,
             customization_file.as_deref(),
             admin_console_file.as_deref(),
-        )
-        .await?;
+        )?;
+        return output::request_preview(&preview, format.as_deref());
+    }
+
+    output::status(format!("Inspecting package: {}", package_path.display()).cyan());
+    output::status(format!(
+        "{} {}",
+        "Package size:".dimmed(),
+        output::format_bytes(validation.total_size).cyan()
+    ));
+    if !validation.violations.is_empty() {
+        // Show non-error validations as hints before sending to API
+        let warnings: Vec<_> = validation
+            .violations
+            .iter()
+            .filter(|v| matches!(v.severity, crate::models::ViolationSeverity::Warning))
+            .collect();
+        if !warnings.is_empty() {
+            output::status("Validation warnings:".yellow());
+            for w in warnings {
+                output::status(format!("  - {}", w));
+            }
+        }
+    }
+
+    let response = run(
+        &client,
+        &package_path,
+        customization_file.as_deref(),
+        admin_console_file.as_deref(),
+    )
+    .await?;
+
+    if output::is_quiet() {
+        output::identifier("Inspection UUID", response.uuid);
+        return Ok(());
+    }
 
     match format.as_deref() {
         Some("json") => {
-            let json_output = serde_json::to_string_pretty(&response)?;
-            println!("{}", json_output);
+            output::json(&response)?;
         }
         _ => {
-            println!("{}", "Inspection initiated:".bold().green());
+            println!("{}", output::tagged("Inspection initiated:".bold().green()));
             println!("  {}: {}", "UUID".dimmed(), response.uuid);
             println!("  {}: {}", "URL".dimmed(), response.url);
         }
@@ -112,7 +198,7 @@ pub async fn execute(
     Ok(())
 }
 
-async fn validate_package_file(package_path: &PathBuf) -> Result<crate::models::ValidationResult> {
+async fn validate_package_file(package_path: &Path) -> Result<crate::models::ValidationResult> {
     use std::fs;
     
     // Basic file validation
@@ -141,15 +227,48 @@ async fn validate_package_file(package_path: &PathBuf) -> Result<crate::models::
         });
     }
     
-    // Check file extension
-    if let Some(ext) = package_path.extension() {
-        if ext != "zip" {
+    // Check the archive by its content, then its extension against it
+    let format = ArchiveFormat::detect(package_path).map_err(|e| {
+        crate::error::CliError::FileSystem(format!("Failed to read package file: {}", e))
+    })?;
+    match format {
+        Some(ArchiveFormat::TarGz) => {
+            let problem = match archive::check_tar_gz(package_path) {
+                Ok(0) => Some("Package archive contains no files".to_string()),
+                Ok(_) => None,
+                Err(e) => Some(format!("Package file is not a valid tar.gz archive: {}", e)),
+            };
+            if let Some(message) = problem {
+                violations.push(crate::models::ValidationViolation {
+                    severity: crate::models::ViolationSeverity::Error,
+                    message,
+                    code: "INVALID_ARCHIVE".to_string(),
+                });
+                is_valid = false;
+            }
+        }
+        Some(ArchiveFormat::Zip) => {}
+        None if metadata.len() > 0 => {
             violations.push(crate::models::ValidationViolation {
                 severity: crate::models::ViolationSeverity::Warning,
-                message: "Package file should have .zip extension".to_string(),
-                code: "WRONG_EXTENSION".to_string(),
+                message: "Package file is neither a zip nor a tar.gz archive".to_string(),
+                code: "UNKNOWN_FORMAT".to_string(),
             });
         }
+        None => {}
+    }
+
+    let expected = format.unwrap_or(ArchiveFormat::Zip);
+    if package_path.extension().is_some() && !expected.matches_extension(package_path) {
+        violations.push(crate::models::ValidationViolation {
+            severity: crate::models::ViolationSeverity::Warning,
+            message: format!(
+                "Package file should have a {} extension for a {} archive",
+                expected.extensions().join(" or "),
+                expected.name()
+            ),
+            code: "WRONG_EXTENSION".to_string(),
+        });
     }
     
     Ok(crate::models::ValidationResult {
@@ -159,15 +278,95 @@ async fn validate_package_file(package_path: &PathBuf) -> Result<crate::models::
     })
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
     }
-    
-    format!("{:.1} {}", size, UNITS[unit_index])
-}
\ No newline at end of file
+
+    fn write(dir: &tempfile::TempDir, name: &str, contents: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(contents)
+            .unwrap();
+        path
+    }
+
+    fn codes(validation: &crate::models::ValidationResult) -> Vec<&str> {
+        validation
+            .violations
+            .iter()
+            .map(|v| v.code.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_validate_zip_and_tar_gz_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = tar_gz(&[("application.xml", b"<app/>")]);
+
+        let zip = validate_package_file(&write(&dir, "app.zip", b"PK\x03\x04rest"))
+            .await
+            .unwrap();
+        assert!(zip.is_valid);
+        assert!(codes(&zip).is_empty());
+
+        for name in ["app.tar.gz", "app.tgz"] {
+            let tar_gz = validate_package_file(&write(&dir, name, &archive))
+                .await
+                .unwrap();
+            assert!(tar_gz.is_valid, "{}", name);
+            assert!(codes(&tar_gz).is_empty(), "{}", name);
+        }
+
+        // The extension is checked against the detected format
+        let misnamed = validate_package_file(&write(&dir, "misnamed.zip", &archive))
+            .await
+            .unwrap();
+        assert!(misnamed.is_valid);
+        assert_eq!(codes(&misnamed), ["WRONG_EXTENSION"]);
+        assert!(misnamed.violations[0].message.contains(".tar.gz or .tgz"));
+
+        let text = validate_package_file(&write(&dir, "notes.zip", b"not an archive"))
+            .await
+            .unwrap();
+        assert!(text.is_valid);
+        assert_eq!(codes(&text), ["UNKNOWN_FORMAT"]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_rejects_broken_tar_gz() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = tar_gz(&[("application.xml", &[b'x'; 4096])]);
+
+        let truncated = write(&dir, "truncated.tar.gz", &archive[..archive.len() / 2]);
+        let validation = validate_package_file(&truncated).await.unwrap();
+        assert!(!validation.is_valid);
+        assert_eq!(codes(&validation), ["INVALID_ARCHIVE"]);
+
+        let empty = validate_package_file(&write(&dir, "empty.tar.gz", &tar_gz(&[])))
+            .await
+            .unwrap();
+        assert!(!empty.is_valid);
+
+        let err = validate_inputs(&truncated, None, None).await.unwrap_err();
+        assert!(
+            err.to_string().contains("not a valid tar.gz archive"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! `inspect-local`: read a package zip's manifest and object list without
+//! contacting the server.
+
+use crate::archive::ArchiveFormat;
+use crate::error::CliError;
+use crate::{output, render, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// Where Appian packages keep their manifest
+const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// Manifest attributes holding the version that produced the package, in
+/// order of preference
+const VERSION_ATTRIBUTES: [&str; 2] = ["Appian-Version", "Implementation-Version"];
+
+/// What a package zip contains, as read from the file alone.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalPackage {
+    pub package: PathBuf,
+    pub version: Option<String>,
+    /// Every attribute of `META-INF/MANIFEST.MF`
+    pub manifest: BTreeMap<String, String>,
+    pub objects: Vec<PackageObject>,
+}
+
+/// One design object exported in the package.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageObject {
+    /// The folder the object is exported in, e.g. `content` or `processModel`
+    #[serde(rename = "type")]
+    pub object_type: String,
+    /// The object's `<name>`, when its file has one
+    pub name: Option<String>,
+    /// The file name without its extension, which Appian sets to the UUID
+    pub uuid: String,
+    pub path: String,
+}
+
+/// Read the manifest and objects of the package zip at `path`.
+pub fn read_package(path: &Path) -> Result<LocalPackage> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        CliError::FileSystem(format!("Failed to read package {}: {}", path.display(), e))
+    })?;
+    if ArchiveFormat::detect(path)? == Some(ArchiveFormat::TarGz) {
+        return Err(CliError::InvalidArgument(format!(
+            "{} is a tar.gz archive; inspect-local reads zip packages",
+            path.display()
+        )));
+    }
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| {
+        CliError::Validation(format!(
+            "{} is not a valid zip archive: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    let manifest = match read_entry(&mut archive, MANIFEST_PATH, path)? {
+        Some(contents) => parse_manifest(&contents).map_err(|e| {
+            CliError::Validation(format!("Malformed manifest in {}: {}", path.display(), e))
+        })?,
+        None => {
+            return Err(CliError::Validation(format!(
+                "{} has no {}; it does not look like an Appian package",
+                path.display(),
+                MANIFEST_PATH
+            )))
+        }
+    };
+
+    let entries: Vec<String> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/') && !name.starts_with("META-INF/"))
+        .filter(|name| {
+            let lower = name.to_ascii_lowercase();
+            lower.ends_with(".xml") || lower.ends_with(".xsd")
+        })
+        .map(str::to_string)
+        .collect();
+    let mut objects = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let name =
+            read_entry(&mut archive, &entry, path)?.and_then(|contents| object_name(&contents));
+        let folder = entry.rsplit_once('/').map_or("", |(folder, _)| folder);
+        objects.push(PackageObject {
+            object_type: folder
+                .split('/')
+                .next()
+                .filter(|f| !f.is_empty())
+                .unwrap_or("(root)")
+                .to_string(),
+            name,
+            uuid: crate::archive::object_uuid(&entry).to_string(),
+            path: entry.clone(),
+        });
+    }
+    objects.sort_by(|a, b| {
+        (&a.object_type, &a.name, &a.uuid).cmp(&(&b.object_type, &b.name, &b.uuid))
+    });
+
+    let version = VERSION_ATTRIBUTES
+        .iter()
+        .find_map(|key| manifest.get(*key).cloned());
+    Ok(LocalPackage {
+        package: path.to_path_buf(),
+        version,
+        manifest,
+        objects,
+    })
+}
+
+/// The entry `name` as text, `None` when the archive has no such entry.
+/// Entries that are not UTF-8 are read lossily.
+fn read_entry<R: Read + Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+    path: &Path,
+) -> Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => {
+            return Err(CliError::Validation(format!(
+                "Failed to read {} in {}: {}",
+                name,
+                path.display(),
+                e
+            )))
+        }
+    };
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| {
+        CliError::Validation(format!(
+            "Failed to read {} in {}: {}",
+            name,
+            path.display(),
+            e
+        ))
+    })?;
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Parse a JAR-style manifest: `Name: value` lines, where a line starting
+/// with a space continues the previous value. Blank lines separate
+/// sections and are skipped.
+fn parse_manifest(contents: &str) -> std::result::Result<BTreeMap<String, String>, String> {
+    let mut attributes: BTreeMap<String, String> = BTreeMap::new();
+    let mut last: Option<String> = None;
+    for (index, line) in contents.trim_start_matches('\u{feff}').lines().enumerate() {
+        if let Some(continued) = line.strip_prefix(' ') {
+            let value = last
+                .as_ref()
+                .and_then(|key| attributes.get_mut(key))
+                .ok_or_else(|| {
+                    format!(
+                        "line {} continues an attribute that was never started",
+                        index + 1
+                    )
+                })?;
+            value.push_str(continued);
+        } else if line.trim().is_empty() {
+            last = None;
+        } else {
+            let (key, value) = line
+                .split_once(':')
+                .filter(|(key, _)| !key.trim().is_empty())
+                .ok_or_else(|| format!("line {} is not a 'Name: value' attribute", index + 1))?;
+            attributes.insert(key.trim().to_string(), value.trim().to_string());
+            last = Some(key.trim().to_string());
+        }
+    }
+    if attributes.is_empty() {
+        return Err("it has no attributes".to_string());
+    }
+    Ok(attributes)
+}
+
+/// Text of the first `<name>` element of an object's XML, with the
+/// predefined entities decoded.
+fn object_name(xml: &str) -> Option<String> {
+    let start = xml.find("<name>")? + "<name>".len();
+    let end = start + xml[start..].find("</name>")?;
+    let name = xml[start..end]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    (!name.is_empty()).then_some(name)
+}
+
+/// Print the manifest version and the objects of a local package zip.
+/// Needs neither a configuration nor the network.
+pub fn execute(package: PathBuf, format: Option<String>) -> Result<()> {
+    render::check_builtin(format.as_deref(), "inspect-local")?;
+    let package = read_package(&package)?;
+
+    match format.as_deref() {
+        Some("json") => output::json(&package)?,
+        _ => {
+            println!(
+                "{}",
+                format!("Package {}", package.package.display()).bold()
+            );
+            println!(
+                "  {}: {}",
+                "Version".dimmed(),
+                package.version.as_deref().unwrap_or("unknown")
+            );
+            println!("  {}: {}", "Objects".dimmed(), package.objects.len());
+            let mut current = None;
+            for object in &package.objects {
+                if current != Some(&object.object_type) {
+                    let count = package
+                        .objects
+                        .iter()
+                        .filter(|o| o.object_type == object.object_type)
+                        .count();
+                    println!("\n{} ({})", object.object_type.cyan(), count);
+                    current = Some(&object.object_type);
+                }
+                println!(
+                    "  {}  {}",
+                    object.name.as_deref().unwrap_or("(unnamed)"),
+                    object.uuid.dimmed()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, contents) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_read_package_lists_manifest_and_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.zip");
+        write_zip(
+            &path,
+            &[
+                (
+                    MANIFEST_PATH,
+                    "Manifest-Version: 1.0\r\nAppian-Version: 24.\r\n 3.0\r\n\r\n",
+                ),
+                (
+                    "content/_a-0001.xml",
+                    "<contentHaul><rule><name>AS_formatName</name></rule></contentHaul>",
+                ),
+                (
+                    "application/_a-0002.xml",
+                    "<applicationHaul><name>Sales &amp; Ops</name></applicationHaul>",
+                ),
+                (
+                    "content/_a-0003.xml",
+                    "<contentHaul><document/></contentHaul>",
+                ),
+                ("content/_a-0003.pdf", "%PDF"),
+                (
+                    "content/_a-0004.v2.xml",
+                    "<contentHaul><rule><name>AS_legacy</name></rule></contentHaul>",
+                ),
+            ],
+        );
+
+        let package = read_package(&path).unwrap();
+        assert_eq!(package.version.as_deref(), Some("24.3.0"));
+        assert_eq!(package.manifest["Manifest-Version"], "1.0");
+        let objects: Vec<(&str, Option<&str>, &str)> = package
+            .objects
+            .iter()
+            .map(|o| (o.object_type.as_str(), o.name.as_deref(), o.uuid.as_str()))
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                ("application", Some("Sales & Ops"), "_a-0002"),
+                ("content", None, "_a-0003"),
+                ("content", Some("AS_formatName"), "_a-0001"),
+                ("content", Some("AS_legacy"), "_a-0004.v2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_package_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.zip");
+
+        write_zip(&path, &[("content/_a-0001.xml", "<contentHaul/>")]);
+        let err = read_package(&path).unwrap_err();
+        assert!(matches!(err, CliError::Validation(_)));
+        assert!(
+            err.to_string().contains("has no META-INF/MANIFEST.MF"),
+            "{}",
+            err
+        );
+
+        write_zip(
+            &path,
+            &[(MANIFEST_PATH, "Manifest-Version: 1.0\nnot an attribute\n")],
+        );
+        let err = read_package(&path).unwrap_err();
+        assert!(
+            err.to_string().contains("Malformed manifest") && err.to_string().contains("line 2"),
+            "{}",
+            err
+        );
+
+        std::fs::write(&path, b"PK\x03\x04 truncated").unwrap();
+        assert!(read_package(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("is not a valid zip archive"));
+
+        let err = read_package(&dir.path().join("missing.zip")).unwrap_err();
+        assert!(matches!(err, CliError::FileSystem(_)));
+    }
+}
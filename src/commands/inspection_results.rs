@@ -1,66 +1,229 @@
-use crate::{client::Client, Config, Result};
+use crate::error::CliError;
+use crate::models::{InspectionProblemsSummary, InspectionResults};
+use crate::polling::WaitOptions;
+use crate::{client::Client, output, polling, render, Config, Result};
 use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::info;
 
+/// Interval and limit for `get-inspection --wait` without `--interval` or
+/// `--timeout`
+const WAIT_INTERVAL: Duration = Duration::from_secs(10);
+const WAIT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Fetch the results of an inspection.
+pub async fn run(client: &Client, uuid: &str) -> Result<InspectionResults> {
+    info!("Getting inspection results for: {}", uuid);
+
+    client.get_inspection_results(uuid).await
+}
+
+/// Poll an inspection until it completes or fails.
+pub async fn wait(client: &Client, uuid: &str, options: WaitOptions) -> Result<InspectionResults> {
+    polling::poll_until(
+        client,
+        &format!("Inspection {}", uuid),
+        options.interval_or(WAIT_INTERVAL),
+        options.timeout_or(WAIT_TIMEOUT),
+        || run(client, uuid),
+        |results| results.status.is_terminal(),
+        |secs, results| {
+            output::progress(format!("[{:4}s] Status: {:?}", secs, results.status).dimmed())
+        },
+    )
+    .await
+}
+
+/// One error or warning of an inspection, as written by
+/// `--export-problems`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProblemRow<'a> {
+    /// `error` or `warning`
+    pub severity: &'static str,
+    pub object_name: &'a str,
+    pub object_uuid: &'a str,
+    pub message: &'a str,
+}
+
+/// Every error, then every warning, of an inspection.
+pub fn problem_rows(problems: &InspectionProblemsSummary) -> Vec<ProblemRow<'_>> {
+    let errors = problems.errors.iter().map(|e| ProblemRow {
+        severity: "error",
+        object_name: &e.object_name,
+        object_uuid: &e.object_uuid,
+        message: &e.error_message,
+    });
+    let warnings = problems.warnings.iter().map(|w| ProblemRow {
+        severity: "warning",
+        object_name: &w.object_name,
+        object_uuid: &w.object_uuid,
+        message: &w.warning_message,
+    });
+    errors.chain(warnings).collect()
+}
+
+/// Write every error and warning to `path` for triage in a tracker: CSV
+/// with a header row for a `.csv` path, a JSON array for `.json`. Without
+/// problems the file holds just the header, or `[]`. Returns the number of
+/// rows written.
+pub fn export_problems(path: &Path, problems: &InspectionProblemsSummary) -> Result<usize> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let csv = match extension.as_deref() {
+        Some("csv") => true,
+        Some("json") => false,
+        _ => {
+            return Err(CliError::InvalidArgument(format!(
+                "--export-problems {} must end in .csv or .json",
+                path.display()
+            )))
+        }
+    };
+
+    let rows = problem_rows(problems);
+    let write = || -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        if csv {
+            let mut writer = csv::Writer::from_writer(file);
+            writer.write_record(["severity", "object_name", "object_uuid", "message"])?;
+            for row in &rows {
+                writer.write_record([
+                    row.severity,
+                    row.object_name,
+                    row.object_uuid,
+                    row.message,
+                ])?;
+            }
+            writer.flush()
+        } else {
+            serde_json::to_writer_pretty(&mut file, &rows)?;
+            writeln!(file)?;
+            file.flush()
+        }
+    };
+    write().map_err(|e| {
+        CliError::FileSystem(format!(
+            "Failed to write problems to {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    Ok(rows.len())
+}
+
+/// With `wait_for_completion`, the inspection is polled until it finishes.
+/// `max_entries` caps the errors and warnings listed in text output;
+/// `Some(0)` prints only the counts. JSON output is never capped.
+/// `export_path` additionally receives every problem, see
+/// [`export_problems`].
 pub async fn execute(
     config: Config,
     uuid: String,
+    wait_for_completion: Option<WaitOptions>,
+    max_entries: Option<usize>,
+    raw: bool,
+    export_path: Option<PathBuf>,
     format: Option<String>,
 ) -> Result<()> {
     let client = Client::new(config)?;
 
-    info!("Getting inspection results for: {}", uuid);
-    println!("{}", format!("Fetching inspection results for {}...", uuid).cyan());
+    if raw {
+        output::raw(
+            &client
+                .get_raw(&client.endpoints().inspections(&uuid))
+                .await?,
+        );
+        return Ok(());
+    }
 
-    let results = client.get_inspection_results(&uuid).await?;
+    let results = if let Some(options) = wait_for_completion {
+        output::status(format!("Waiting for inspection {} to finish...", uuid).cyan());
+        let results = wait(&client, &uuid, options).await?;
+        output::status("");
+        results
+    } else {
+        output::status(format!("Fetching inspection results for {}...", uuid).cyan());
+        run(&client, &uuid).await?
+    };
 
-    match format.as_deref() {
-        Some("json") => {
-            let json_output = serde_json::to_string_pretty(&results)?;
-            println!("{}", json_output);
-        }
-        _ => {
-            println!("{}", "Inspection Results:".bold().green());
-            println!("  {}: {:?}", "Status".dimmed(), results.status);
-
-            let admin = &results.summary.admin_console_settings_expected;
-            println!("{}", "  Admin Console Settings:".bold());
-            println!("    {}: {}", "Total".dimmed(), admin.total);
-            println!("    {}: {}", "Imported".dimmed(), admin.imported);
-            println!("    {}: {}", "Failed".dimmed(), admin.failed);
-            println!("    {}: {}", "Skipped".dimmed(), admin.skipped);
-
-            let objs = &results.summary.objects_expected;
-            println!("{}", "  Package Objects:".bold());
-            println!("    {}: {}", "Total".dimmed(), objs.total);
-            println!("    {}: {}", "Imported".dimmed(), objs.imported);
-            println!("    {}: {}", "Failed".dimmed(), objs.failed);
-            println!("    {}: {}", "Skipped".dimmed(), objs.skipped);
-
-            let probs = &results.summary.problems;
-            println!("{}", "  Problems:".bold());
-            println!("    {}: {}", "Total Errors".dimmed(), probs.total_errors);
-            println!("    {}: {}", "Total Warnings".dimmed(), probs.total_warnings);
-
-            if !probs.errors.is_empty() {
-                println!("{}", "    Errors:".bold());
-                for e in &probs.errors {
-                    println!("      • {}", e.object_name.bold());
-                    println!("        {}: {}", "UUID".dimmed(), e.object_uuid);
-                    println!("        {}: {}", "Message".dimmed(), e.error_message);
-                }
-            }
+    render::renderer(format.as_deref()).render_inspection(&uuid, &results, max_entries)?;
 
-            if !probs.warnings.is_empty() {
-                println!("{}", "    Warnings:".bold());
-                for w in &probs.warnings {
-                    println!("      • {}", w.object_name.bold());
-                    println!("        {}: {}", "UUID".dimmed(), w.object_uuid);
-                    println!("        {}: {}", "Message".dimmed(), w.warning_message);
-                }
-            }
-        }
+    let problems = &results.summary.problems;
+    if let Some(path) = &export_path {
+        let written = export_problems(path, problems)?;
+        output::status(format!(
+            "Wrote {} problem(s) to {}",
+            written,
+            path.display()
+        ));
+    }
+    output::result_line(
+        &output::status_name(&results.status),
+        problems.total_errors as u64,
+        problems.total_warnings as u64,
+        results.status.is_terminal() && !results.status.is_success(),
+    );
+
+    // Only a waited-on inspection gates the exit code; a one-off fetch
+    // reports whatever state it found
+    if wait_for_completion.is_some() && !results.status.is_success() {
+        return Err(CliError::DeploymentFailed(format!(
+            "Inspection {} failed",
+            uuid
+        )));
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_problems_as_csv_and_json() {
+        let problems: InspectionProblemsSummary = serde_json::from_value(serde_json::json!({
+            "totalErrors": 1,
+            "totalWarnings": 1,
+            "errors": [{"errorMessage": "Missing precedent, \"AS_rule\"", "objectName": "AS_form", "objectUuid": "u-1"}],
+            "warnings": [{"warningMessage": "Deprecated function", "objectName": "AS_page", "objectUuid": "u-2"}]
+        }))
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let csv_path = dir.path().join("problems.CSV");
+        assert_eq!(export_problems(&csv_path, &problems).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(&csv_path).unwrap(),
+            "severity,object_name,object_uuid,message\n\
+             error,AS_form,u-1,\"Missing precedent, \"\"AS_rule\"\"\"\n\
+             warning,AS_page,u-2,Deprecated function\n"
+        );
+
+        let json_path = dir.path().join("problems.json");
+        export_problems(&json_path, &problems).unwrap();
+        let rows: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(rows[1]["severity"], "warning");
+        assert_eq!(rows[1]["object_uuid"], "u-2");
+
+        // No problems still makes a valid, empty file
+        let none: InspectionProblemsSummary =
+            serde_json::from_value(serde_json::json!({"totalErrors": 0, "totalWarnings": 0}))
+                .unwrap();
+        assert_eq!(export_problems(&csv_path, &none).unwrap(), 0);
+        assert_eq!(
+            std::fs::read_to_string(&csv_path).unwrap(),
+            "severity,object_name,object_uuid,message\n"
+        );
+        export_problems(&json_path, &none).unwrap();
+        assert_eq!(std::fs::read_to_string(&json_path).unwrap().trim(), "[]");
+
+        let err = export_problems(&dir.path().join("problems.txt"), &none).unwrap_err();
+        assert!(matches!(err, CliError::InvalidArgument(_)));
+    }
+}
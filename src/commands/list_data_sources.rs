@@ -0,0 +1,66 @@
+use crate::models::DataSource;
+use crate::{client::Client, output, render, Config, Result};
+use tracing::info;
+
+/// Fetch the data sources a deployment can target.
+pub async fn run(client: &Client) -> Result<Vec<DataSource>> {
+    info!("Listing data sources");
+
+    client.list_data_sources().await
+}
+
+pub async fn execute(config: Config, format: Option<String>) -> Result<()> {
+    let client = Client::new(config)?;
+    let mut data_sources = run(&client).await?;
+    data_sources.sort_by_key(|ds| ds.name.to_lowercase());
+
+    if output::is_quiet() {
+        for data_source in &data_sources {
+            println!("{}", data_source.name);
+        }
+        return Ok(());
+    }
+
+    render::renderer(format.as_deref()).render_data_sources(&data_sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CliError;
+    use crate::transport::{MockResponse, MockTransport};
+    use std::sync::Arc;
+
+    fn client_with(responses: Vec<MockResponse>) -> Client {
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        Client::with_transport(config, Arc::new(MockTransport::new(responses))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_lists_data_sources() {
+        let client = client_with(vec![MockResponse::json(
+            200,
+            r#"{"dataSources": [{"uuid": "ds-1", "name": "jdbc/Appian", "type": "MYSQL"}, {"uuid": "ds-2", "name": "jdbc/Reporting"}]}"#,
+        )]);
+
+        let data_sources = run(&client).await.unwrap();
+        assert_eq!(data_sources.len(), 2);
+        assert_eq!(data_sources[0].data_source_type.as_deref(), Some("MYSQL"));
+        assert_eq!(data_sources[1].data_source_type, None);
+    }
+
+    #[tokio::test]
+    async fn test_missing_endpoint_explains_itself() {
+        let client = client_with(vec![MockResponse::json(404, "Not Found")]);
+
+        let err = run(&client).await.unwrap_err();
+        assert!(matches!(err, CliError::Api { status: 404, .. }));
+        assert!(err
+            .to_string()
+            .contains("does not provide the data sources endpoint"));
+    }
+}
@@ -1,108 +1,563 @@
-use crate::{client::Client, Config, Result};
+use crate::error::CliError;
+use crate::models::{LogEntry, LogLevel, LogsResponse};
+use crate::state::{CursorStore, LogCursor};
+use crate::{client::Client, output, polling, render, Config, Result};
+use chrono::{DateTime, Utc};
 use colored::*;
+use std::io::Write;
 use tracing::info;
 
+/// Fetch the logs for a deployment, optionally limited to entries at or after
+/// `since`, at `min_level` or above, and to the last `tail` lines.
+///
+/// Pages are requested by offset until the API reports `hasMore: false`. With
+/// `tail`, the first page is only used to learn the total and the remaining
+/// requests start at the page holding the last `tail` entries.
+pub async fn run(
+    client: &Client,
+    deployment_uuid: &str,
+    tail: Option<usize>,
+    since: Option<DateTime<Utc>>,
+    min_level: Option<LogLevel>,
+) -> Result<LogsResponse> {
+    info!("Fetching logs for deployment: {}", deployment_uuid);
+
+    let first = client.get_deployment_logs(deployment_uuid, 0).await?;
+    let total = first.total;
+    let start = tail.map_or(0, |n| (total.max(0) as usize).saturating_sub(n));
+
+    let mut offset = first.logs.len();
+    let mut has_more = first.has_more;
+    let mut logs = first.logs;
+
+    // Skip the middle pages entirely when only the tail is wanted. A level
+    // filter may leave the tail short, so every page is needed then.
+    if has_more && start > offset && min_level.is_none() {
+        logs.clear();
+        offset = start;
+    }
+
+    while has_more {
+        let page = client.get_deployment_logs(deployment_uuid, offset).await?;
+        // Guard against servers that keep reporting more but send nothing
+        if page.logs.is_empty() {
+            break;
+        }
+        offset += page.logs.len();
+        has_more = page.has_more;
+        logs.extend(page.logs);
+    }
+
+    // Entries are chronological, so filtering after the tail skip still
+    // yields the last `tail` entries within the window
+    if let Some(since) = since {
+        logs.retain(|entry| entry.timestamp >= since);
+    }
+    if let Some(min_level) = min_level {
+        logs.retain(|entry| entry.level.is_at_least(min_level));
+    }
+
+    if let Some(n) = tail {
+        let excess = logs.len().saturating_sub(n);
+        logs.drain(..excess);
+    }
+
+    Ok(LogsResponse {
+        logs,
+        total,
+        has_more: false,
+        next_cursor: None,
+    })
+}
+
+/// Writes log entries to stdout as colored text, or as CSV with a single
+/// header row for `--format csv`.
+enum EntryWriter<W: Write> {
+    Text(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> EntryWriter<W> {
+    fn new(out: W, format: Option<&str>) -> Result<Self> {
+        if format != Some("csv") {
+            return Ok(EntryWriter::Text(out));
+        }
+        let mut writer = csv::Writer::from_writer(out);
+        writer
+            .write_record(["timestamp", "level", "component", "message"])
+            .map_err(std::io::Error::from)?;
+        Ok(EntryWriter::Csv(Box::new(writer)))
+    }
+
+    fn write(&mut self, entry: &LogEntry) -> Result<()> {
+        match self {
+            EntryWriter::Text(out) => {
+                let level_color = match entry.level {
+                    LogLevel::Error => "red",
+                    LogLevel::Warn => "yellow",
+                    LogLevel::Info => "green",
+                    LogLevel::Debug => "blue",
+                };
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    entry
+                        .timestamp
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                        .dimmed(),
+                    format!("[{:5}]", format!("{:?}", entry.level)).color(level_color),
+                    entry.message
+                )?;
+            }
+            EntryWriter::Csv(writer) => {
+                writer
+                    .write_record([
+                        entry.timestamp.to_rfc3339(),
+                        format!("{:?}", entry.level),
+                        entry.component.clone(),
+                        entry.message.clone(),
+                    ])
+                    .map_err(std::io::Error::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Push buffered rows out, e.g. after each batch while following.
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            EntryWriter::Text(out) => out.flush()?,
+            EntryWriter::Csv(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// Drop the entries `cursor` says were printed by an earlier run: those
+/// before its timestamp, and the first ones at it.
+fn after_cursor(logs: &mut Vec<LogEntry>, cursor: &LogCursor) {
+    let mut skip_at_timestamp = cursor.printed_at_timestamp;
+    logs.retain(|entry| {
+        if entry.timestamp == cursor.timestamp && skip_at_timestamp > 0 {
+            skip_at_timestamp -= 1;
+            return false;
+        }
+        entry.timestamp >= cursor.timestamp
+    });
+}
+
+/// The store for `--since-last-run` and the position it holds for
+/// `deployment_uuid`. A position that cannot be read is reported and
+/// ignored, so everything is printed.
+fn last_run(config: &Config, deployment_uuid: &str) -> Result<(CursorStore, Option<LogCursor>)> {
+    let state_dir = config.state_dir().ok_or_else(|| {
+        CliError::Configuration(
+            "--since-last-run needs a state directory; set state_dir in the config".to_string(),
+        )
+    })?;
+    let store = CursorStore::new(&state_dir);
+    let cursor = store.load(deployment_uuid).unwrap_or_else(|e| {
+        output::warning(format!("{}; showing all entries", e));
+        None
+    });
+    Ok((store, cursor))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     config: Config,
     deployment_uuid: String,
     follow: bool,
     tail: Option<usize>,
+    since: Option<String>,
+    since_last_run: bool,
+    min_level: Option<LogLevel>,
+    raw: bool,
     format: Option<String>,
 ) -> Result<()> {
+    render::check_builtin(format.as_deref(), "logs")?;
+    let since = since
+        .map(|value| crate::duration::parse_since(&value, Utc::now()))
+        .transpose()?;
+    let last_run = since_last_run
+        .then(|| last_run(&config, &deployment_uuid))
+        .transpose()?;
     let client = Client::new(config)?;
-    
-    info!("Fetching logs for deployment: {}", deployment_uuid);
-    
+
+    if raw {
+        // Only the first page: following `hasMore` would mean parsing it
+        let path = format!("{}?offset=0", client.endpoints().logs(&deployment_uuid));
+        output::raw(&client.get_raw(&path).await?);
+        return Ok(());
+    }
+
     if follow {
-        println!("{}", "Following logs (streaming)...".yellow());
-        println!("{}", "Press Ctrl+C to stop".dimmed());
-        println!();
-        
+        output::status("Following logs (streaming)...".yellow());
+        output::status("Press Ctrl+C to stop".dimmed());
+        output::status("");
+
         // Stream logs (simplified implementation)
         // In a real implementation, this would use WebSocket or SSE
-        stream_logs(&client, &deployment_uuid, format.clone()).await?;
+        stream_logs(
+            &client,
+            &deployment_uuid,
+            since,
+            min_level,
+            format.as_deref(),
+        )
+        .await?;
     } else {
         // Fetch logs once
-        let response = client.get_deployment_logs(&deployment_uuid, tail).await?;
-        
+        let cursor = last_run.as_ref().and_then(|(_, cursor)| *cursor);
+        let since = cursor.map(|cursor| cursor.timestamp).or(since);
+        let mut response = run(&client, &deployment_uuid, tail, since, min_level).await?;
+        if let Some(cursor) = &cursor {
+            after_cursor(&mut response.logs, cursor);
+        }
+
         match format.as_deref() {
             Some("json") => {
-                let json_output = serde_json::to_string_pretty(&response)?;
-                println!("{}", json_output);
+                output::json(&response)?;
+            }
+            Some("csv") => {
+                let mut writer = EntryWriter::new(std::io::stdout(), Some("csv"))?;
+                for log_entry in &response.logs {
+                    writer.write(log_entry)?;
+                }
+                writer.flush()?;
             }
             _ => {
-                println!("{}", format!("Logs for deployment: {}", deployment_uuid).bold().green());
+                println!(
+                    "{}",
+                    format!("Logs for deployment: {}", deployment_uuid)
+                        .bold()
+                        .green()
+                );
                 println!("Total entries: {}", response.total.to_string().cyan());
                 println!();
-                
+
                 if response.logs.is_empty() {
-                    println!("{}", "No logs found.".yellow());
+                    let message = if cursor.is_some() {
+                        "No new logs since the last run."
+                    } else {
+                        "No logs found."
+                    };
+                    println!("{}", message.yellow());
                 } else {
+                    let mut writer = EntryWriter::new(std::io::stdout(), None)?;
                     for log_entry in &response.logs {
-                        let level_color = match log_entry.level {
-            crate::models::LogLevel::Error => "red",
-            crate::models::LogLevel::Warn => "yellow", 
-            crate::models::LogLevel::Info => "green",
-            crate::models::LogLevel::Debug => "blue",
-        };
-                        
-                        println!(
-                            "{} {} {}",
-                            log_entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string().dimmed(),
-                            format!("[{:5}]", format!("{:?}", log_entry.level)).color(level_color),
-                            log_entry.message
-                        );
+                        writer.write(log_entry)?;
                     }
                 }
             }
         }
+
+        if let Some((store, cursor)) = &last_run {
+            if let Some(next) =
+                LogCursor::advance(*cursor, response.logs.iter().map(|entry| entry.timestamp))
+            {
+                // Best effort: the logs were printed either way
+                if let Err(e) = store.save(&deployment_uuid, &next) {
+                    output::warning(e);
+                }
+            }
+        }
     }
     
     Ok(())
 }
 
+/// Where follow mode resumes: the server's cursor once it has handed one
+/// out, otherwise the number of entries already seen.
+#[derive(Debug, Default)]
+struct LogPosition {
+    seen: usize,
+    cursor: Option<String>,
+}
+
+/// Fetch the entries logged since `position` and advance it past them.
+async fn fetch_new(
+    client: &Client,
+    deployment_uuid: &str,
+    position: &mut LogPosition,
+) -> Result<Vec<LogEntry>> {
+    if let Some(cursor) = position.cursor.take() {
+        let page = client
+            .get_deployment_logs_since(deployment_uuid, &cursor)
+            .await?;
+        if let Some(next) = page.next_cursor {
+            position.seen += page.logs.len();
+            position.cursor = Some(next);
+            return Ok(page.logs);
+        }
+        // Without a cursor in the answer the server ignored ours, and the
+        // page may start anywhere; continue by offset instead
+        info!("Server ignored the log cursor; following by offset");
+    }
+
+    let mut logs = Vec::new();
+    loop {
+        let page = client
+            .get_deployment_logs(deployment_uuid, position.seen + logs.len())
+            .await?;
+        if page.logs.is_empty() {
+            break;
+        }
+        logs.extend(page.logs);
+        if page.next_cursor.is_some() {
+            position.cursor = page.next_cursor;
+        }
+        if !page.has_more {
+            break;
+        }
+    }
+    position.seen += logs.len();
+    Ok(logs)
+}
+
 async fn stream_logs(
     client: &Client,
     deployment_uuid: &str,
-    _format: Option<String>,
+    since: Option<DateTime<Utc>>,
+    min_level: Option<LogLevel>,
+    format: Option<&str>,
 ) -> Result<()> {
-    // Simplified streaming implementation
-    // In a real implementation, this would use WebSocket or Server-Sent Events
-    let mut last_log_count = 0;
-    
+    // Polls for new entries; the API has no push channel
+    let mut position = LogPosition::default();
+    // Created once so CSV gets a single header row
+    let mut writer = EntryWriter::new(std::io::stdout(), format)?;
+    let mut polls = 0;
+
     loop {
-        let response = client.get_deployment_logs(deployment_uuid, None).await?;
-        
-        // Print only new logs
-        let new_logs = &response.logs[last_log_count..];
-        
-        for log_entry in new_logs {
-            let level_color = match log_entry.level {
-                crate::models::LogLevel::Error => "red",
-                crate::models::LogLevel::Warn => "yellow", 
-                crate::models::LogLevel::Info => "green",
-                crate::models::LogLevel::Debug => "blue",
-            };
-            
-            println!(
-                "{} {} {}",
-                log_entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string().dimmed(),
-                format!("[{:5}]", format!("{:?}", log_entry.level)).color(level_color),
-                log_entry.message
-            );
+        let new_logs = fetch_new(client, deployment_uuid, &mut position).await?;
+
+        for log_entry in new_logs
+            .iter()
+            .filter(|entry| since.is_none_or(|s| entry.timestamp >= s))
+            .filter(|entry| min_level.is_none_or(|min| entry.level.is_at_least(min)))
+        {
+            writer.write(log_entry)?;
         }
-        
-        last_log_count = response.logs.len();
-        
+        writer.flush()?;
+
         // Check if deployment is complete
         let status_response = client.get_deployment_status(deployment_uuid).await?;
         if status_response.status.is_terminal() {
-            println!("\n{}", "Deployment completed. Log streaming stopped.".green());
+            output::status(format!(
+                "\n{}",
+                "Deployment completed. Log streaming stopped.".green()
+            ));
             break;
         }
-        
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        polls += 1;
+        polling::check_poll_count(client, &format!("Deployment {}", deployment_uuid), polls)?;
+
+        client.pause(std::time::Duration::from_secs(2)).await?;
     }
-    
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MockResponse, MockTransport};
+    use std::sync::Arc;
+
+    fn log_page(messages: &[&str], total: i32, has_more: bool) -> MockResponse {
+        let logs: Vec<_> = messages
+            .iter()
+            .map(|m| {
+                serde_json::json!({
+                    "timestamp": "2024-01-01T10:00:00Z",
+                    "level": "Info",
+                    "component": "deploy",
+                    "message": m
+                })
+            })
+            .collect();
+        let body = serde_json::json!({ "logs": logs, "total": total, "hasMore": has_more });
+        MockResponse::json(200, &body.to_string())
+    }
+
+    fn client_with(transport: Arc<MockTransport>) -> Client {
+        let config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            timeout_seconds: 30,
+            ..Config::default()
+        };
+        Client::with_transport(config, transport).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_follows_pages() {
+        let transport = Arc::new(MockTransport::new(vec![
+            log_page(&["one", "two"], 3, true),
+            log_page(&["three"], 3, false),
+        ]));
+        let client = client_with(transport.clone());
+
+        let response = run(&client, "dep-1", None, None, None).await.unwrap();
+        let messages: Vec<_> = response.logs.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages, ["one", "two", "three"]);
+        assert!(!response.has_more);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].url.ends_with("/log?offset=0"));
+        assert!(requests[1].url.ends_with("/log?offset=2"));
+    }
+
+    #[tokio::test]
+    async fn test_run_tail_skips_to_last_page() {
+        let transport = Arc::new(MockTransport::new(vec![
+            log_page(&["one", "two"], 5, true),
+            log_page(&["five"], 5, false),
+        ]));
+        let client = client_with(transport.clone());
+
+        let response = run(&client, "dep-1", Some(1), None, None).await.unwrap();
+        assert_eq!(response.logs.len(), 1);
+        assert_eq!(response.logs[0].message, "five");
+        assert!(transport.requests()[1].url.ends_with("/log?offset=4"));
+    }
+
+    #[test]
+    fn test_since_last_run_skips_printed_entries() {
+        let entry = |timestamp: &str, message: &str| LogEntry {
+            timestamp: timestamp.parse().unwrap(),
+            level: LogLevel::Info,
+            component: "deploy".to_string(),
+            message: message.to_string(),
+        };
+        let mut logs = vec![
+            entry("2024-01-01T10:00:00Z", "old"),
+            entry("2024-01-01T10:00:05Z", "printed"),
+            entry("2024-01-01T10:00:05Z", "same second, new"),
+            entry("2024-01-01T10:00:09Z", "new"),
+        ];
+        let cursor = LogCursor {
+            timestamp: "2024-01-01T10:00:05Z".parse().unwrap(),
+            printed_at_timestamp: 1,
+        };
+        after_cursor(&mut logs, &cursor);
+        let messages: Vec<_> = logs.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages, ["same second, new", "new"]);
+
+        // A corrupt position is ignored rather than failing the command
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            state_dir: Some(dir.path().to_path_buf()),
+            ..Config::default()
+        };
+        std::fs::create_dir_all(dir.path().join("logs")).unwrap();
+        std::fs::write(dir.path().join("logs").join("dep-1.json"), "garbage").unwrap();
+        let (store, cursor) = last_run(&config, "dep-1").unwrap();
+        assert!(cursor.is_none());
+        store
+            .save(
+                "dep-1",
+                &LogCursor::advance(None, logs.iter().map(|l| l.timestamp)).unwrap(),
+            )
+            .unwrap();
+        let (_, cursor) = last_run(&config, "dep-1").unwrap();
+        assert_eq!(cursor.unwrap().timestamp, logs[1].timestamp);
+    }
+
+    #[test]
+    fn test_csv_quotes_messages_and_writes_one_header() {
+        let entry = |level, message: &str| LogEntry {
+            timestamp: "2024-01-01T10:00:00Z".parse().unwrap(),
+            level,
+            component: "deploy".to_string(),
+            message: message.to_string(),
+        };
+
+        let mut out = Vec::new();
+        let mut writer = EntryWriter::new(&mut out, Some("csv")).unwrap();
+        writer.write(&entry(LogLevel::Info, "plain")).unwrap();
+        writer.flush().unwrap();
+        writer
+            .write(&entry(LogLevel::Error, "failed, see \"rule\"\nline two"))
+            .unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "timestamp,level,component,message\n\
+             2024-01-01T10:00:00+00:00,Info,deploy,plain\n\
+             2024-01-01T10:00:00+00:00,Error,deploy,\"failed, see \"\"rule\"\"\nline two\"\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_min_level_filters_before_tail() {
+        let page = serde_json::json!({
+            "logs": [
+                {"timestamp": "2024-01-01T10:00:00Z", "level": "Error", "component": "deploy", "message": "bad"},
+                {"timestamp": "2024-01-01T10:00:01Z", "level": "Info", "component": "deploy", "message": "ok"}
+            ],
+            "total": 2,
+            "hasMore": false
+        });
+        let transport = Arc::new(MockTransport::new(vec![MockResponse::json(
+            200,
+            &page.to_string(),
+        )]));
+        let client = client_with(transport);
+
+        let response = run(&client, "dep-1", Some(1), None, Some(LogLevel::Warn))
+            .await
+            .unwrap();
+        let messages: Vec<_> = response.logs.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(messages, ["bad"]);
+    }
+
+    #[tokio::test]
+    async fn test_follow_advances_cursor_or_offset() {
+        let page = |messages: &[&str], cursor: Option<&str>| {
+            let logs: Vec<_> = messages
+                .iter()
+                .map(|m| serde_json::json!({
+                    "timestamp": "2024-01-01T10:00:00Z", "level": "Info", "component": "deploy", "message": m
+                }))
+                .collect();
+            let body = serde_json::json!({ "logs": logs, "total": 0, "hasMore": false, "nextCursor": cursor });
+            MockResponse::json(200, &body.to_string())
+        };
+        let transport = Arc::new(MockTransport::new(vec![
+            page(&["one", "two"], Some("c2")),
+            page(&["three"], Some("c3")),
+            // The cursor is ignored from here on
+            page(&["one", "two", "three", "four"], None),
+            page(&["four"], None),
+        ]));
+        let client = client_with(transport.clone());
+        let mut position = LogPosition::default();
+
+        let messages =
+            |logs: Vec<LogEntry>| logs.into_iter().map(|l| l.message).collect::<Vec<_>>();
+        assert_eq!(
+            messages(fetch_new(&client, "dep-1", &mut position).await.unwrap()),
+            ["one", "two"]
+        );
+        assert_eq!(
+            messages(fetch_new(&client, "dep-1", &mut position).await.unwrap()),
+            ["three"]
+        );
+        assert_eq!(
+            messages(fetch_new(&client, "dep-1", &mut position).await.unwrap()),
+            ["four"]
+        );
+        assert_eq!(position.seen, 4);
+
+        let urls: Vec<String> = transport.requests().into_iter().map(|r| r.url).collect();
+        assert!(urls[0].ends_with("/log?offset=0"));
+        assert!(urls[1].ends_with("/log?cursor=c2"));
+        assert!(urls[2].ends_with("/log?cursor=c3"));
+        assert!(urls[3].ends_with("/log?offset=3"));
+    }
+}
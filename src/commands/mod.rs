@@ -1,15 +1,23 @@
+pub mod capabilities;
+pub mod config;
 #[cfg(feature = "deploy")]
 pub mod deploy;
 #[cfg(feature = "download")]
 pub mod download_package;
 #[cfg(feature = "export")]
 pub mod export;
+#[cfg(all(feature = "export", feature = "monitor", feature = "download"))]
+pub mod export_and_download;
 #[cfg(feature = "get_packages")]
 pub mod get_packages;
 #[cfg(feature = "validate")]
 pub mod inspect;
 #[cfg(feature = "validate")]
+pub mod inspect_local;
+#[cfg(feature = "validate")]
 pub mod inspection_results;
+#[cfg(feature = "deploy")]
+pub mod list_data_sources;
 #[cfg(feature = "logs")]
 pub mod logs;
 #[cfg(feature = "monitor")]
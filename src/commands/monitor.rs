@@ -1,87 +1,743 @@
-use crate::{client::Client, Config, Result};
+use crate::config::NotifyFormat;
+use crate::duration::format_elapsed;
+use crate::error::CliError;
+use crate::models::{DeploymentResults, DeploymentStatus, ExportStatus, StatusReport};
+use crate::{client::Client, output, polling, text, Config, Result};
 use colored::*;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
 use std::time::Duration;
-use tokio::time::sleep;
-use tracing::info;
+use tracing::{debug, info};
 
+/// Poll an export or deployment until it reaches a terminal status or is
+/// left waiting for review.
+///
+/// `on_poll` is called after every poll with the elapsed seconds and the
+/// latest status, so callers can report progress. Returns the final status.
+pub async fn run<F>(
+    client: &Client,
+    deployment_uuid: &str,
+    kind: Option<&str>,
+    interval: Duration,
+    timeout: Duration,
+    on_poll: F,
+) -> Result<StatusReport>
+where
+    F: FnMut(u64, &StatusReport),
+{
+    info!(
+        "Monitoring deployment: {} with interval {}s, timeout {}s",
+        deployment_uuid,
+        interval.as_secs(),
+        timeout.as_secs()
+    );
+
+    // Determine if this is an export or deployment based on kind parameter
+    let operation_type = match kind {
+        Some("export") => "export",
+        Some("deployment") => "deployment",
+        _ => "deployment", // Default to deployment
+    };
+
+    let what = format!("Operation {}", deployment_uuid);
+    let is_done = |report: &StatusReport| report.is_terminal() || report.is_pending_review();
+    let long_poll = client
+        .config()
+        .monitor
+        .long_poll_seconds
+        .map(Duration::from_secs);
+    if let (Some(wait), "export") = (long_poll, operation_type) {
+        let long_poller = LongPoller::new(wait);
+        return polling::poll_until_waited(
+            client,
+            &what,
+            interval,
+            timeout,
+            || async { long_poller.poll(client, deployment_uuid).await },
+            is_done,
+            on_poll,
+        )
+        .await;
+    }
+
+    polling::poll_until(
+        client,
+        &what,
+        interval,
+        timeout,
+        || async {
+            if operation_type == "export" {
+                Ok(StatusReport::Export(
+                    client.get_export_status(deployment_uuid).await?,
+                ))
+            } else {
+                Ok(StatusReport::Deployment(
+                    client.get_deployment_status(deployment_uuid).await?,
+                ))
+            }
+        },
+        is_done,
+        on_poll,
+    )
+    .await
+}
+
+/// Export status polls that let the server hold each request until the
+/// status changes, as long as it does so.
+///
+/// The first poll is a plain one, to learn the current status. After that
+/// a long poll that comes back unchanged in under half of `wait` means the
+/// server ignored the `wait` parameter, and the rest of the wait falls back
+/// to interval polling.
+struct LongPoller {
+    wait: Duration,
+    last: std::sync::Mutex<Option<ExportStatus>>,
+    supported: std::sync::atomic::AtomicBool,
+}
+
+impl LongPoller {
+    fn new(wait: Duration) -> Self {
+        LongPoller {
+            wait,
+            last: std::sync::Mutex::new(None),
+            supported: std::sync::atomic::AtomicBool::new(true),
+        }
+    }
+
+    /// The latest status, and whether the server held the request so the
+    /// next poll may follow without sleeping.
+    async fn poll(&self, client: &Client, export_uuid: &str) -> Result<(StatusReport, bool)> {
+        use std::sync::atomic::Ordering;
+
+        let last = *self.last.lock().unwrap();
+        let (export, waited) = match last {
+            Some(last) if self.supported.load(Ordering::Relaxed) => {
+                let started = tokio::time::Instant::now();
+                let export = client
+                    .get_export_status_waiting(export_uuid, self.wait)
+                    .await?;
+                if export.status == last && started.elapsed() < self.wait / 2 {
+                    info!("Export status long polling is not supported; polling at the interval instead");
+                    self.supported.store(false, Ordering::Relaxed);
+                    (export, false)
+                } else {
+                    (export, true)
+                }
+            }
+            // Learn the current status first, or poll at the interval
+            _ => {
+                let export = client.get_export_status(export_uuid).await?;
+                let waited = last.is_none() && self.supported.load(Ordering::Relaxed);
+                (export, waited)
+            }
+        };
+        *self.last.lock().unwrap() = Some(export.status);
+        Ok((StatusReport::Export(export), waited))
+    }
+}
+
+/// Map a terminal status to the command's result. Only a fully successful
+/// export or deployment is `Ok`; a rollback, a pending review and an export
+/// that completed with errors get their own errors so CI can tell them
+/// apart from a plain failure, unless `fail_on_pending` asks for a pending
+/// review to count as one.
+pub fn check_outcome(
+    deployment_uuid: &str,
+    report: &StatusReport,
+    fail_on_pending: bool,
+) -> Result<()> {
+    match report {
+        StatusReport::Deployment(deployment) => match deployment.status {
+            // Not terminal, so nothing has failed yet
+            DeploymentStatus::Succeeded | DeploymentStatus::InProgress => Ok(()),
+            DeploymentStatus::Failed => Err(CliError::DeploymentFailed(format!(
+                "Deployment {} failed",
+                deployment_uuid
+            ))),
+            DeploymentStatus::RolledBack => Err(CliError::RolledBack(format!(
+                "Deployment {} failed and its changes were rolled back",
+                deployment_uuid
+            ))),
+            DeploymentStatus::PendingReview if fail_on_pending => Err(CliError::DeploymentFailed(format!(
+                "Deployment {} is pending review (--fail-on-pending)",
+                deployment_uuid
+            ))),
+            DeploymentStatus::PendingReview => {
+                let mut message = format!("Deployment {} needs approval before it can continue", deployment_uuid);
+                if let Some(link) = deployment.review_link() {
+                    message.push_str(&format!("; review it at {}", link.url));
+                }
+                Err(CliError::PendingReview(message))
+            }
+        },
+        StatusReport::Export(export) => match export.status {
+            ExportStatus::Completed | ExportStatus::InProgress => Ok(()),
+            // The artifacts exist, so they can still be downloaded
+            ExportStatus::CompletedWithErrors | ExportStatus::CompletedWithExportErrors => {
+                Err(CliError::CompletedWithErrors(format!(
+                    "Export {} completed with errors; its artifacts can still be downloaded with download-package",
+                    deployment_uuid
+                )))
+            }
+            ExportStatus::Failed => Err(CliError::DeploymentFailed(format!(
+                "Export {} failed",
+                deployment_uuid
+            ))),
+            ExportStatus::Canceled => Err(CliError::DeploymentFailed(format!(
+                "Export {} was canceled",
+                deployment_uuid
+            ))),
+        },
+    }
+}
+
+/// Print the final line (and JSON status if requested) for a finished poll
+/// and return the outcome from [`check_outcome`].
+pub fn finish(
+    deployment_uuid: &str,
+    report: &StatusReport,
+    elapsed: u64,
+    fail_on_pending: bool,
+    format: Option<&str>,
+    labels: &BTreeMap<String, String>,
+) -> Result<()> {
+    let outcome = check_outcome(deployment_uuid, report, fail_on_pending);
+    let duration = report
+        .duration_seconds()
+        .map(|seconds| format!(" (total duration {})", format_elapsed(seconds)))
+        .unwrap_or_default();
+
+    output::status(""); // Move to new line
+    let (icon, color) = output::status_style(report);
+    match &outcome {
+        Err(CliError::PendingReview(_)) => {
+            output::status(
+                format!(
+                    "{} Operation {} is awaiting review after {} seconds",
+                    icon, deployment_uuid, elapsed
+                )
+                .color(color)
+                .bold(),
+            );
+            if let StatusReport::Deployment(deployment) = report {
+                for link in &deployment.result_links {
+                    output::status(format!("  {}", link).yellow());
+                }
+            }
+        }
+        Ok(()) => output::status(
+            format!(
+                "{} Operation {} completed after {} seconds{}",
+                icon, deployment_uuid, elapsed, duration
+            )
+            .color(color),
+        ),
+        Err(_) => output::status(
+            format!(
+                "{} Operation {} ended with status {} after {} seconds{}",
+                icon,
+                deployment_uuid,
+                report.status_label(),
+                elapsed,
+                duration
+            )
+            .color(color)
+            .bold(),
+        ),
+    }
+
+    // Print final status
+    if format == Some("json") {
+        let mut value = serde_json::to_value(report)?;
+        if let (false, Some(object)) = (labels.is_empty(), value.as_object_mut()) {
+            object.insert("labels".to_string(), serde_json::json!(labels));
+        }
+        output::json_with_duration(&value, report.duration_seconds())?;
+    }
+
+    outcome
+}
+
+/// When `outcome` is a rollback, fetch the deployment's results and list
+/// what the rollback reverted, so a post-mortem can start from the CLI
+/// output. The error keeps its exit code and gains a one-line summary;
+/// results without rollback details, or that cannot be fetched, are
+/// reported as a rollback with no details. Other outcomes pass through.
+pub async fn explain_rollback(
+    client: &Client,
+    deployment_uuid: &str,
+    outcome: Result<()>,
+) -> Result<()> {
+    let Err(CliError::RolledBack(message)) = outcome else {
+        return outcome;
+    };
+
+    let summary = match client.get_deployment_results(deployment_uuid).await {
+        Ok(DeploymentResults::Import(import)) => import.rollback,
+        Ok(DeploymentResults::Export(_)) => None,
+        Err(e) => {
+            debug!(
+                "Could not fetch rollback details for {}: {}",
+                deployment_uuid, e
+            );
+            None
+        }
+    };
+
+    for line in text::rollback_lines(summary.as_ref()) {
+        output::status(line);
+    }
+    let detail = match &summary {
+        Some(summary) => format!(
+            "{} of {} objects reverted",
+            summary.objects.reverted, summary.objects.total
+        ),
+        None => "no rollback details available".to_string(),
+    };
+    Err(CliError::RolledBack(format!("{} ({})", message, detail)))
+}
+
+/// Tell the configured webhook, if any, that an operation reached a terminal
+/// status, unless `notify.on` excludes this outcome. A failed notification
+/// is only a warning.
+pub async fn notify(
+    client: &Client,
+    deployment_uuid: &str,
+    report: &StatusReport,
+    elapsed: u64,
+    success: bool,
+) {
+    let notify = &client.config().notify;
+    if notify.webhook.is_none() || !notify.on.should_notify(success) {
+        return;
+    }
+
+    let status = match report {
+        StatusReport::Export(export) => output::status_name(&export.status),
+        StatusReport::Deployment(deployment) => output::status_name(&deployment.status),
+    };
+    let payload = notification_payload(notify.format, deployment_uuid, &status, elapsed, success);
+    if let Err(e) = client.post_webhook(&payload).await {
+        output::warning(format!("Webhook notification failed: {}", e));
+    }
+}
+
+/// Webhook body for `format`: the raw summary, a Slack message with a
+/// colored attachment, or a Teams MessageCard with a theme color.
+pub fn notification_payload(
+    format: NotifyFormat,
+    deployment_uuid: &str,
+    status: &str,
+    elapsed: u64,
+    success: bool,
+) -> serde_json::Value {
+    let (mark, color) = if success {
+        ("✅", "2EB886")
+    } else {
+        ("❌", "A30200")
+    };
+    let title = format!(
+        "{} Appian operation {} finished with status {}",
+        mark, deployment_uuid, status
+    );
+    let elapsed_text = format_elapsed(elapsed);
+
+    match format {
+        NotifyFormat::Raw => serde_json::json!({
+            "uuid": deployment_uuid,
+            "status": status,
+            "elapsed_s": elapsed,
+            "success": success,
+        }),
+        NotifyFormat::Slack => serde_json::json!({
+            "text": title,
+            "attachments": [{
+                "color": format!("#{}", color),
+                "blocks": [{
+                    "type": "section",
+                    "fields": [
+                        {"type": "mrkdwn", "text": format!("*Operation*\n`{}`", deployment_uuid)},
+                        {"type": "mrkdwn", "text": format!("*Status*\n{}", status)},
+                        {"type": "mrkdwn", "text": format!("*Elapsed*\n{}", elapsed_text)},
+                    ],
+                }],
+            }],
+        }),
+        NotifyFormat::Teams => serde_json::json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": title,
+            "themeColor": color,
+            "title": title,
+            "sections": [{
+                "facts": [
+                    {"name": "Operation", "value": deployment_uuid},
+                    {"name": "Status", "value": status},
+                    {"name": "Elapsed", "value": elapsed_text},
+                ],
+            }],
+        }),
+    }
+}
+
+/// Prometheus text-format metrics for a monitor after `polls` polls, for a
+/// node exporter textfile collector. The status is a gauge set to 1 with the
+/// current status as a label.
+pub fn render_metrics(
+    deployment_uuid: &str,
+    kind: &str,
+    polls: u64,
+    report: &StatusReport,
+    elapsed: u64,
+) -> String {
+    let status = match report {
+        StatusReport::Export(export) => output::status_name(&export.status),
+        StatusReport::Deployment(deployment) => output::status_name(&deployment.status),
+    };
+    let labels = format!("operation=\"{}\",kind=\"{}\"", deployment_uuid, kind);
+
+    let mut metrics = String::new();
+    let _ = writeln!(
+        metrics,
+        "# HELP appian_monitor_polls_total Status polls performed by the monitor."
+    );
+    let _ = writeln!(metrics, "# TYPE appian_monitor_polls_total counter");
+    let _ = writeln!(
+        metrics,
+        "appian_monitor_polls_total{{{}}} {}",
+        labels, polls
+    );
+    let _ = writeln!(metrics, "# HELP appian_monitor_status Current status of the monitored operation (1 for the status label).");
+    let _ = writeln!(metrics, "# TYPE appian_monitor_status gauge");
+    let _ = writeln!(
+        metrics,
+        "appian_monitor_status{{{},status=\"{}\"}} 1",
+        labels, status
+    );
+    let _ = writeln!(
+        metrics,
+        "# HELP appian_monitor_elapsed_seconds Seconds since the monitor started."
+    );
+    let _ = writeln!(metrics, "# TYPE appian_monitor_elapsed_seconds gauge");
+    let _ = writeln!(
+        metrics,
+        "appian_monitor_elapsed_seconds{{{}}} {}",
+        labels, elapsed
+    );
+    metrics
+}
+
+/// Replace `path` with `contents` atomically: the file is written next to
+/// it and renamed into place, so a scraper never reads a partial file.
+pub fn write_metrics(path: &Path, contents: &str) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        CliError::InvalidArgument(format!("Invalid --metrics-file: {}", path.display()))
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    std::fs::write(&temp_path, contents)
+        .and_then(|()| std::fs::rename(&temp_path, path))
+        .map_err(|e| {
+            CliError::FileSystem(format!(
+                "Failed to write metrics to {}: {}",
+                path.display(),
+                e
+            ))
+        })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn execute(
     config: Config,
     deployment_uuid: String,
     kind: Option<String>,
-    interval: u64,
-    timeout: u64,
+    interval: Duration,
+    timeout: Duration,
+    fail_on_pending: bool,
+    metrics_file: Option<std::path::PathBuf>,
     format: Option<String>,
 ) -> Result<()> {
     let client = Client::new(config)?;
-    
-    info!("Monitoring deployment: {} with interval {}s, timeout {}s", deployment_uuid, interval, timeout);
-    
-    // Determine if this is an export or deployment based on kind parameter
-    let operation_type = match kind.as_deref() {
-        Some("export") => "export",
-        Some("deployment") => "deployment",
-        _ => "deployment", // Default to deployment
+
+    let operation_type = if kind.as_deref() == Some("export") {
+        "export"
+    } else {
+        "deployment"
     };
+    output::status(
+        format!(
+            "Monitoring {} operation: {}",
+            operation_type, deployment_uuid
+        )
+        .bold()
+        .cyan(),
+    );
+    output::status(
+        format!(
+            "Interval: {}, Timeout: {}",
+            format_elapsed(interval.as_secs()),
+            format_elapsed(timeout.as_secs())
+        )
+        .dimmed(),
+    );
+    output::status("");
+
+    let mut elapsed = 0;
+    let mut polls = 0;
+    let report = run(
+        &client,
+        &deployment_uuid,
+        kind.as_deref(),
+        interval,
+        timeout,
+        |secs, report| {
+            elapsed = secs;
+            polls += 1;
+            output::progress(format!("[{:4}s] Status: {}", secs, report.status_label()).dimmed());
+            if let Some(path) = &metrics_file {
+                // A failed write must not end the monitor
+                let metrics = render_metrics(&deployment_uuid, operation_type, polls, report, secs);
+                if let Err(e) = write_metrics(path, &metrics) {
+                    output::warning(e);
+                }
+            }
+        },
+    )
+    .await?;
 
-    let start_time = std::time::Instant::now();
-    let timeout_duration = Duration::from_secs(timeout);
-    let interval_duration = Duration::from_secs(interval);
+    let outcome = finish(
+        &deployment_uuid,
+        &report,
+        elapsed,
+        fail_on_pending,
+        format.as_deref(),
+        &BTreeMap::new(),
+    );
+    let outcome = explain_rollback(&client, &deployment_uuid, outcome).await;
+    notify(&client, &deployment_uuid, &report, elapsed, outcome.is_ok()).await;
+    outcome
+}
 
-    println!("{}", format!("Monitoring {} operation: {}", operation_type, deployment_uuid).bold().cyan());
-    println!("{}", format!("Interval: {}s, Timeout: {}s", interval, timeout).dimmed());
-    println!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DeploymentStatusResponse, ResultLink};
 
-    loop {
-        if start_time.elapsed() > timeout_duration {
-            return Err(crate::error::CliError::Timeout(format!(
-                "Operation {} did not complete within {} seconds",
-                deployment_uuid, timeout
-            )));
-        }
+    fn deployment(status: DeploymentStatus) -> StatusReport {
+        StatusReport::Deployment(DeploymentStatusResponse {
+            deployment_id: uuid::Uuid::nil(),
+            status,
+            current_step: None,
+            result_links: vec![],
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        })
+    }
 
-        // Get current status
-        let status = if operation_type == "export" {
-            let export_response = client.get_export_status(&deployment_uuid).await?;
-            format!("{:?}", export_response.status)
-        } else {
-            let deployment_response = client.get_deployment_status(&deployment_uuid).await?;
-            format!("{:?}", deployment_response.status)
-        };
+    #[tokio::test(start_paused = true)]
+    async fn test_long_poll_falls_back_when_the_server_answers_at_once() {
+        use crate::transport::{MockResponse, MockTransport};
 
-        let elapsed = start_time.elapsed().as_secs();
-        print!("\r{}", format!("[{:4}s] Status: {}", elapsed, status).dimmed());
-        
-        // Check if operation is complete
-        let is_complete = if operation_type == "export" {
-            let export_response = client.get_export_status(&deployment_uuid).await?;
-            export_response.status.is_terminal()
-        } else {
-            let deployment_response = client.get_deployment_status(&deployment_uuid).await?;
-            deployment_response.status.is_terminal()
+        let mut config = Config {
+            base_url: "https://example.appian.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
         };
+        config.monitor.long_poll_seconds = Some(20);
+        let running = MockResponse::json(200, r#"{"status": "IN_PROGRESS"}"#);
+        let transport = std::sync::Arc::new(MockTransport::new(vec![
+            running.clone(),
+            running.clone(),
+            running,
+            MockResponse::json(200, r#"{"status": "COMPLETED"}"#),
+        ]));
+        let client = Client::with_transport(config, transport.clone()).unwrap();
 
-        if is_complete {
-            println!(); // Move to new line
-            println!("{}", format!("✓ Operation {} completed after {} seconds", deployment_uuid, elapsed).green());
-            
-            // Print final status
-            if format.as_deref() == Some("json") {
-                if operation_type == "export" {
-                    let export_response = client.get_export_status(&deployment_uuid).await?;
-                    let json_output = serde_json::to_string_pretty(&export_response)?;
-                    println!("{}", json_output);
-                } else {
-                    let deployment_response = client.get_deployment_status(&deployment_uuid).await?;
-                    let json_output = serde_json::to_string_pretty(&deployment_response)?;
-                    println!("{}", json_output);
+        let started = tokio::time::Instant::now();
+        let report = run(
+            &client,
+            "e1",
+            Some("export"),
+            Duration::from_secs(5),
+            Duration::from_secs(60),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert!(report.is_terminal());
+
+        let urls: Vec<String> = transport.requests().iter().map(|r| r.url.clone()).collect();
+        assert_eq!(urls.len(), 4);
+        assert!(!urls[0].contains("wait="));
+        // The unchanged answer to the long poll came back at once...
+        assert!(urls[1].ends_with("?wait=20"), "{}", urls[1]);
+        // ...so the remaining polls are plain ones, an interval apart
+        assert!(!urls[2].contains("wait=") && !urls[3].contains("wait="));
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_check_outcome_exit_codes() {
+        assert!(check_outcome("d", &deployment(DeploymentStatus::Succeeded), false).is_ok());
+
+        let failed = check_outcome("d", &deployment(DeploymentStatus::Failed), false).unwrap_err();
+        assert!(matches!(failed, CliError::DeploymentFailed(_)));
+        assert_eq!(failed.exit_code(), 5);
+
+        let rolled_back =
+            check_outcome("d", &deployment(DeploymentStatus::RolledBack), false).unwrap_err();
+        assert!(matches!(rolled_back, CliError::RolledBack(_)));
+        assert_eq!(rolled_back.exit_code(), 7);
+
+        let mut pending = deployment(DeploymentStatus::PendingReview);
+        if let StatusReport::Deployment(d) = &mut pending {
+            d.result_links = vec![
+                ResultLink::from_url("https://example.com/deployments/1/log".to_string()),
+                ResultLink::from_url("https://example.com/review/1".to_string()),
+            ];
+        }
+        let err = check_outcome("d", &pending, false).unwrap_err();
+        assert_eq!(err.exit_code(), 8);
+        assert!(err.to_string().contains("https://example.com/review/1"));
+        let err = check_outcome("d", &pending, true).unwrap_err();
+        assert!(matches!(err, CliError::DeploymentFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_explain_rollback_lists_reverted_objects() {
+        use crate::transport::{MockResponse, MockTransport};
+        use std::sync::Arc;
+
+        let results = serde_json::json!({
+            "status": "FAILED",
+            "summary": {
+                "databaseScripts": 0,
+                "adminConsoleSettings": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                "plugins": {"total": 0, "imported": 0, "skipped": 0},
+                "objects": {"total": 3, "imported": 2, "failed": 1, "skipped": 0},
+                "deploymentLogUrl": "https://example.com/log"
+            },
+            "rollback": {
+                "objects": {
+                    "total": 3,
+                    "reverted": 2,
+                    "failed": 0,
+                    "revertedObjects": [{"objectName": "AS_Home", "objectUuid": "o-1"}]
                 }
             }
-            
-            break;
-        }
+        });
+        let transport = Arc::new(MockTransport::new(vec![
+            MockResponse::json(200, &results.to_string()),
+            MockResponse::json(404, "not found"),
+        ]));
+        let config = Config {
+            base_url: "https://test.example.com".to_string(),
+            api_key: "k".to_string(),
+            ..Config::default()
+        };
+        let client = Client::with_transport(config, transport).unwrap();
+
+        let rolled_back = || check_outcome("d", &deployment(DeploymentStatus::RolledBack), false);
+        let err = explain_rollback(&client, "d", rolled_back())
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 7);
+        assert!(err.to_string().contains("(2 of 3 objects reverted)"));
+
+        let err = explain_rollback(&client, "d", rolled_back())
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 7);
+        assert!(err.to_string().contains("no rollback details available"));
 
-        sleep(interval_duration).await;
+        let failed = check_outcome("d", &deployment(DeploymentStatus::Failed), false);
+        assert_eq!(
+            explain_rollback(&client, "d", failed)
+                .await
+                .unwrap_err()
+                .exit_code(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_notification_payloads() {
+        let raw = notification_payload(NotifyFormat::Raw, "d", "SUCCEEDED", 75, true);
+        assert_eq!(
+            raw,
+            serde_json::json!({"uuid": "d", "status": "SUCCEEDED", "elapsed_s": 75, "success": true})
+        );
+
+        let slack = notification_payload(NotifyFormat::Slack, "d", "FAILED", 75, false);
+        assert!(slack["text"]
+            .as_str()
+            .unwrap()
+            .contains("finished with status FAILED"));
+        assert_eq!(slack["attachments"][0]["color"], "#A30200");
+        assert_eq!(
+            slack["attachments"][0]["blocks"][0]["fields"][1]["text"],
+            "*Status*\nFAILED"
+        );
+
+        let teams = notification_payload(NotifyFormat::Teams, "d", "SUCCEEDED", 75, true);
+        assert_eq!(teams["@type"], "MessageCard");
+        assert_eq!(teams["themeColor"], "2EB886");
+        assert_eq!(teams["sections"][0]["facts"][0]["value"], "d");
+    }
+
+    #[test]
+    fn test_metrics_file() {
+        let metrics = render_metrics(
+            "d",
+            "deployment",
+            3,
+            &deployment(DeploymentStatus::InProgress),
+            20,
+        );
+        assert!(metrics.contains("# TYPE appian_monitor_polls_total counter\n"));
+        assert!(
+            metrics.contains("appian_monitor_polls_total{operation=\"d\",kind=\"deployment\"} 3\n")
+        );
+        assert!(metrics.contains(
+            "appian_monitor_status{operation=\"d\",kind=\"deployment\",status=\"IN_PROGRESS\"} 1\n"
+        ));
+        assert!(metrics
+            .contains("appian_monitor_elapsed_seconds{operation=\"d\",kind=\"deployment\"} 20\n"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("appian.prom");
+        write_metrics(&path, "first\n").unwrap();
+        write_metrics(&path, &metrics).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), metrics);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_check_outcome_export_with_errors() {
+        let export = |status| {
+            StatusReport::Export(
+                serde_json::from_value(serde_json::json!({ "status": status })).unwrap(),
+            )
+        };
+
+        assert!(check_outcome("e", &export(ExportStatus::Completed), false).is_ok());
+        for status in [
+            ExportStatus::CompletedWithErrors,
+            ExportStatus::CompletedWithExportErrors,
+        ] {
+            let err = check_outcome("e", &export(status), false).unwrap_err();
+            assert!(matches!(err, CliError::CompletedWithErrors(_)));
+            assert_eq!(err.exit_code(), 9);
+        }
+        assert_eq!(
+            check_outcome("e", &export(ExportStatus::Failed), false)
+                .unwrap_err()
+                .exit_code(),
+            5
+        );
     }
-    
-    Ok(())
-}
\ No newline at end of file
+}
@@ -1,22 +1,106 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub base_url: String,
+    #[serde(default)]
     pub api_key: String,
     pub timeout_seconds: u64,
-    
+
+    /// Shell command whose output is the API key, e.g. a secret manager
+    /// lookup. Only run when no `api_key` is set.
+    #[serde(default)]
+    pub api_key_command: Option<String>,
+
+    /// How long `api_key_command` may run before it is killed
+    #[serde(default = "default_api_key_command_timeout")]
+    pub api_key_command_timeout_seconds: u64,
+
     #[serde(default)]
     pub logging: LoggingConfig,
-    
+
     #[serde(default)]
     pub download: DownloadConfig,
-    
+
     #[serde(default)]
     pub monitor: MonitorConfig,
+
+    #[serde(default)]
+    pub notify: NotifyConfig,
+
+    #[serde(default)]
+    pub http: HttpConfig,
+
+    /// JSON-lines file each command appends a record of its outcome to
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+
+    /// Directory for state kept between runs, such as the position of
+    /// `logs --since-last-run`; see [`Config::state_dir`]
+    #[serde(default)]
+    pub state_dir: Option<PathBuf>,
+
+    /// Upper bound on the combined size of files attached to a deploy or
+    /// inspect upload. Unlimited when unset.
+    #[serde(default)]
+    pub max_upload_bytes: Option<u64>,
+
+    /// Wall-clock budget for a whole command, covering every request,
+    /// retry and poll. Unlimited when unset.
+    #[serde(default)]
+    pub deadline_seconds: Option<u64>,
+
+    /// Bound on a single request attempt, after which it is cancelled and
+    /// retried like a connection failure. Unlimited when unset.
+    #[serde(default)]
+    pub attempt_timeout_seconds: Option<u64>,
+
+    /// Most requests sent per second, shared by concurrent requests such as
+    /// bulk exports; `0` for no limit
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit_per_second: u32,
+
+    /// Extra headers sent with every request, e.g. for a gateway that
+    /// routes on `X-Tenant`
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Let `headers` replace `Authorization` / `appian-api-key`
+    #[serde(default)]
+    pub allow_auth_header_override: bool,
+
+    /// User-Agent sent with every request; see [`Config::user_agent`]
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Short name for the Appian site, shown in output; see
+    /// [`Config::environment_name`]
+    #[serde(default)]
+    pub environment: Option<String>,
+
+    /// Root of the `/deployment/v2` endpoints (deployment status, logs,
+    /// artifacts, packages), for gateways that mount the API elsewhere
+    #[serde(default = "default_deployment_api_path")]
+    pub deployment_api_path: String,
+
+    /// Root of the `/suite/deployment-management/v2` endpoints (exports,
+    /// results, inspections, data sources)
+    #[serde(default = "default_deployment_mgmt_api_path")]
+    pub deployment_mgmt_api_path: String,
+
+    /// Answer requests from saved responses in this file instead of the
+    /// network; only set by the hidden `--replay-file` flag
+    #[serde(skip)]
+    pub replay_file: Option<PathBuf>,
+
+    /// Write the body of each successful response to this file before it is
+    /// parsed; only set by `--save-response`
+    #[serde(skip)]
+    pub save_response: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +110,10 @@ pub struct LoggingConfig {
     
     #[serde(default)]
     pub json: bool,
+
+    /// Log redacted HTTP request/response bodies at debug level
+    #[serde(default)]
+    pub trace_http: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,15 +132,146 @@ pub struct MonitorConfig {
     
     #[serde(default = "default_jitter")]
     pub jitter: bool,
-    
+
+    /// Retries for a request that failed to connect, timed out or got a
+    /// response whose status is listed in `retry_on`
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Retries after a failed connection or a status in `retry_on`;
+    /// `max_retries` when unset
+    #[serde(default)]
+    pub max_retries_transient: Option<u32>,
+
+    /// Retries after a request timed out; `max_retries` when unset. A timed
+    /// out upload may still be running on the server, so `0` avoids
+    /// sending it twice.
+    #[serde(default)]
+    pub max_retries_timeout: Option<u32>,
+
+    /// Response statuses treated as transient; anything else fails at once
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<u16>,
+
+    /// Polls a wait may make before giving up, on top of its timeout.
+    /// Unlimited when unset.
+    #[serde(default)]
+    pub max_polls: Option<u32>,
+
+    /// Seconds an export status poll asks the server to hold the request
+    /// until the status changes (a long poll). Interval polling when unset.
+    #[serde(default)]
+    pub long_poll_seconds: Option<u64>,
+
     #[serde(default = "default_logs_follow")]
     pub logs_follow_default: bool,
 }
 
+/// Connection pooling, for commands that send many requests to the same
+/// host (polling, bulk exports)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// How long an idle connection is kept for reuse
+    #[serde(default = "default_pool_idle_timeout")]
+    pub pool_idle_timeout_seconds: u64,
+
+    /// Most idle connections kept per host; `0` opens a new connection for
+    /// every request
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+
+    /// Interval of TCP keepalive probes on open connections; `0` to send
+    /// none
+    #[serde(default = "default_tcp_keepalive")]
+    pub tcp_keepalive_seconds: u64,
+
+    /// Speak HTTP/2 from the first request instead of negotiating it, for
+    /// servers or gateways known to support it (including over plain http)
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+}
+
+/// Webhook called when a monitored or waited-on deployment finishes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default)]
+    pub webhook: Option<String>,
+
+    #[serde(default)]
+    pub on: NotifyOn,
+
+    #[serde(default)]
+    pub format: NotifyFormat,
+}
+
+/// Which outcomes trigger the webhook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+    #[default]
+    Always,
+    Failure,
+}
+
+impl NotifyOn {
+    pub fn should_notify(&self, success: bool) -> bool {
+        match self {
+            NotifyOn::Always => true,
+            NotifyOn::Failure => !success,
+        }
+    }
+}
+
+/// Payload shape sent to the webhook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyFormat {
+    /// `{uuid, status, elapsed_s, success}`, for generic endpoints
+    #[default]
+    Raw,
+    /// Slack incoming webhook message
+    Slack,
+    /// Microsoft Teams MessageCard
+    Teams,
+}
+
+impl std::str::FromStr for NotifyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "raw" => Ok(NotifyFormat::Raw),
+            "slack" => Ok(NotifyFormat::Slack),
+            "teams" => Ok(NotifyFormat::Teams),
+            _ => anyhow::bail!("expected 'raw', 'slack' or 'teams', not '{}'", value),
+        }
+    }
+}
+
+impl std::str::FromStr for NotifyOn {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "always" => Ok(NotifyOn::Always),
+            "failure" => Ok(NotifyOn::Failure),
+            _ => anyhow::bail!("expected 'always' or 'failure', not '{}'", value),
+        }
+    }
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
 
+fn default_deployment_api_path() -> String {
+    "/deployment/v2".to_string()
+}
+
+fn default_deployment_mgmt_api_path() -> String {
+    "/suite/deployment-management/v2".to_string()
+}
+
 fn default_download_dir() -> PathBuf {
     PathBuf::from(".")
 }
@@ -69,42 +288,196 @@ fn default_jitter() -> bool {
     true
 }
 
+fn default_rate_limit() -> u32 {
+    10
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_on() -> Vec<u16> {
+    vec![429, 502, 503, 504]
+}
+
+fn default_pool_idle_timeout() -> u64 {
+    90
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    8
+}
+
+fn default_tcp_keepalive() -> u64 {
+    60
+}
+
 fn default_logs_follow() -> bool {
     false
 }
 
+fn default_api_key_command_timeout() -> u64 {
+    10
+}
+
+/// Most output accepted from `api_key_command`; an API key is far smaller
+const API_KEY_COMMAND_MAX_OUTPUT: u64 = 64 * 1024;
+
+/// How much of the command's stderr is kept for the error message
+const API_KEY_COMMAND_MAX_STDERR: u64 = 4 * 1024;
+
+const CONFIG_FILE_NAME: &str = "appian-config.toml";
+
+/// Headers the client sets for authentication
+const AUTH_HEADERS: [&str; 2] = ["authorization", "appian-api-key"];
+
 #[derive(Debug, Clone)]
 pub struct CliOverrides {
     pub base_url: Option<String>,
     pub api_key: Option<String>,
+    pub trace_http: bool,
+    pub max_upload_bytes: Option<u64>,
+    pub deadline_seconds: Option<u64>,
+    pub attempt_timeout_seconds: Option<u64>,
+    pub rate_limit_per_second: Option<u32>,
+    pub retry_on: Option<Vec<u16>>,
+    pub max_polls: Option<u32>,
+    pub long_poll_seconds: Option<u64>,
+    pub api_timeout_retries: Option<u32>,
+    pub headers: Vec<(String, String)>,
+    pub allow_auth_header_override: bool,
+    pub user_agent: Option<String>,
+    pub notify_webhook: Option<String>,
+    pub notify_on: Option<NotifyOn>,
+    pub notify_format: Option<NotifyFormat>,
+    pub replay_file: Option<PathBuf>,
+    pub save_response: Option<PathBuf>,
 }
 
+/// A problem found while checking a configuration, located by the dotted
+/// path of the field it concerns (empty when it concerns a whole file)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigProblem {
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigProblem {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        ConfigProblem {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// User-Agent used unless `user_agent` is configured, so Appian access logs
+/// can attribute requests to the CLI
+pub const DEFAULT_USER_AGENT: &str = concat!("appian-deployment-cli/", env!("CARGO_PKG_VERSION"));
+
 impl Config {
-    pub fn load(config_file: Option<PathBuf>, cli_overrides: &CliOverrides) -> Result<Self> {
-        let mut config = if let Some(config_path) = config_file {
-            Self::from_file(&config_path)?
-        } else if Path::new("appian-config.toml").exists() {
-            Self::from_file(Path::new("appian-config.toml"))?
+    /// Load configuration with precedence, lowest first: config files in the
+    /// order given (later files overlay earlier ones), environment variables,
+    /// then CLI flags. With no files given, the nearest `appian-config.toml`
+    /// is used if one exists.
+    pub fn load(config_files: &[PathBuf], cli_overrides: &CliOverrides) -> Result<Self> {
+        let files: Vec<PathBuf> = if config_files.is_empty() {
+            std::env::current_dir()
+                .ok()
+                .and_then(|dir| find_config_file(&dir))
+                .into_iter()
+                .collect()
         } else {
+            config_files.to_vec()
+        };
+
+        let mut config = if files.is_empty() {
             Self::from_env()?
+        } else {
+            let mut config = Self::from_files(&files)?;
+            config.apply_env_overrides();
+            config
         };
 
         config.apply_cli_overrides(cli_overrides);
+        config.resolve_api_key()?;
         config.validate()?;
         
-        debug!("Loaded configuration: {:?}", config);
+        debug!("Loaded configuration: {:?}", config.redacted());
+        Ok(config)
+    }
+
+    /// Deep-merge the files in order, so an overlay only needs the keys it
+    /// changes, including individual keys of `[logging]`, `[download]` and
+    /// `[monitor]`.
+    fn from_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut merged = toml::Value::Table(toml::map::Map::new());
+        for path in paths {
+            merge_values(&mut merged, Self::read_value(path)?);
+        }
+
+        let config: Config = merged.try_into().with_context(|| {
+            let names: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+            format!("Failed to parse config file(s): {}", names.join(", "))
+        })?;
+
         Ok(config)
     }
 
-    fn from_file(path: &Path) -> Result<Self> {
+    /// Check config files without contacting the server or reading the
+    /// environment's overrides, reporting every problem found. Unset
+    /// `${VAR}` references are left in place rather than reported, since
+    /// they are normally only set where the CLI runs.
+    pub fn check_files(paths: &[PathBuf]) -> Vec<ConfigProblem> {
+        let keep_unset = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .or_else(|| Some(format!("${{{}}}", name)))
+        };
+
+        let mut problems = Vec::new();
+        let mut merged = toml::Value::Table(toml::map::Map::new());
+        for path in paths {
+            match Self::read_value_with(path, &keep_unset) {
+                Ok(value) => merge_values(&mut merged, value),
+                Err(e) => problems.push(ConfigProblem::new("", format!("{:#}", e))),
+            }
+        }
+        if !problems.is_empty() {
+            return problems;
+        }
+
+        match merged.try_into::<Config>() {
+            Ok(config) => config.problems(),
+            Err(e) => vec![deserialize_problem(&e.to_string())],
+        }
+    }
+
+    fn read_value(path: &Path) -> Result<toml::Value> {
+        Self::read_value_with(path, &|name| std::env::var(name).ok())
+    }
+
+    fn read_value_with(
+        path: &Path,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<toml::Value> {
         info!("Loading configuration from: {}", path.display());
         let contents = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
-        
-        let config: Config = toml::from_str(&contents)
+
+        let mut value: toml::Value = toml::from_str(&contents)
             .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
-        
-        Ok(config)
+        interpolate_value(&mut value, lookup).with_context(|| {
+            format!(
+                "Failed to resolve variables in config file: {}",
+                path.display()
+            )
+        })?;
+        if let Some(dir) = path.parent() {
+            resolve_relative_paths(&mut value, dir);
+        }
+
+        Ok(value)
     }
 
     fn from_env() -> Result<Self> {
@@ -125,12 +498,27 @@ impl Config {
             base_url,
             api_key,
             timeout_seconds,
-            logging: LoggingConfig::default(),
-            download: DownloadConfig::default(),
-            monitor: MonitorConfig::default(),
+            ..Config::default()
         })
     }
 
+    fn apply_env_overrides(&mut self) {
+        if let Ok(base_url) = std::env::var("APPIAN_BASE_URL") {
+            self.base_url = base_url;
+        }
+
+        if let Ok(api_key) = std::env::var("APPIAN_API_KEY") {
+            self.api_key = api_key;
+        }
+
+        if let Some(timeout) = std::env::var("APPIAN_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+        {
+            self.timeout_seconds = timeout;
+        }
+    }
+
     fn apply_cli_overrides(&mut self, cli: &CliOverrides) {
         if let Some(base_url) = &cli.base_url {
             self.base_url = base_url.clone();
@@ -139,62 +527,648 @@ impl Config {
         if let Some(api_key) = &cli.api_key {
             self.api_key = api_key.clone();
         }
+
+        if cli.trace_http {
+            self.logging.trace_http = true;
+        }
+
+        if cli.max_upload_bytes.is_some() {
+            self.max_upload_bytes = cli.max_upload_bytes;
+        }
+
+        if cli.deadline_seconds.is_some() {
+            self.deadline_seconds = cli.deadline_seconds;
+        }
+
+        if cli.attempt_timeout_seconds.is_some() {
+            self.attempt_timeout_seconds = cli.attempt_timeout_seconds;
+        }
+
+        if let Some(rate_limit) = cli.rate_limit_per_second {
+            self.rate_limit_per_second = rate_limit;
+        }
+
+        if let Some(retry_on) = &cli.retry_on {
+            self.monitor.retry_on = retry_on.clone();
+        }
+
+        if cli.max_polls.is_some() {
+            self.monitor.max_polls = cli.max_polls;
+        }
+
+        if cli.long_poll_seconds.is_some() {
+            self.monitor.long_poll_seconds = cli.long_poll_seconds;
+        }
+
+        if cli.api_timeout_retries.is_some() {
+            self.monitor.max_retries_timeout = cli.api_timeout_retries;
+        }
+
+        for (name, value) in &cli.headers {
+            // Header names are case-insensitive, so a flag replaces a config
+            // entry spelled differently
+            self.headers
+                .retain(|existing, _| !existing.eq_ignore_ascii_case(name));
+            self.headers.insert(name.clone(), value.clone());
+        }
+
+        if cli.allow_auth_header_override {
+            self.allow_auth_header_override = true;
+        }
+
+        if cli.user_agent.is_some() {
+            self.user_agent = cli.user_agent.clone();
+        }
+
+        if cli.notify_webhook.is_some() {
+            self.notify.webhook = cli.notify_webhook.clone();
+        }
+
+        if let Some(on) = cli.notify_on {
+            self.notify.on = on;
+        }
+
+        if let Some(format) = cli.notify_format {
+            self.notify.format = format;
+        }
+
+        if cli.replay_file.is_some() {
+            self.replay_file = cli.replay_file.clone();
+            // Nothing is sent, so a replay needs no real credentials
+            if self.api_key.is_empty() && self.api_key_command.is_none() {
+                self.api_key = "replay".to_string();
+            }
+        }
+
+        if cli.save_response.is_some() {
+            self.save_response = cli.save_response.clone();
+        }
+    }
+
+    /// Fill in an unset `api_key` from `api_key_command`.
+    fn resolve_api_key(&mut self) -> Result<()> {
+        if let (true, Some(command)) = (self.api_key.is_empty(), &self.api_key_command) {
+            let timeout = std::time::Duration::from_secs(self.api_key_command_timeout_seconds);
+            self.api_key = run_api_key_command(command, timeout, API_KEY_COMMAND_MAX_OUTPUT)?;
+        }
+        Ok(())
     }
 
     fn validate(&self) -> Result<()> {
+        match self.problems().into_iter().next() {
+            Some(problem) => anyhow::bail!("{}", problem.message),
+            None => Ok(()),
+        }
+    }
+
+    /// Every problem with the configured values, including ones that only
+    /// show up across fields. The API key is only checked for presence.
+    pub fn problems(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+
         if self.base_url.is_empty() {
-            anyhow::bail!("base_url cannot be empty");
+            problems.push(ConfigProblem::new("base_url", "base_url cannot be empty"));
+        } else if !self.base_url.contains("${") {
+            match url::Url::parse(&self.base_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(url) => problems.push(ConfigProblem::new(
+                    "base_url",
+                    format!("base_url must use http or https, not '{}'", url.scheme()),
+                )),
+                Err(e) => problems.push(ConfigProblem::new(
+                    "base_url",
+                    format!("base_url '{}' is not a valid URL: {}", self.base_url, e),
+                )),
+            }
         }
 
-        if self.api_key.is_empty() {
-            anyhow::bail!("api_key cannot be empty");
+        if self.api_key.is_empty() && self.api_key_command.is_none() {
+            problems.push(ConfigProblem::new("api_key", "api_key cannot be empty"));
+        }
+        if self
+            .api_key_command
+            .as_deref()
+            .is_some_and(|c| c.trim().is_empty())
+        {
+            problems.push(ConfigProblem::new(
+                "api_key_command",
+                "api_key_command cannot be empty",
+            ));
+        }
+        if self.api_key_command.is_some() && self.api_key_command_timeout_seconds == 0 {
+            problems.push(ConfigProblem::new(
+                "api_key_command_timeout_seconds",
+                "api_key_command_timeout_seconds must be greater than 0",
+            ));
         }
 
         if self.timeout_seconds == 0 {
-            anyhow::bail!("timeout_seconds must be greater than 0");
+            problems.push(ConfigProblem::new(
+                "timeout_seconds",
+                "timeout_seconds must be greater than 0",
+            ));
+        }
+        if self.attempt_timeout_seconds == Some(0) {
+            problems.push(ConfigProblem::new(
+                "attempt_timeout_seconds",
+                "attempt_timeout_seconds must be greater than 0",
+            ));
         }
 
-        Ok(())
+        if self.http.pool_idle_timeout_seconds == 0 {
+            problems.push(ConfigProblem::new(
+                "http.pool_idle_timeout_seconds",
+                "http.pool_idle_timeout_seconds must be greater than 0; set http.pool_max_idle_per_host = 0 to keep no idle connections",
+            ));
+        }
+
+        if self.monitor.backoff_initial_ms > self.monitor.backoff_max_ms {
+            problems.push(ConfigProblem::new(
+                "monitor.backoff_initial_ms",
+                format!(
+                    "monitor.backoff_initial_ms ({}) must not exceed monitor.backoff_max_ms ({})",
+                    self.monitor.backoff_initial_ms, self.monitor.backoff_max_ms
+                ),
+            ));
+        }
+
+        for &code in &self.monitor.retry_on {
+            if let Err(e) = check_retry_status(code) {
+                problems.push(ConfigProblem::new(
+                    "monitor.retry_on",
+                    format!("Invalid monitor.retry_on: {}", e),
+                ));
+            }
+        }
+        if self.monitor.max_polls == Some(0) {
+            problems.push(ConfigProblem::new(
+                "monitor.max_polls",
+                "monitor.max_polls must be greater than 0",
+            ));
+        }
+        if self.monitor.long_poll_seconds == Some(0) {
+            problems.push(ConfigProblem::new(
+                "monitor.long_poll_seconds",
+                "monitor.long_poll_seconds must be greater than 0",
+            ));
+        }
+
+        for (field, path) in [
+            ("deployment_api_path", &self.deployment_api_path),
+            ("deployment_mgmt_api_path", &self.deployment_mgmt_api_path),
+        ] {
+            if !path.starts_with('/') || path.contains(['?', '#']) {
+                problems.push(ConfigProblem::new(
+                    field,
+                    format!(
+                        "{} must be an absolute path such as /deployment/v2, not '{}'",
+                        field, path
+                    ),
+                ));
+            }
+        }
+
+        if let Some(webhook) = self.notify.webhook.as_deref().filter(|w| !w.contains("${")) {
+            match url::Url::parse(webhook) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                _ => problems.push(ConfigProblem::new(
+                    "notify.webhook",
+                    format!(
+                        "notify.webhook must be an http or https URL, not '{}'",
+                        webhook
+                    ),
+                )),
+            }
+        }
+
+        for (name, value) in &self.headers {
+            let field = format!("headers.{}", name);
+            if let Err(e) = check_header(name, value) {
+                problems.push(ConfigProblem::new(&field, format!("{:#}", e)));
+            }
+            if !self.allow_auth_header_override
+                && AUTH_HEADERS
+                    .iter()
+                    .any(|auth| name.eq_ignore_ascii_case(auth))
+            {
+                problems.push(ConfigProblem::new(
+                    &field,
+                    format!(
+                        "Custom header '{}' would replace authentication; set allow_auth_header_override (or pass --allow-auth-header-override) to permit it",
+                        name
+                    ),
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// A copy safe to log: the API key and every header value that
+    /// [`redact_sensitive_info`](crate::error::redact_sensitive_info) would
+    /// mask in a `Name: value` line are replaced.
+    pub fn redacted(&self) -> Config {
+        const MASK: &str = "***REDACTED***";
+        let mut config = self.clone();
+        if !config.api_key.is_empty() {
+            config.api_key = MASK.to_string();
+        }
+        for (name, value) in config.headers.iter_mut() {
+            let line = format!("{}: {}", name, value);
+            if crate::error::redact_sensitive_info(&line) != line {
+                *value = MASK.to_string();
+            }
+        }
+        config
+    }
+
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    /// Which Appian site results come from: `environment` when set,
+    /// otherwise the host of `base_url`.
+    pub fn environment_name(&self) -> Option<String> {
+        self.environment
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| {
+                url::Url::parse(&self.base_url)
+                    .ok()?
+                    .host_str()
+                    .map(str::to_string)
+            })
+    }
+
+    /// Where state kept between runs lives: `state_dir` when set,
+    /// otherwise `appian-deployment-cli` under the platform's state (or
+    /// cache) directory. `None` when the platform has neither.
+    pub fn state_dir(&self) -> Option<PathBuf> {
+        self.state_dir.clone().or_else(|| {
+            dirs::state_dir()
+                .or_else(dirs::cache_dir)
+                .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+        })
     }
 
     pub fn get_api_url(&self, path: &str) -> String {
-        format!("{}/{}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Locate a deserialization error by the field it names: `toml` reports
+/// type errors as "...\nin `a.b`" and absent keys as "missing field `a`".
+fn deserialize_problem(message: &str) -> ConfigProblem {
+    let quoted = |text: &str| {
+        text.strip_prefix('`')
+            .and_then(|t| t.strip_suffix('`'))
+            .map(str::to_string)
+    };
+    let (detail, field) = match message.rsplit_once("\nin ") {
+        Some((detail, location)) => (detail, quoted(location.trim())),
+        None => (message, None),
+    };
+    let field = field
+        .or_else(|| {
+            detail
+                .split_once("missing field ")
+                .and_then(|(_, name)| quoted(name.trim()))
+        })
+        .unwrap_or_default();
+    ConfigProblem::new(&field, detail.trim())
+}
 
-    #[test]
-    fn test_default_config() {
-        let config = Config::from_env().unwrap();
-        assert_eq!(config.timeout_seconds, 300);
-        assert_eq!(config.logging.level, "info");
-        assert!(!config.logging.json);
+/// Find the nearest `appian-config.toml` in `start` or one of its parents.
+/// The search stops at the filesystem root or at a directory containing
+/// `.git`, so a config outside the current repository is never picked up.
+pub fn find_config_file(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        let candidate = dir.join(CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
     }
+    None
+}
 
-    #[test]
-    fn test_api_url_construction() {
-        let config = Config {
-            base_url: "https://example.com".to_string(),
-            api_key: "test".to_string(),
+/// Run `command` through the platform shell and return its trimmed stdout.
+///
+/// The command gets no stdin and is killed once `timeout` passes. Output
+/// beyond `max_output` bytes is rejected rather than used. A failing command
+/// is reported with its (redacted, truncated) stderr.
+fn run_api_key_command(
+    command: &str,
+    timeout: std::time::Duration,
+    max_output: u64,
+) -> Result<String> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    // Keep at most `cap` bytes but drain the rest, so the child never
+    // blocks on a full pipe
+    fn read_capped(mut reader: impl Read, cap: u64) -> (Vec<u8>, bool) {
+        let mut kept = Vec::new();
+        let _ = reader.by_ref().take(cap).read_to_end(&mut kept);
+        let overflow = std::io::copy(&mut reader, &mut std::io::sink()).unwrap_or(0) > 0;
+        (kept, overflow)
+    }
+
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    let mut child = shell
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start api_key_command")?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("api_key_command has no stdout")?;
+    let stderr = child
+        .stderr
+        .take()
+        .context("api_key_command has no stderr")?;
+    // Readers report over channels, so collecting their output is bounded by
+    // the same deadline as the command
+    let (stdout_tx, stdout_rx) = std::sync::mpsc::channel();
+    let (stderr_tx, stderr_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || stdout_tx.send(read_capped(stdout, max_output)));
+    std::thread::spawn(move || stderr_tx.send(read_capped(stderr, API_KEY_COMMAND_MAX_STDERR)));
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .context("Failed to wait for api_key_command")?
+        {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            // The readers are left behind: a grandchild may still hold the pipes
+            anyhow::bail!(
+                "api_key_command did not finish within {} seconds",
+                timeout.as_secs_f64()
+            );
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+
+    // A background process the command started may keep the pipes open
+    // after it exits; its readers are then left behind
+    let collect = |rx: std::sync::mpsc::Receiver<(Vec<u8>, bool)>| {
+        match rx.recv_timeout(deadline.saturating_duration_since(std::time::Instant::now())) {
+            Ok(read) => Ok(read),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Err(anyhow::anyhow!(
+                "api_key_command exited but its output was not closed within {} seconds; a background process it started may still hold it",
+                timeout.as_secs_f64()
+            )),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Ok(Default::default()),
+        }
+    };
+    let (output, overflow) = collect(stdout_rx)?;
+    let (errors, _) = collect(stderr_rx)?;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&errors);
+        anyhow::bail!(
+            "api_key_command failed ({}): {}",
+            status,
+            crate::error::redact_sensitive_info(stderr.trim())
+        );
+    }
+    if overflow {
+        anyhow::bail!(
+            "api_key_command printed more than {} bytes; expected only the API key",
+            max_output
+        );
+    }
+
+    let key = String::from_utf8(output).context("api_key_command printed invalid UTF-8")?;
+    let key = key.trim();
+    if key.is_empty() {
+        anyhow::bail!("api_key_command printed nothing");
+    }
+    Ok(key.to_string())
+}
+
+/// Dotted keys of config values that are file system paths
+const PATH_KEYS: [&str; 3] = ["download.dir", "audit_log", "state_dir"];
+
+/// Make the relative [`PATH_KEYS`] in one config file relative to `dir`,
+/// the file's own directory, instead of the working directory. Each file of
+/// a layered config is resolved against its own directory before merging.
+fn resolve_relative_paths(value: &mut toml::Value, dir: &Path) {
+    for key in PATH_KEYS {
+        let mut target = Some(&mut *value);
+        for part in key.split('.') {
+            target = target.and_then(|v| v.get_mut(part));
+        }
+        if let Some(toml::Value::String(path)) = target {
+            if !path.is_empty() && Path::new(path.as_str()).is_relative() {
+                *path = dir.join(path.as_str()).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// Merge `overlay` into `base`. Tables are merged key by key; any other
+/// value in the overlay replaces the base value.
+fn merge_values(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` references in every string of a
+/// parsed config, so secrets can stay out of the committed file.
+fn interpolate_value(
+    value: &mut toml::Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        toml::Value::String(s) => *s = interpolate_env(s, lookup)?,
+        toml::Value::Array(items) => {
+            for item in items {
+                interpolate_value(item, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                interpolate_value(item, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_env(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .with_context(|| format!("Unterminated variable reference in '{}'", input))?;
+        let expr = &after[..end];
+
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        if name.is_empty() {
+            anyhow::bail!("Empty variable reference in '{}'", input);
+        }
+
+        match (lookup(name), default) {
+            (Some(resolved), _) => output.push_str(&resolved),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => anyhow::bail!("Environment variable {} is not set", name),
+        }
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Parse a byte size such as `500MB`, `2GB` or `1048576` (binary units).
+pub fn parse_byte_size(input: &str) -> Result<u64> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+
+    let value: u64 = digits
+        .parse()
+        .with_context(|| format!("Invalid size: '{}'", input))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => anyhow::bail!(
+            "Invalid size unit in '{}' (expected B, KB, MB or GB)",
+            input
+        ),
+    };
+
+    value
+        .checked_mul(multiplier)
+        .with_context(|| format!("Size '{}' is too large", input))
+}
+
+/// Parse a comma-separated list of HTTP statuses to retry, such as
+/// `429,502,503,504`. Duplicates are dropped.
+pub fn parse_status_codes(input: &str) -> Result<Vec<u16>> {
+    let mut codes = Vec::new();
+    for item in input.split(',') {
+        let item = item.trim();
+        let code: u16 = item
+            .parse()
+            .with_context(|| format!("Invalid status code '{}' in '{}'", item, input))?;
+        check_retry_status(code)?;
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    Ok(codes)
+}
+
+/// Parse a `--header` value of the form `Name: Value`.
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    let (name, value) = input
+        .split_once(':')
+        .with_context(|| format!("Invalid header '{}': expected 'Name: Value'", input))?;
+    let (name, value) = (name.trim(), value.trim());
+    check_header(name, value)?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn check_header(name: &str, value: &str) -> Result<()> {
+    reqwest::header::HeaderName::from_bytes(name.as_bytes())
+        .with_context(|| format!("Invalid header name '{}'", name))?;
+    reqwest::header::HeaderValue::from_str(value)
+        .with_context(|| format!("Invalid value for header '{}'", name))?;
+    Ok(())
+}
+
+/// Only client and server errors can be retried; success and redirect
+/// statuses never reach the retry logic.
+fn check_retry_status(code: u16) -> Result<()> {
+    if !(400..=599).contains(&code) {
+        anyhow::bail!("Status code {} cannot be retried (expected 400-599)", code);
+    }
+    Ok(())
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            base_url: String::new(),
+            api_key: String::new(),
+            api_key_command: None,
+            api_key_command_timeout_seconds: default_api_key_command_timeout(),
             timeout_seconds: 300,
             logging: LoggingConfig::default(),
             download: DownloadConfig::default(),
             monitor: MonitorConfig::default(),
-        };
-
-        assert_eq!(config.get_api_url("api/v1/test"), "https://example.com/api/v1/test");
-        assert_eq!(config.get_api_url("/api/v1/test"), "https://example.com/api/v1/test");
-        assert_eq!(config.get_api_url("test"), "https://example.com/test");
+            notify: NotifyConfig::default(),
+            http: HttpConfig::default(),
+            audit_log: None,
+            state_dir: None,
+            max_upload_bytes: None,
+            deadline_seconds: None,
+            attempt_timeout_seconds: None,
+            rate_limit_per_second: default_rate_limit(),
+            headers: BTreeMap::new(),
+            allow_auth_header_override: false,
+            user_agent: None,
+            environment: None,
+            deployment_api_path: default_deployment_api_path(),
+            deployment_mgmt_api_path: default_deployment_mgmt_api_path(),
+            replay_file: None,
+            save_response: None,
+        }
     }
 }
+
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
             level: default_log_level(),
             json: false,
+            trace_http: false,
         }
     }
 }
@@ -205,13 +1179,388 @@ impl Default for DownloadConfig {
         }
     }
 }
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout_seconds: default_pool_idle_timeout(),
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            tcp_keepalive_seconds: default_tcp_keepalive(),
+            http2_prior_knowledge: false,
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// Retries allowed after a failed connection or a status in `retry_on`
+    pub fn transient_retries(&self) -> u32 {
+        self.max_retries_transient.unwrap_or(self.max_retries)
+    }
+
+    /// Retries allowed after a request timed out
+    pub fn timeout_retries(&self) -> u32 {
+        self.max_retries_timeout.unwrap_or(self.max_retries)
+    }
+}
+
 impl Default for MonitorConfig {
     fn default() -> Self {
         Self {
             backoff_initial_ms: default_backoff_initial(),
             backoff_max_ms: default_backoff_max(),
             jitter: default_jitter(),
+            max_retries: default_max_retries(),
+            max_retries_transient: None,
+            max_retries_timeout: None,
+            retry_on: default_retry_on(),
+            max_polls: None,
+            long_poll_seconds: None,
             logs_follow_default: default_logs_follow(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.timeout_seconds, 300);
+        assert_eq!(config.logging.level, "info");
+        assert!(!config.logging.json);
+    }
+
+    #[test]
+    fn test_api_url_construction() {
+        let config = Config {
+            base_url: "https://example.com".to_string(),
+            api_key: "test".to_string(),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            config.get_api_url("api/v1/test"),
+            "https://example.com/api/v1/test"
+        );
+        assert_eq!(
+            config.get_api_url("/api/v1/test"),
+            "https://example.com/api/v1/test"
+        );
+        assert_eq!(config.get_api_url("test"), "https://example.com/test");
+
+        let config = Config {
+            deployment_mgmt_api_path: "/gateway/appian/".to_string(),
+            ..config
+        };
+        assert_eq!(config.problems(), vec![]);
+        let config = Config {
+            deployment_api_path: "deployment/v2".to_string(),
+            ..config
+        };
+        assert_eq!(config.problems()[0].field, "deployment_api_path");
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        let lookup = |name: &str| (name == "APPIAN_API_KEY").then(|| "secret".to_string());
+
+        assert_eq!(
+            interpolate_env("${APPIAN_API_KEY}", &lookup).unwrap(),
+            "secret"
+        );
+        assert_eq!(
+            interpolate_env("https://${HOST:-mysite.appiancloud.com}/suite", &lookup).unwrap(),
+            "https://mysite.appiancloud.com/suite"
+        );
+        assert_eq!(
+            interpolate_env("no variables", &lookup).unwrap(),
+            "no variables"
+        );
+        assert!(interpolate_env("${MISSING}", &lookup).is_err());
+        assert!(interpolate_env("${APPIAN_API_KEY", &lookup).is_err());
+
+        let mut value: toml::Value = toml::from_str(
+            "base_url = \"https://example.com\"\napi_key = \"${APPIAN_API_KEY}\"\ntimeout_seconds = 300\n[logging]\nlevel = \"${LEVEL:-debug}\"\n",
+        )
+        .unwrap();
+        interpolate_value(&mut value, &lookup).unwrap();
+        let config: Config = value.try_into().unwrap();
+        assert_eq!(config.api_key, "secret");
+        assert_eq!(config.logging.level, "debug");
+    }
+
+    #[test]
+    fn test_overlay_merges_sub_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.toml");
+        let overlay = dir.path().join("local.toml");
+        std::fs::write(
+            &base,
+            "base_url = \"https://shared.example.com\"\napi_key = \"shared\"\ntimeout_seconds = 300\n\n[logging]\nlevel = \"info\"\njson = true\n\n[monitor]\nbackoff_max_ms = 5000\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &overlay,
+            "api_key = \"mine\"\n\n[logging]\nlevel = \"debug\"\n",
+        )
+        .unwrap();
+
+        let config = Config::from_files(&[base, overlay]).unwrap();
+        assert_eq!(config.base_url, "https://shared.example.com");
+        assert_eq!(config.api_key, "mine");
+        // Overlay changed one logging key without dropping the others
+        assert_eq!(config.logging.level, "debug");
+        assert!(config.logging.json);
+        assert_eq!(config.monitor.backoff_max_ms, 5000);
+        assert_eq!(config.monitor.backoff_initial_ms, 1000);
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("1048576").unwrap(), 1024 * 1024);
+        assert_eq!(parse_byte_size("500MB").unwrap(), 500 * 1024 * 1024);
+        assert_eq!(parse_byte_size("2gb").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert!(parse_byte_size("10TB").is_err());
+        assert!(parse_byte_size("MB").is_err());
+    }
+
+    #[test]
+    fn test_parse_status_codes() {
+        assert_eq!(parse_status_codes("502, 503,502").unwrap(), vec![502, 503]);
+        assert_eq!(parse_status_codes("500").unwrap(), vec![500]);
+        assert!(parse_status_codes("").is_err());
+        assert!(parse_status_codes("503,abc").is_err());
+        assert!(parse_status_codes("200").is_err());
+    }
+
+    #[test]
+    fn test_custom_headers() {
+        assert_eq!(
+            parse_header("X-Tenant:  acme ").unwrap(),
+            ("X-Tenant".to_string(), "acme".to_string())
+        );
+        assert!(parse_header("X-Tenant").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+
+        let mut config = Config {
+            base_url: "https://test.example.com".to_string(),
+            api_key: "key".to_string(),
+            ..Config::default()
+        };
+        config
+            .headers
+            .insert("Authorization".to_string(), "Basic abc".to_string());
+        assert!(config.validate().is_err());
+        config.allow_auth_header_override = true;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_notify_config() {
+        let config: Config = toml::from_str(
+            "base_url = \"https://example.com\"\napi_key = \"k\"\ntimeout_seconds = 30\n\
+             [notify]\nwebhook = \"ftp://hooks.example.com\"\non = \"failure\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.notify.on, NotifyOn::Failure);
+        assert!(!config.notify.on.should_notify(true));
+        assert!(config.notify.on.should_notify(false));
+        assert_eq!(config.problems()[0].field, "notify.webhook");
+
+        assert_eq!(config.notify.format, NotifyFormat::Raw);
+        assert_eq!(
+            "Teams".parse::<NotifyFormat>().unwrap(),
+            NotifyFormat::Teams
+        );
+        assert_eq!("Always".parse::<NotifyOn>().unwrap(), NotifyOn::Always);
+        assert!("sometimes".parse::<NotifyOn>().is_err());
+    }
+
+    #[test]
+    fn test_redacted_masks_secrets() {
+        let config = Config {
+            api_key: "key-123".to_string(),
+            headers: BTreeMap::from([
+                ("X-Tenant".to_string(), "acme".to_string()),
+                ("X-Gateway-Token".to_string(), "tok-456".to_string()),
+            ]),
+            ..Config::default()
+        };
+        let logged = format!("{:?}", config.redacted());
+        assert!(
+            !logged.contains("key-123") && !logged.contains("tok-456"),
+            "{}",
+            logged
+        );
+        assert!(logged.contains("\"X-Tenant\": \"acme\""), "{}", logged);
+        assert_eq!(config.api_key, "key-123");
+    }
+
+    #[test]
+    fn test_http_config() {
+        let config: Config = toml::from_str(
+            "base_url = \"https://example.com\"\napi_key = \"k\"\ntimeout_seconds = 30\n\
+             [http]\npool_max_idle_per_host = 2\npool_idle_timeout_seconds = 0\n",
+        )
+        .unwrap();
+        assert_eq!(config.http.pool_max_idle_per_host, 2);
+        assert_eq!(config.http.tcp_keepalive_seconds, 60);
+        assert!(!config.http.http2_prior_knowledge);
+        assert_eq!(config.problems()[0].field, "http.pool_idle_timeout_seconds");
+    }
+
+    #[test]
+    fn test_paths_are_relative_to_config_file() {
+        let root = tempfile::tempdir().unwrap();
+        let base_dir = root.path().join("base");
+        let overlay_dir = root.path().join("overlay");
+        std::fs::create_dir_all(&base_dir).unwrap();
+        std::fs::create_dir_all(&overlay_dir).unwrap();
+
+        let base = base_dir.join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &base,
+            "base_url = \"https://example.com\"\napi_key = \"k\"\ntimeout_seconds = 30\naudit_log = \"logs/audit.jsonl\"\n[download]\ndir = \"artifacts\"\n",
+        )
+        .unwrap();
+        let config = Config::from_files(std::slice::from_ref(&base)).unwrap();
+        assert_eq!(config.download.dir, base_dir.join("artifacts"));
+        assert_eq!(config.audit_log, Some(base_dir.join("logs/audit.jsonl")));
+
+        // An overlay's paths are relative to the overlay; absolute paths are kept
+        let absolute = root.path().join("elsewhere").join("audit.jsonl");
+        let overlay = overlay_dir.join("local.toml");
+        std::fs::write(
+            &overlay,
+            format!(
+                "audit_log = '{}'\n[download]\ndir = \"../out\"\n",
+                absolute.display()
+            ),
+        )
+        .unwrap();
+        let config = Config::from_files(&[base, overlay]).unwrap();
+        assert_eq!(config.download.dir, overlay_dir.join("../out"));
+        assert_eq!(config.audit_log, Some(absolute));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_api_key_command() {
+        let timeout = std::time::Duration::from_secs(5);
+        assert_eq!(
+            run_api_key_command("printf ' key-123\\n'", timeout, 64).unwrap(),
+            "key-123"
+        );
+
+        let err = run_api_key_command("echo 'token: abcdef' >&2; exit 3", timeout, 64)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("exit status: 3"));
+        assert!(err.contains("***REDACTED***"));
+        assert!(!err.contains("abcdef"));
+
+        let err = run_api_key_command("head -c 1000 /dev/zero", timeout, 64).unwrap_err();
+        assert!(err.to_string().contains("more than 64 bytes"));
+
+        let start = std::time::Instant::now();
+        let err =
+            run_api_key_command("sleep 5", std::time::Duration::from_millis(200), 64).unwrap_err();
+        assert!(err.to_string().contains("did not finish"));
+        assert!(start.elapsed() < std::time::Duration::from_secs(3));
+
+        let start = std::time::Instant::now();
+        let err = run_api_key_command(
+            "echo key-123; sleep 10 &",
+            std::time::Duration::from_millis(300),
+            64,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("output was not closed"), "{}", err);
+        assert!(start.elapsed() < std::time::Duration::from_secs(3));
+
+        let mut config = Config {
+            base_url: "https://example.com".to_string(),
+            api_key_command: Some("echo from-command".to_string()),
+            ..Config::default()
+        };
+        assert!(config.problems().is_empty());
+        config.resolve_api_key().unwrap();
+        assert_eq!(config.api_key, "from-command");
+    }
+
+    #[test]
+    fn test_check_files_reports_every_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &path,
+            "base_url = \"ftp://example.com\"\napi_key = \"${APPIAN_CHECK_UNSET_KEY}\"\ntimeout_seconds = 0\n\
+             [monitor]\nbackoff_initial_ms = 5000\nbackoff_max_ms = 1000\nretry_on = [200]\n",
+        )
+        .unwrap();
+
+        let fields: Vec<_> = Config::check_files(std::slice::from_ref(&path))
+            .into_iter()
+            .map(|p| p.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "base_url",
+                "timeout_seconds",
+                "monitor.backoff_initial_ms",
+                "monitor.retry_on"
+            ]
+        );
+
+        std::fs::write(
+            &path,
+            "base_url = \"https://example.com\"\napi_key = \"k\"\ntimeout_seconds = \"soon\"\n",
+        )
+        .unwrap();
+        let problems = Config::check_files(std::slice::from_ref(&path));
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "timeout_seconds");
+
+        std::fs::write(
+            &path,
+            "base_url = \"https://example.com\"\ntimeout_seconds = 30\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Config::check_files(std::slice::from_ref(&path))[0].field,
+            "api_key"
+        );
+
+        std::fs::write(
+            &path,
+            "base_url = \"https://example.com\"\napi_key = \"k\"\ntimeout_seconds = 30\n",
+        )
+        .unwrap();
+        assert!(Config::check_files(&[path]).is_empty());
+    }
+
+    #[test]
+    fn test_find_config_file_walks_up_to_git_boundary() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        let nested = repo.join("apps").join("hr");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+
+        // A config above the repository boundary is ignored
+        std::fs::write(root.path().join(CONFIG_FILE_NAME), "").unwrap();
+        assert_eq!(find_config_file(&nested), None);
+
+        // The nearest config inside the repository wins
+        std::fs::write(repo.join(CONFIG_FILE_NAME), "").unwrap();
+        assert_eq!(find_config_file(&nested), Some(repo.join(CONFIG_FILE_NAME)));
+
+        std::fs::write(nested.join(CONFIG_FILE_NAME), "").unwrap();
+        assert_eq!(
+            find_config_file(&nested),
+            Some(nested.join(CONFIG_FILE_NAME))
+        );
+    }
+}
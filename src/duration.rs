@@ -0,0 +1,130 @@
+use crate::error::{CliError, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Parse a human duration such as `90s`, `10m`, `2h` or `1d`.
+/// A bare number is taken as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let trimmed = input.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| CliError::InvalidArgument(format!("Invalid duration: '{}'", input)))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(CliError::InvalidArgument(format!(
+                "Invalid duration unit in '{}' (expected s, m, h or d)",
+                input
+            )))
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| CliError::InvalidArgument(format!("Duration '{}' is too large", input)))
+}
+
+/// Parse an `--interval` or `--timeout` value of a wait, which must be at
+/// least one second. Used as the clap value parser for these flags, so a
+/// bare number keeps meaning seconds for the older `-seconds` spellings.
+pub fn parse_wait_duration(input: &str) -> Result<Duration> {
+    let duration = parse_duration(input)?;
+    if duration.is_zero() {
+        return Err(CliError::InvalidArgument(format!(
+            "Duration '{}' must be at least 1 second",
+            input
+        )));
+    }
+    Ok(duration)
+}
+
+/// Format a number of seconds for humans, e.g. `3m 42s` or `1h 5m 0s`.
+pub fn format_elapsed(seconds: u64) -> String {
+    let (hours, minutes, secs) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, secs)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Parse a `--since` value: either an RFC3339 timestamp or a duration
+/// relative to `now` (e.g. `10m` means ten minutes ago).
+pub fn parse_since(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(input.trim()) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let ago = parse_duration(input).map_err(|_| {
+        CliError::InvalidArgument(format!(
+            "Invalid --since value '{}': expected an RFC3339 timestamp or a duration like 10m or 1h",
+            input
+        ))
+    })?;
+    let ago = chrono::Duration::from_std(ago).map_err(|_| {
+        CliError::InvalidArgument(format!("--since value '{}' is too large", input))
+    })?;
+
+    Ok(now - ago)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_parse_wait_duration() {
+        assert_eq!(parse_wait_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_wait_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(
+            parse_wait_duration("7200").unwrap(),
+            Duration::from_secs(7200)
+        );
+        assert!(parse_wait_duration("0").is_err());
+        assert!(parse_wait_duration("0h").is_err());
+        assert!(parse_wait_duration("-5m").is_err());
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(45), "45s");
+        assert_eq!(format_elapsed(222), "3m 42s");
+        assert_eq!(format_elapsed(3900), "1h 5m 0s");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let absolute = parse_since("2024-01-01T10:00:00Z", now).unwrap();
+        assert_eq!(absolute.to_rfc3339(), "2024-01-01T10:00:00+00:00");
+
+        let relative = parse_since("1h", now).unwrap();
+        assert_eq!(relative.to_rfc3339(), "2024-01-01T11:00:00+00:00");
+
+        assert!(parse_since("yesterday", now).is_err());
+    }
+}
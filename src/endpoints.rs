@@ -0,0 +1,149 @@
+//! Endpoint paths for the Appian deployment APIs.
+//!
+//! Appian splits the API across two roots: `deployment_api_path`
+//! (`/deployment/v2`) serves packages, import status, logs and artifacts,
+//! while `deployment_mgmt_api_path` (`/suite/deployment-management/v2`) serves
+//! exports, inspections, data sources and the results of any deployment. The
+//! same `deployments/{uuid}` suffix means different things under each root,
+//! so every path is built here rather than at the call site.
+
+use crate::config::Config;
+
+/// Path builder over the two configured API roots.
+#[derive(Debug, Clone, Copy)]
+pub struct EndpointPaths<'a> {
+    deployment_api: &'a str,
+    mgmt_api: &'a str,
+}
+
+impl<'a> EndpointPaths<'a> {
+    pub fn new(deployment_api: &'a str, mgmt_api: &'a str) -> Self {
+        Self {
+            deployment_api,
+            mgmt_api,
+        }
+    }
+
+    pub fn from_config(config: &'a Config) -> Self {
+        Self::new(
+            &config.deployment_api_path,
+            &config.deployment_mgmt_api_path,
+        )
+    }
+
+    /// Package listing (`GET`).
+    pub fn packages(&self) -> String {
+        join(self.deployment_api, "packages")
+    }
+
+    /// Import submission (`POST`) and, where the site supports it, the
+    /// listing of deployments (`GET`).
+    pub fn imports(&self) -> String {
+        join(self.deployment_api, "deployments")
+    }
+
+    /// Export submission (`POST`).
+    pub fn exports(&self) -> String {
+        join(self.mgmt_api, "deployments")
+    }
+
+    /// Status of an import.
+    pub fn deployments(&self, uuid: &str) -> String {
+        join(self.deployment_api, &format!("deployments/{}", uuid))
+    }
+
+    /// Cancel action of a running export (`POST`).
+    pub fn cancel_export(&self, uuid: &str) -> String {
+        join(self.mgmt_api, &format!("deployments/{}/cancel", uuid))
+    }
+
+    /// Status and results of an export or import.
+    pub fn results(&self, uuid: &str) -> String {
+        join(self.mgmt_api, &format!("deployments/{}", uuid))
+    }
+
+    /// Log of a deployment.
+    pub fn logs(&self, uuid: &str) -> String {
+        join(self.deployment_api, &format!("deployments/{}/log", uuid))
+    }
+
+    /// A downloadable artifact, such as an exported package.
+    pub fn artifacts(&self, id: &str) -> String {
+        join(self.deployment_api, &format!("artifacts/{}", id))
+    }
+
+    /// Data sources available to imports.
+    pub fn data_sources(&self) -> String {
+        join(self.mgmt_api, "data-sources")
+    }
+
+    /// Inspection submission (`POST`).
+    pub fn new_inspection(&self) -> String {
+        join(self.mgmt_api, "inspections")
+    }
+
+    /// Results of an inspection.
+    pub fn inspections(&self, uuid: &str) -> String {
+        join(self.mgmt_api, &format!("inspections/{}", uuid))
+    }
+}
+
+fn join(root: &str, rest: &str) -> String {
+    format!(
+        "{}/{}",
+        root.trim_end_matches('/'),
+        rest.trim_start_matches('/')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_paths() {
+        let config = Config::default();
+        let paths = EndpointPaths::from_config(&config);
+
+        assert_eq!(paths.packages(), "/deployment/v2/packages");
+        assert_eq!(paths.imports(), "/deployment/v2/deployments");
+        assert_eq!(
+            paths.exports(),
+            "/suite/deployment-management/v2/deployments"
+        );
+        assert_eq!(paths.deployments("abc"), "/deployment/v2/deployments/abc");
+        assert_eq!(
+            paths.results("abc"),
+            "/suite/deployment-management/v2/deployments/abc"
+        );
+        assert_eq!(
+            paths.cancel_export("abc"),
+            "/suite/deployment-management/v2/deployments/abc/cancel"
+        );
+        assert_eq!(paths.logs("abc"), "/deployment/v2/deployments/abc/log");
+        assert_eq!(paths.artifacts("42"), "/deployment/v2/artifacts/42");
+        assert_eq!(
+            paths.data_sources(),
+            "/suite/deployment-management/v2/data-sources"
+        );
+        assert_eq!(
+            paths.new_inspection(),
+            "/suite/deployment-management/v2/inspections"
+        );
+        assert_eq!(
+            paths.inspections("abc"),
+            "/suite/deployment-management/v2/inspections/abc"
+        );
+    }
+
+    #[test]
+    fn test_custom_roots() {
+        let paths = EndpointPaths::new("/gateway/deployment/", "/gateway/mgmt");
+
+        assert_eq!(
+            paths.deployments("abc"),
+            "/gateway/deployment/deployments/abc"
+        );
+        assert_eq!(paths.results("abc"), "/gateway/mgmt/deployments/abc");
+    }
+}
@@ -1,4 +1,3 @@
-use anyhow;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,6 +16,16 @@ pub enum CliError {
     #[error("API error: {status} - {message}")]
     Api { status: u16, message: String },
 
+    /// A response the CLI cannot use, such as HTML from a gateway or JSON of
+    /// the wrong shape. `status` is the HTTP status the server sent, usually
+    /// a success, so it is reported apart from [`CliError::Api`].
+    #[error("Unexpected response: {message}")]
+    UnexpectedResponse {
+        status: u16,
+        content_type: Option<String>,
+        message: String,
+    },
+
     #[error("File system error: {0}")]
     FileSystem(String),
 
@@ -27,13 +36,31 @@ pub enum CliError {
     #[error("Timeout error: {0}")]
     Timeout(String),
 
+    /// A wait gave up after `max_polls` polls; a timeout with its own kind
+    #[error("Max polls reached: {0}")]
+    MaxPolls(String),
+
     #[error("Deployment failed: {0}")]
     #[allow(dead_code)]
     DeploymentFailed(String),
 
+    #[error("Deployment rolled back: {0}")]
+    RolledBack(String),
+
+    #[error("Deployment pending review: {0}")]
+    PendingReview(String),
+
+    #[error("Completed with errors: {0}")]
+    CompletedWithErrors(String),
+
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
 
+    /// A step of a multi-stage command failed; the exit code identifies the
+    /// stage and `source` keeps the underlying error
+    #[error("{stage} stage failed: {source}")]
+    Stage { stage: Stage, source: Box<CliError> },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -53,6 +80,60 @@ pub enum CliError {
 
 pub type Result<T> = std::result::Result<T, CliError>;
 
+/// Stages of `export-and-download`, each failing with its own exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Starting the export (exit 10)
+    Export,
+    /// Waiting for the export to finish (exit 11)
+    Wait,
+    /// Downloading the package (exit 12)
+    Download,
+}
+
+impl Stage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Export => "export",
+            Stage::Wait => "wait",
+            Stage::Download => "download",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Stage::Export => 10,
+            Stage::Wait => 11,
+            Stage::Download => 12,
+        }
+    }
+
+    /// Attach this stage to an error
+    pub fn wrap(self, source: CliError) -> CliError {
+        CliError::Stage {
+            stage: self,
+            source: Box::new(source),
+        }
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+const REQUEST_ID_MARKER: &str = " [request ID: ";
+
+/// Append the server-side request ID to an error message, so it shows up in
+/// text output and can be recovered by [`CliError::request_id`].
+pub fn with_request_id(message: String, request_id: Option<&str>) -> String {
+    match request_id {
+        Some(id) => format!("{}{}{}]", message, REQUEST_ID_MARKER, id),
+        None => message,
+    }
+}
+
 impl CliError {
     #[allow(dead_code)]
     pub fn exit_code(&self) -> i32 {
@@ -67,11 +148,16 @@ impl CliError {
                     1
                 }
             }
+            CliError::UnexpectedResponse { .. } => 5,
             CliError::FileSystem(_) => 1,
             CliError::Validation(_) => 2,
-            CliError::Timeout(_) => 6,
+            CliError::Timeout(_) | CliError::MaxPolls(_) => 6,
             CliError::DeploymentFailed(_) => 5,
+            CliError::RolledBack(_) => 7,
+            CliError::PendingReview(_) => 8,
+            CliError::CompletedWithErrors(_) => 9,
             CliError::InvalidArgument(_) => 2,
+            CliError::Stage { stage, .. } => stage.exit_code(),
             CliError::Io(_) => 1,
             CliError::Serialization(_) => 2,
             CliError::UrlParse(_) => 2,
@@ -81,22 +167,134 @@ impl CliError {
     }
 }
 
+impl CliError {
+    /// Stable machine-readable name of the error variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CliError::Network(_) => "network",
+            CliError::Authentication(_) => "authentication",
+            CliError::Configuration(_) => "configuration",
+            CliError::Api { .. } => "api",
+            CliError::UnexpectedResponse { .. } => "unexpected_response",
+            CliError::FileSystem(_) => "file_system",
+            CliError::Validation(_) => "validation",
+            CliError::Timeout(_) => "timeout",
+            CliError::MaxPolls(_) => "max_polls",
+            CliError::DeploymentFailed(_) => "deployment_failed",
+            CliError::RolledBack(_) => "rolled_back",
+            CliError::PendingReview(_) => "pending_review",
+            CliError::CompletedWithErrors(_) => "completed_with_errors",
+            CliError::InvalidArgument(_) => "invalid_argument",
+            CliError::Stage { .. } => "stage_failed",
+            CliError::Io(_) => "io",
+            CliError::Serialization(_) => "serialization",
+            CliError::UrlParse(_) => "url_parse",
+            CliError::Anyhow(_) => "internal",
+            CliError::Unknown(_) => "unknown",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            CliError::Network(m)
+            | CliError::Authentication(m)
+            | CliError::Configuration(m)
+            | CliError::FileSystem(m)
+            | CliError::Validation(m)
+            | CliError::Timeout(m)
+            | CliError::MaxPolls(m)
+            | CliError::DeploymentFailed(m)
+            | CliError::RolledBack(m)
+            | CliError::PendingReview(m)
+            | CliError::CompletedWithErrors(m)
+            | CliError::InvalidArgument(m)
+            | CliError::Unknown(m) => m.clone(),
+            CliError::Api { message, .. } | CliError::UnexpectedResponse { message, .. } => {
+                message.clone()
+            }
+            CliError::Io(e) => e.to_string(),
+            CliError::Serialization(e) => e.to_string(),
+            CliError::UrlParse(e) => e.to_string(),
+            CliError::Anyhow(e) => format!("{:#}", e),
+            CliError::Stage { source, .. } => source.message(),
+        }
+    }
+
+    /// Server-side request ID attached to an API or authentication error,
+    /// for quoting in Appian support cases
+    pub fn request_id(&self) -> Option<&str> {
+        let message = match self {
+            CliError::Stage { source, .. } => return source.request_id(),
+            CliError::Api { message, .. }
+            | CliError::UnexpectedResponse { message, .. }
+            | CliError::Authentication(message) => message,
+            _ => return None,
+        };
+        let start = message.rfind(REQUEST_ID_MARKER)? + REQUEST_ID_MARKER.len();
+        message[start..].strip_suffix(']')
+    }
+
+    /// Structured form used for `--format json` error output:
+    /// `{"error": {"kind": "api", "status": 404, "message": "...", "exit_code": 1, "request_id": "..."}}`.
+    /// An unexpected response adds its `status` and `content_type` the same way.
+    /// A stage error adds `stage` and the underlying error's kind as `cause`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut error = serde_json::json!({
+            "kind": self.kind(),
+            "message": self.message(),
+            "exit_code": self.exit_code(),
+        });
+        let cause = match self {
+            CliError::Stage { stage, source } => {
+                error["stage"] = serde_json::json!(stage.name());
+                error["cause"] = serde_json::json!(source.kind());
+                source.as_ref()
+            }
+            _ => self,
+        };
+        match cause {
+            CliError::Api { status, .. } => error["status"] = serde_json::json!(status),
+            CliError::UnexpectedResponse {
+                status,
+                content_type,
+                ..
+            } => {
+                error["status"] = serde_json::json!(status);
+                error["content_type"] = serde_json::json!(content_type);
+            }
+            _ => {}
+        }
+        if let Some(request_id) = self.request_id() {
+            error["request_id"] = serde_json::json!(request_id);
+        }
+        serde_json::json!({ "error": error })
+    }
+}
+
 #[allow(dead_code)]
 pub fn redact_sensitive_info(input: &str) -> String {
     let mut result = input.to_string();
-    
-    // Redact API keys (common patterns)
-    result = regex::Regex::new(r#"(?i)(api[_-]?key|apikey|token)["']?\s*[:=]\s*["']?[a-zA-Z0-9_-]{20,}["']?"#)
+
+    // Redact authentication and secret-looking headers regardless of value length
+    result = regex::Regex::new(r"(?im)\b(authorization|appian-api-key|[a-z0-9-]*(?:token|secret|password|cookie|api-key)[a-z0-9-]*)\s*:\s*[^\r\n]+")
         .unwrap()
-        .replace_all(&result, "$1=***REDACTED***")
+        .replace_all(&result, "$1: ***REDACTED***")
         .to_string();
-    
+
+    // Redact API keys (common patterns)
+    result = regex::Regex::new(
+        r#"(?i)(api[_-]?key|apikey|token)["']?\s*[:=]\s*["']?[a-zA-Z0-9_-]{20,}["']?"#,
+    )
+    .unwrap()
+    .replace_all(&result, "$1=***REDACTED***")
+    .to_string();
+
     // Redact URLs with embedded credentials
     result = regex::Regex::new(r"(?i)(https?://)[a-zA-Z0-9_-]+:[^@]+@")
         .unwrap()
         .replace_all(&result, "${1}***:***@")
         .to_string();
-    
+
     result
 }
 
@@ -112,6 +310,62 @@ mod tests {
         assert_eq!(CliError::Api { status: 500, message: "test".to_string() }.exit_code(), 5);
         assert_eq!(CliError::Api { status: 400, message: "test".to_string() }.exit_code(), 1);
         assert_eq!(CliError::Timeout("test".to_string()).exit_code(), 6);
+        assert_eq!(CliError::RolledBack("test".to_string()).exit_code(), 7);
+        assert_eq!(CliError::PendingReview("test".to_string()).exit_code(), 8);
+    }
+
+    #[test]
+    fn test_error_json() {
+        let json = CliError::Api {
+            status: 404,
+            message: "Resource not found".to_string(),
+        }
+        .to_json();
+        assert_eq!(json["error"]["kind"], "api");
+        assert_eq!(json["error"]["status"], 404);
+        assert_eq!(json["error"]["message"], "Resource not found");
+
+        let message = with_request_id("Resource not found".to_string(), Some("abc-123"));
+        let json = CliError::Api {
+            status: 404,
+            message,
+        }
+        .to_json();
+        assert_eq!(json["error"]["request_id"], "abc-123");
+
+        let json = CliError::Timeout("too slow".to_string()).to_json();
+        assert_eq!(json["error"]["kind"], "timeout");
+        assert_eq!(json["error"]["exit_code"], 6);
+        let json = CliError::MaxPolls("3 polls".to_string()).to_json();
+        assert_eq!(json["error"]["kind"], "max_polls");
+        assert_eq!(json["error"]["exit_code"], 6);
+        assert!(json["error"].get("status").is_none());
+        assert!(json["error"].get("request_id").is_none());
+
+        let message = with_request_id("Service unavailable".to_string(), Some("req-9"));
+        let err = Stage::Export.wrap(CliError::Api {
+            status: 503,
+            message,
+        });
+        assert_eq!(err.exit_code(), 10);
+        let json = err.to_json();
+        assert_eq!(json["error"]["kind"], "stage_failed");
+        assert_eq!(json["error"]["stage"], "export");
+        assert_eq!(json["error"]["cause"], "api");
+        assert_eq!(json["error"]["status"], 503);
+        assert_eq!(json["error"]["request_id"], "req-9");
+        assert_eq!(
+            Stage::Wait
+                .wrap(CliError::Timeout("t".to_string()))
+                .exit_code(),
+            11
+        );
+        assert_eq!(
+            Stage::Download
+                .wrap(CliError::FileSystem("f".to_string()))
+                .exit_code(),
+            12
+        );
     }
 
     #[test]
@@ -126,5 +380,11 @@ mod tests {
         assert!(redacted.contains("***REDACTED***"));
         assert!(redacted.contains("***:***@"));
         assert!(redacted.contains("\"normal\": \"value\""));
+
+        let header = redact_sensitive_info("authorization: Bearer short-key");
+        assert_eq!(header, "authorization: ***REDACTED***");
+        let header = redact_sensitive_info("X-Gateway-Token: abc");
+        assert_eq!(header, "X-Gateway-Token: ***REDACTED***");
+        assert_eq!(redact_sensitive_info("X-Tenant: acme"), "X-Tenant: acme");
     }
 }
\ No newline at end of file
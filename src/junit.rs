@@ -0,0 +1,209 @@
+//! JUnit XML rendering for `--format junit`, so inspection and import
+//! outcomes show up in a CI system's test results.
+//!
+//! Each object problem becomes a `<testcase>`: errors and failed objects
+//! fail, warnings pass with the warning in `<system-out>`.
+
+use crate::models::{ImportDeploymentResults, InspectionResults};
+use std::fmt::Write;
+
+/// One `<testcase>` element
+struct TestCase {
+    class_name: String,
+    name: String,
+    failure: Option<String>,
+    output: Option<String>,
+}
+
+impl TestCase {
+    fn passed(class_name: &str, name: &str) -> Self {
+        TestCase {
+            class_name: class_name.to_string(),
+            name: name.to_string(),
+            failure: None,
+            output: None,
+        }
+    }
+
+    fn failed(class_name: &str, name: &str, message: String) -> Self {
+        TestCase {
+            failure: Some(message),
+            ..TestCase::passed(class_name, name)
+        }
+    }
+}
+
+/// Report for an inspection: one case per error and warning, or a single
+/// passing case when the inspection found nothing.
+pub fn inspection_report(uuid: &str, results: &InspectionResults) -> String {
+    let problems = &results.summary.problems;
+    let mut cases: Vec<TestCase> = problems
+        .errors
+        .iter()
+        .map(|e| {
+            TestCase::failed(
+                "inspection.errors",
+                &object_label(&e.object_name, &e.object_uuid),
+                e.error_message.clone(),
+            )
+        })
+        .collect();
+    cases.extend(problems.warnings.iter().map(|w| TestCase {
+        output: Some(format!("Warning: {}", w.warning_message)),
+        ..TestCase::passed(
+            "inspection.warnings",
+            &object_label(&w.object_name, &w.object_uuid),
+        )
+    }));
+
+    // Errors may be counted without being listed
+    let unlisted =
+        problems.total_errors as usize - problems.errors.len().min(problems.total_errors as usize);
+    if unlisted > 0 {
+        cases.push(TestCase::failed(
+            "inspection",
+            "unlisted errors",
+            format!("{} error(s) reported without details", unlisted),
+        ));
+    }
+    if cases.is_empty() {
+        cases.push(TestCase::passed("inspection", "package"));
+    }
+
+    render(&format!("Appian inspection {}", uuid), &cases)
+}
+
+/// Report for an import: one case per summary category, failing when any
+/// of its items failed, plus one failing case per listed object error.
+pub fn import_report(uuid: &str, results: &ImportDeploymentResults) -> String {
+    let summary = &results.summary;
+    let category = |name: &str, total: u32, failed: u32| {
+        if failed > 0 {
+            TestCase::failed(
+                "import",
+                name,
+                format!("{} of {} failed to import", failed, total),
+            )
+        } else {
+            TestCase::passed("import", name)
+        }
+    };
+
+    let mut cases = vec![
+        category(
+            "Admin Console Settings",
+            summary.admin_console_settings.total,
+            summary.admin_console_settings.failed,
+        ),
+        category("Objects", summary.objects.total, summary.objects.failed),
+        TestCase::passed("import", "Plugins"),
+    ];
+    cases.extend(summary.objects.errors.iter().map(|e| {
+        TestCase::failed(
+            "import.objects",
+            &object_label(&e.object_name, &e.object_uuid),
+            e.error_message.clone(),
+        )
+    }));
+
+    render(&format!("Appian deployment {}", uuid), &cases)
+}
+
+fn object_label(name: &str, uuid: &str) -> String {
+    format!("{} ({})", name, uuid)
+}
+
+fn render(suite: &str, cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|c| c.failure.is_some()).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{}\" failures=\"{}\">",
+        cases.len(),
+        failures
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">",
+        escape(suite),
+        cases.len(),
+        failures
+    );
+    for case in cases {
+        let _ = write!(
+            xml,
+            "    <testcase classname=\"{}\" name=\"{}\"",
+            escape(&case.class_name),
+            escape(&case.name)
+        );
+        if case.failure.is_none() && case.output.is_none() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        if let Some(message) = &case.failure {
+            let _ = writeln!(
+                xml,
+                "      <failure message=\"{}\">{}</failure>",
+                escape(message),
+                escape(message)
+            );
+        }
+        if let Some(output) = &case.output {
+            let _ = writeln!(xml, "      <system-out>{}</system-out>", escape(output));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspection_report() {
+        let results: InspectionResults = serde_json::from_str(
+            r#"{
+                "summary": {
+                    "adminConsoleSettingsExpected": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                    "objectsExpected": {"total": 2, "imported": 1, "failed": 1, "skipped": 0},
+                    "problems": {
+                        "totalErrors": 1,
+                        "totalWarnings": 1,
+                        "errors": [{"errorMessage": "Missing <rule> & co", "objectName": "My Rule", "objectUuid": "abc"}],
+                        "warnings": [{"warningMessage": "Deprecated", "objectName": "Old UI", "objectUuid": "def"}]
+                    }
+                },
+                "status": "COMPLETED"
+            }"#,
+        )
+        .unwrap();
+
+        let xml = inspection_report("1234", &results);
+        assert!(xml.contains(
+            r#"<testsuite name="Appian inspection 1234" tests="2" failures="1" errors="0">"#
+        ));
+        assert!(xml.contains(r#"name="My Rule (abc)""#));
+        assert!(xml.contains(r#"<failure message="Missing &lt;rule&gt; &amp; co">"#));
+        assert!(xml.contains("<system-out>Warning: Deprecated</system-out>"));
+    }
+}
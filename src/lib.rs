@@ -1,10 +1,49 @@
+pub mod archive;
+pub mod audit;
+#[cfg(any(feature = "deploy", feature = "download"))]
+pub mod checksum;
 pub mod client;
 pub mod commands;
 pub mod config;
+pub mod duration;
+pub mod endpoints;
 pub mod error;
+pub mod junit;
+pub mod markdown;
 pub mod models;
+pub mod output;
+pub mod polling;
+pub mod query;
+pub mod rate_limit;
+pub mod render;
+#[cfg(feature = "logs")]
+pub mod state;
+pub mod text;
+pub mod transport;
 
 pub use client::Client;
 pub use config::Config;
 pub use error::{CliError, Result};
-pub use models::*;
\ No newline at end of file
+pub use models::*;
+
+// Programmatic API: each command's `run` returns typed results instead of printing
+#[cfg(feature = "deploy")]
+pub use commands::deploy::{run as deploy, DeployArgs, DeployPlan};
+#[cfg(feature = "status")]
+pub use commands::deployment_results::run as deployment_results;
+#[cfg(feature = "download")]
+pub use commands::download_package::{run as download_package, DownloadResult};
+#[cfg(feature = "export")]
+pub use commands::export::run as export;
+#[cfg(feature = "get_packages")]
+pub use commands::get_packages::run as get_packages;
+#[cfg(feature = "validate")]
+pub use commands::inspect::run as inspect;
+#[cfg(feature = "validate")]
+pub use commands::inspection_results::run as inspection_results;
+#[cfg(feature = "logs")]
+pub use commands::logs::run as logs;
+#[cfg(feature = "monitor")]
+pub use commands::monitor::run as monitor;
+#[cfg(feature = "status")]
+pub use commands::status::run as status;
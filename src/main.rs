@@ -1,16 +1,24 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use colored::*;
 use std::path::PathBuf;
+#[cfg(any(
+    feature = "validate",
+    feature = "deploy",
+    feature = "status",
+    feature = "monitor"
+))]
+use std::time::Duration;
 use tracing::info;
 
-
-mod client;
-mod commands;
-mod config;
-mod error;
-mod models;
-
-use crate::config::{Config, CliOverrides};
-use crate::error::Result;
+use appian_deployment_cli::audit::{self, AuditRecord};
+use appian_deployment_cli::client::HTTP_TRACE_TARGET;
+use appian_deployment_cli::commands;
+use appian_deployment_cli::config::{self, CliOverrides, Config};
+use appian_deployment_cli::duration;
+use appian_deployment_cli::error::{CliError, Result};
+use appian_deployment_cli::output;
+#[cfg(any(feature = "validate", feature = "deploy", feature = "status"))]
+use appian_deployment_cli::polling::WaitOptions;
 
 #[derive(Parser)]
 #[command(name = "appian-deployment-cli")]
@@ -21,8 +29,12 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    #[arg(long, global = true, help = "Configuration file path")]
-    config_file: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Configuration file path (repeatable; later files overlay earlier ones)"
+    )]
+    config_file: Vec<PathBuf>,
 
     #[arg(long, global = true, help = "Base URL for Appian API")]
     base_url: Option<String>,
@@ -33,13 +45,185 @@ struct Cli {
     #[arg(long, global = true, help = "Enable verbose output")]
     verbose: bool,
 
-    #[arg(long, global = true, help = "Suppress non-essential output")]
+    #[arg(
+        long,
+        global = true,
+        help = "Suppress non-essential output; export, deploy and inspect print only the resulting UUID"
+    )]
     quiet: bool,
 
-    #[arg(long, global = true, help = "Output format (text or json)")]
+    #[arg(
+        long,
+        global = true,
+        help = "Output format (text, json, junit/markdown for get-inspection and get-deployment-results, or csv for logs)"
+    )]
     format: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print JSON output on a single line instead of pretty-printed"
+    )]
+    json_compact: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Print only the values at a dotted path in the JSON result (e.g. status, summary.objects.failed, logs.*.message); implies --format json"
+    )]
+    query: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Report the request ids the server sent back (in JSON results as requestIds, or as a final status line), to match a run with the server's logs"
+    )]
+    include_request_ids: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Log redacted HTTP request/response bodies"
+    )]
+    trace_http: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "WHEN",
+        help = "Use colors: auto (on a terminal, unless NO_COLOR is set), always or never"
+    )]
+    color: Option<output::ColorChoice>,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "color",
+        help = "Disable colored output; same as --color never"
+    )]
+    no_color: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "SIZE",
+        help = "Refuse deploy/inspect uploads whose files total more than SIZE (e.g. 500MB)"
+    )]
+    max_upload_size: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        help = "Give up with a timeout error once the whole command has run this long, retries and polling included (e.g. 30m)"
+    )]
+    deadline: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        help = "Cancel and retry any single request still waiting for a response after this long (e.g. 15s); uploads are exempt"
+    )]
+    attempt_timeout: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Send at most N requests per second, shared by concurrent requests (default 10; 0 for no limit)"
+    )]
+    rate_limit: Option<u32>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "CODES",
+        help = "Comma-separated HTTP statuses to retry (default 429,502,503,504)"
+    )]
+    retry_on: Option<String>,
+
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), help = "Give up waiting after N status polls, or at the timeout if that comes first")]
+    max_polls: Option<u32>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        help = "Long-poll export status: let the server hold each poll this long until the status changes (e.g. 30s); falls back to interval polling when unsupported"
+    )]
+    long_poll: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        help = "Retry a request that timed out at most N times (0 never resends it); overrides monitor.max_retries_timeout"
+    )]
+    api_timeout_retries: Option<u32>,
+
+    #[arg(
+        long = "header",
+        global = true,
+        value_name = "NAME: VALUE",
+        help = "Extra header for every request (repeatable)"
+    )]
+    headers: Vec<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Allow --header / config headers to replace the authentication headers"
+    )]
+    allow_auth_header_override: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "User-Agent sent with every request (default appian-deployment-cli/<version>)"
+    )]
+    user_agent: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        help = "POST a JSON summary to URL when monitor or deploy --wait finishes"
+    )]
+    notify_webhook: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "WHEN",
+        help = "When to call the webhook: always (default) or failure"
+    )]
+    notify_on: Option<config::NotifyOn>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT",
+        help = "Webhook payload shape: raw (default), slack or teams"
+    )]
+    notify_format: Option<config::NotifyFormat>,
+
+    /// Offline mode for demos and CI: answer requests from saved responses
+    #[arg(long, global = true, hide = true, value_name = "PATH")]
+    replay_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Write the raw body of each successful API response to PATH (unredacted)"
+    )]
+    save_response: Option<PathBuf>,
 }
 
+// Parsed once per run, so the size of the deploy variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(feature = "get_packages")]
@@ -47,15 +231,37 @@ enum Commands {
     GetPackages {
         #[arg(long, help = "Application UUID (repeatable)")]
         app_uuid: Vec<String>,
+
+        #[arg(
+            long,
+            default_value = "4",
+            help = "With several application UUIDs, how many requests to run at once"
+        )]
+        concurrency: usize,
     },
 
+    #[cfg(feature = "deploy")]
+    #[command(about = "List the data sources a deployment can target with --data-source")]
+    ListDataSources,
+
     #[cfg(feature = "export")]
-    #[command(about = "Export application to artifact zip")]
+    #[command(
+        about = "Export application to artifact zip",
+        args_conflicts_with_subcommands = true
+    )]
     Export {
-        #[arg(long, value_delimiter = ',', help = "UUIDs to export (repeatable or comma-separated)")]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "UUIDs to export (repeatable or comma-separated)"
+        )]
         uuids: Vec<String>,
 
-        #[arg(long, default_value = "package", help = "Export type (package|application)")]
+        #[arg(
+            long,
+            default_value = "package",
+            help = "Export type (package|application)"
+        )]
         export_type: String,
 
         #[arg(long, help = "Export name")]
@@ -66,6 +272,86 @@ enum Commands {
 
         #[arg(long, help = "Validate without execution")]
         dry_run: bool,
+
+        #[arg(
+            long,
+            requires = "dry_run",
+            help = "With --dry-run, estimate the export's object count and size from package metadata"
+        )]
+        estimate: bool,
+
+        #[arg(
+            long,
+            help = "Print the HTTP request that would be sent and exit without calling the API"
+        )]
+        print_request: bool,
+
+        #[arg(
+            long,
+            default_value = "4",
+            help = "With several package UUIDs, how many exports to run at once"
+        )]
+        concurrency: usize,
+
+        #[arg(
+            long,
+            help = "With several package UUIDs, keep starting exports after one fails"
+        )]
+        continue_on_error: bool,
+
+        #[command(subcommand)]
+        action: Option<ExportCommands>,
+    },
+
+    #[cfg(all(feature = "export", feature = "monitor", feature = "download"))]
+    #[command(
+        name = "export-and-download",
+        about = "Export, wait for the export to finish and download its package"
+    )]
+    ExportAndDownload {
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "UUIDs to export (repeatable or comma-separated)"
+        )]
+        uuids: Vec<String>,
+
+        #[arg(
+            long,
+            default_value = "package",
+            help = "Export type (package|application)"
+        )]
+        export_type: String,
+
+        #[arg(long, help = "Export name")]
+        name: Option<String>,
+
+        #[arg(long, help = "Export description")]
+        description: Option<String>,
+
+        #[arg(long, help = "Output directory or file")]
+        output: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "File name template, e.g. \"{name}-{date}-{uuid}.{ext}\" (tokens: uuid, name, date, status, ext)"
+        )]
+        name_template: Option<String>,
+
+        #[arg(long, help = "Overwrite existing files")]
+        overwrite: bool,
+
+        #[arg(long, visible_alias = "interval-seconds", value_name = "DURATION", default_value = "10s", value_parser = duration::parse_wait_duration, help = "Polling interval (e.g. 30s or 1m; a bare number is seconds)")]
+        interval: Duration,
+
+        #[arg(long, visible_alias = "timeout-seconds", value_name = "DURATION", default_value = "1h", value_parser = duration::parse_wait_duration, help = "Give up waiting for the export after this long (e.g. 90m or 2h; a bare number is seconds)")]
+        timeout: Duration,
+
+        #[arg(
+            long,
+            help = "Download the package of an export that completed with errors instead of failing"
+        )]
+        allow_export_errors: bool,
     },
 
     #[cfg(feature = "validate")]
@@ -79,6 +365,22 @@ enum Commands {
 
         #[arg(long, help = "Admin Console settings zip (.zip)")]
         admin_console_file: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Print the HTTP request that would be sent and exit without calling the API"
+        )]
+        print_request: bool,
+    },
+
+    #[cfg(feature = "validate")]
+    #[command(
+        name = "inspect-local",
+        about = "Show the manifest version and objects of a package zip, without contacting the server"
+    )]
+    InspectLocal {
+        #[arg(value_name = "PACKAGE_ZIP", help = "Package zip file path")]
+        package: PathBuf,
     },
 
     #[cfg(feature = "validate")]
@@ -86,13 +388,103 @@ enum Commands {
     GetInspection {
         #[arg(long, help = "Inspection UUID")]
         uuid: String,
+
+        #[arg(
+            long,
+            visible_alias = "poll",
+            help = "Poll until the inspection completes or fails"
+        )]
+        wait: bool,
+
+        #[arg(long, value_name = "DURATION", requires = "wait", value_parser = duration::parse_wait_duration, help = "With --wait, the polling interval (default 10s)")]
+        interval: Option<Duration>,
+
+        #[arg(long, value_name = "DURATION", requires = "wait", value_parser = duration::parse_wait_duration, help = "With --wait, give up after this long (default 1h)")]
+        timeout: Option<Duration>,
+
+        #[arg(
+            long,
+            help = "Print only the counts, without the per-object errors and warnings (JSON output is unaffected)"
+        )]
+        summary_only: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with = "summary_only",
+            help = "List at most N errors and N warnings (JSON output is unaffected)"
+        )]
+        max_entries: Option<usize>,
+
+        #[arg(
+            long,
+            conflicts_with = "wait",
+            help = "Print the response body exactly as received, without parsing it"
+        )]
+        raw: bool,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with = "raw",
+            help = "Also write every error and warning to PATH (.csv or .json) for triage"
+        )]
+        export_problems: Option<PathBuf>,
     },
 
     #[cfg(feature = "deploy")]
     #[command(about = "Deploy package to target environment")]
+    #[command(group(clap::ArgGroup::new("waits").args(["wait", "inspect_first"]).multiple(true)))]
     Deploy {
-        #[arg(long, help = "Package zip file path")]
-        package_zip_name: PathBuf,
+        #[arg(
+            long,
+            help = "Package zip file path, or - to read the package from stdin (optional for a plug-ins-only deployment with --plugins-file)"
+        )]
+        package_zip_name: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "FILE_NAME",
+            requires = "package_zip_name",
+            help = "With --package-zip-name -, the file name to upload the package from stdin as (e.g. app.zip)"
+        )]
+        package_name: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "EXPORT_UUID",
+            conflicts_with = "package_zip_name",
+            help = "Download a completed export's package, customization and plug-ins files and deploy them"
+        )]
+        from_export: Option<String>,
+
+        #[arg(
+            long,
+            requires = "from_export",
+            help = "With --from-export, keep the downloaded files instead of deleting them"
+        )]
+        keep_artifacts: bool,
+
+        #[arg(long, value_name = "URL", conflicts_with_all = ["package_zip_name", "from_export"], help = "Download the package zip from an https URL and deploy it")]
+        package_url: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "HEX",
+            requires = "package_url",
+            help = "Expected SHA-256 of the package downloaded with --package-url"
+        )]
+        package_sha256: Option<String>,
+
+        #[arg(long, value_name = "DEPLOYMENT_UUID", requires = "package_zip_name", conflicts_with_all = ["from_export", "package_url"], help = "Redeploy --package-zip-name after an earlier deployment completed with import errors")]
+        retry_failed: Option<String>,
+
+        #[arg(
+            long,
+            requires = "retry_failed",
+            help = "Run the database scripts again when retrying with --retry-failed"
+        )]
+        rerun_database_scripts: bool,
 
         #[arg(long, help = "Deployment name")]
         name: String,
@@ -112,14 +504,95 @@ enum Commands {
         #[arg(long, help = "Admin Console settings zip (.zip)")]
         admin_console_file: Option<PathBuf>,
 
+        #[arg(
+            long,
+            value_name = "ID",
+            conflicts_with = "admin_console_file",
+            help = "Reference to Admin Console settings already on the server, instead of uploading --admin-console-file"
+        )]
+        admin_console_ref: Option<String>,
+
         #[arg(long, help = "Plug-ins file (.zip)")]
         plugins_file: Option<PathBuf>,
 
         #[arg(long, help = "Data source name or UUID")]
         data_source: Option<String>,
 
-        #[arg(long, value_delimiter = ',', help = "Comma-separated database scripts (.sql,.ddl) in execution order")]
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Comma-separated database scripts (.sql,.ddl) in execution order"
+        )]
         database_scripts: Option<Vec<PathBuf>>,
+
+        #[arg(
+            long,
+            value_name = "PATH:ORDER",
+            help = "Database script with explicit execution order (repeatable; cannot be combined with --database-scripts)"
+        )]
+        database_script: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Require database script orders to be contiguous starting at 1"
+        )]
+        strict_order: bool,
+
+        #[arg(long, help = "Treat pre-flight warnings as errors")]
+        strict: bool,
+
+        #[arg(
+            long = "label",
+            value_name = "KEY=VALUE",
+            help = "Label recorded in the command's output, e.g. a CI pipeline ID or git SHA (repeatable; not sent to Appian)"
+        )]
+        labels: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Inspect the package first and deploy only if the inspection finds no errors (exit 2 otherwise)"
+        )]
+        inspect_first: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            default_value = "0",
+            requires = "inspect_first",
+            help = "With --inspect-first, deploy despite up to N inspection errors"
+        )]
+        max_errors: u32,
+
+        #[arg(
+            long,
+            help = "Wait for the deployment to finish; exits non-zero unless it succeeds"
+        )]
+        wait: bool,
+
+        #[arg(long, value_name = "DURATION", requires = "waits", value_parser = duration::parse_wait_duration, help = "With --wait or --inspect-first, the polling interval (default 10s)")]
+        interval: Option<Duration>,
+
+        #[arg(long, value_name = "DURATION", requires = "waits", value_parser = duration::parse_wait_duration, help = "With --wait or --inspect-first, give up waiting after this long (default 1h each)")]
+        timeout: Option<Duration>,
+
+        #[arg(
+            long,
+            requires = "wait",
+            help = "With --wait, treat a deployment pending review as failed (exit 5) instead of exit 8"
+        )]
+        fail_on_pending: bool,
+
+        #[arg(
+            long,
+            help = "Deploy even if another deployment is in progress on the site"
+        )]
+        force: bool,
+
+        #[arg(
+            long,
+            help = "Print the HTTP request that would be sent and exit without calling the API"
+        )]
+        print_request: bool,
     },
 
     #[cfg(feature = "status")]
@@ -130,16 +603,61 @@ enum Commands {
 
         #[arg(long, help = "Operation kind (export or deployment)")]
         kind: Option<String>,
+
+        #[arg(
+            long,
+            help = "Print the response body exactly as received, without parsing it"
+        )]
+        raw: bool,
     },
 
     #[cfg(feature = "status")]
     #[command(about = "Retrieve deployment results", alias = "results")]
     GetDeploymentResults {
-        #[arg(long, alias = "uuid", help = "Deployment UUID", value_name = "DEPLOYMENT_UUID")]
+        #[arg(
+            long,
+            alias = "uuid",
+            help = "Deployment UUID",
+            value_name = "DEPLOYMENT_UUID"
+        )]
         deployment_uuid: String,
 
         #[arg(long, help = "Poll until terminal status before printing results")]
         poll: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "poll",
+            help = "Print summary count updates until terminal status"
+        )]
+        follow: bool,
+
+        #[arg(long, value_name = "DURATION", value_parser = duration::parse_wait_duration, help = "With --poll or --follow, the polling interval (default 10s)")]
+        interval: Option<Duration>,
+
+        #[arg(long, value_name = "DURATION", value_parser = duration::parse_wait_duration, help = "With --poll or --follow, give up after this long (default 10m)")]
+        timeout: Option<Duration>,
+
+        #[arg(
+            long,
+            help = "Print only the counts, without the per-object errors (JSON output is unaffected)"
+        )]
+        summary_only: bool,
+
+        #[arg(
+            long,
+            value_name = "N",
+            conflicts_with = "summary_only",
+            help = "List at most N failed objects (JSON output is unaffected)"
+        )]
+        max_entries: Option<usize>,
+
+        #[arg(
+            long,
+            conflicts_with = "follow",
+            help = "Print the results body exactly as received, without parsing it"
+        )]
+        raw: bool,
     },
 
     #[cfg(feature = "monitor")]
@@ -151,11 +669,24 @@ enum Commands {
         #[arg(long, help = "Operation kind (export or deployment)")]
         kind: Option<String>,
 
-        #[arg(long, default_value = "10", help = "Polling interval in seconds")]
-        interval_seconds: u64,
+        #[arg(long, visible_alias = "interval-seconds", value_name = "DURATION", default_value = "10s", value_parser = duration::parse_wait_duration, help = "Polling interval (e.g. 30s or 1m; a bare number is seconds)")]
+        interval: Duration,
+
+        #[arg(long, visible_alias = "timeout-seconds", value_name = "DURATION", default_value = "1h", value_parser = duration::parse_wait_duration, help = "Give up waiting after this long (e.g. 90m or 2h; a bare number is seconds)")]
+        timeout: Duration,
+
+        #[arg(
+            long,
+            help = "Treat a deployment pending review as failed (exit 5) instead of exit 8"
+        )]
+        fail_on_pending: bool,
 
-        #[arg(long, help = "Timeout in seconds")]
-        timeout_seconds: Option<u64>,
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Write Prometheus text-format metrics to PATH after every poll"
+        )]
+        metrics_file: Option<PathBuf>,
     },
 
     #[cfg(feature = "download")]
@@ -167,8 +698,25 @@ enum Commands {
         #[arg(long, help = "Output directory or file")]
         output: Option<PathBuf>,
 
+        #[arg(
+            long,
+            help = "File name template, e.g. \"{name}-{date}-{uuid}.{ext}\" (tokens: uuid, name, date, status, ext)"
+        )]
+        name_template: Option<String>,
+
         #[arg(long, help = "Overwrite existing files")]
         overwrite: bool,
+
+        #[arg(long, requires = "expected_sha256", conflicts_with_all = ["output", "name_template", "overwrite"], help = "Stream the artifact and check its checksum without writing it to disk")]
+        verify_only: bool,
+
+        #[arg(
+            long,
+            value_name = "HASH",
+            requires = "verify_only",
+            help = "SHA-256 the artifact must match (with --verify-only)"
+        )]
+        expected_sha256: Option<String>,
     },
 
     #[cfg(feature = "logs")]
@@ -182,13 +730,157 @@ enum Commands {
 
         #[arg(long, help = "Number of lines to show from the end of logs")]
         tail: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Only show entries at or after an RFC3339 timestamp or a relative duration (e.g. 10m, 1h)"
+        )]
+        since: Option<String>,
+
+        #[arg(long, conflicts_with_all = ["since", "follow"], help = "Only show entries logged after those shown by the previous --since-last-run for this deployment")]
+        since_last_run: bool,
+
+        #[arg(
+            long,
+            help = "Only show entries at this level or more severe (error|warn|info|debug)"
+        )]
+        min_level: Option<appian_deployment_cli::models::LogLevel>,
+
+        #[arg(long, conflicts_with_all = ["follow", "tail", "since", "since_last_run", "min_level"], help = "Print the first page of the response body exactly as received, without parsing it")]
+        raw: bool,
     },
+
+    #[command(subcommand, about = "Work with configuration files")]
+    Config(ConfigCommands),
+
+    #[command(about = "Print the version, compiled-in features and output formats as JSON")]
+    Capabilities,
+}
+
+#[cfg(feature = "export")]
+#[derive(Subcommand)]
+enum ExportCommands {
+    #[command(about = "Cancel a running export and print its resulting status")]
+    Cancel {
+        #[arg(value_name = "UUID", help = "Export UUID")]
+        uuid: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    #[command(about = "Check config files for problems without contacting the server")]
+    Validate,
+}
+
+/// Id of the per-command `--output-format` flag
+const OUTPUT_FORMAT: &str = "output_format";
+
+/// Give every subcommand an `--output-format` that overrides the global
+/// `--format` for that command, so a script can set the global flag once
+/// and still pick another format for one stage.
+fn with_output_format(command: clap::Command) -> clap::Command {
+    command.mut_subcommands(|sub| {
+        with_output_format(
+            sub.arg(
+                clap::Arg::new(OUTPUT_FORMAT)
+                    .long("output-format")
+                    .value_name("FORMAT")
+                    .help(
+                        "Output format for this command; takes precedence over the global --format",
+                    ),
+            ),
+        )
+    })
+}
+
+/// The `--output-format` of the innermost subcommand that was given one
+fn command_format(matches: &ArgMatches) -> Option<String> {
+    let mut format = None;
+    let mut current = matches;
+    while let Some((_, sub)) = current.subcommand() {
+        if let Ok(Some(value)) = sub.try_get_one::<String>(OUTPUT_FORMAT) {
+            format = Some(value.clone());
+        }
+        current = sub;
+    }
+    format
 }
 
 #[tokio::main]
-async fn main() -> crate::error::Result<()> {
-    let cli = Cli::parse();
+async fn main() {
+    let matches = with_output_format(Cli::command()).get_matches();
+    let invocation = Invocation::from_matches(&matches);
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(format) = command_format(&matches) {
+        cli.format = Some(format);
+    }
+    if let Some(query) = &cli.query {
+        output::set_query(query);
+        cli.format = Some("json".to_string());
+    }
+    let format = cli.format.clone();
+
+    let color = if cli.no_color {
+        output::ColorChoice::Never
+    } else {
+        cli.color.unwrap_or_default()
+    };
+    output::set_color(color);
+    output::set_quiet(cli.quiet);
+    output::set_compact_json(cli.json_compact);
+    output::set_include_request_ids(cli.include_request_ids);
+
+    if let Err(err) = run(cli, invocation).await {
+        report_error(&err, format.as_deref());
+        std::process::exit(err.exit_code());
+    }
+}
+
+fn report_error(err: &CliError, format: Option<&str>) {
+    match format {
+        Some("json") => {
+            let json_output = output::to_json_string(&output::error_json(err))
+                .unwrap_or_else(|_| err.to_string());
+            eprintln!("{}", json_output);
+        }
+        _ => eprintln!(
+            "{}",
+            output::tagged(format!("{} {}", "Error:".red().bold(), err))
+        ),
+    }
+}
+
+/// What the audit log records about the command line
+struct Invocation {
+    /// Subcommand path, e.g. `deploy` or `config validate`
+    command: String,
+    /// The UUID the command was pointed at, if any
+    target_uuid: Option<String>,
+}
 
+impl Invocation {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let mut names = Vec::new();
+        let mut target_uuid = None;
+        let mut current = matches;
+        while let Some((name, sub)) = current.subcommand() {
+            names.push(name.to_string());
+            for id in ["deployment_uuid", "uuid", "from_export"] {
+                if let Ok(Some(uuid)) = sub.try_get_one::<String>(id) {
+                    target_uuid.get_or_insert_with(|| uuid.clone());
+                }
+            }
+            current = sub;
+        }
+        Invocation {
+            command: names.join(" "),
+            target_uuid,
+        }
+    }
+}
+
+async fn run(cli: Cli, invocation: Invocation) -> Result<()> {
     // Initialize logging
     let _log_level = if cli.verbose {
         "debug"
@@ -202,134 +894,497 @@ async fn main() -> crate::error::Result<()> {
 
     info!("Appian Deployment CLI starting");
 
+    // Checked offline, before a configuration is required
+    if let Commands::Config(ConfigCommands::Validate) = cli.command {
+        return commands::config::validate(&cli.config_file, cli.format);
+    }
+    if let Commands::Capabilities = cli.command {
+        return commands::capabilities::execute();
+    }
+    #[cfg(feature = "validate")]
+    if let Commands::InspectLocal { package } = cli.command {
+        return commands::inspect_local::execute(package, cli.format);
+    }
+
+    let max_upload_bytes = cli
+        .max_upload_size
+        .as_deref()
+        .map(config::parse_byte_size)
+        .transpose()
+        .map_err(|e| CliError::InvalidArgument(format!("--max-upload-size: {:#}", e)))?;
+
+    let deadline_seconds = cli
+        .deadline
+        .as_deref()
+        .map(duration::parse_duration)
+        .transpose()?
+        .map(|d| d.as_secs());
+
+    let attempt_timeout_seconds = cli
+        .attempt_timeout
+        .as_deref()
+        .map(duration::parse_duration)
+        .transpose()?
+        .map(|d| d.as_secs());
+    if attempt_timeout_seconds == Some(0) {
+        return Err(CliError::InvalidArgument(
+            "--attempt-timeout must be at least 1 second".to_string(),
+        ));
+    }
+
+    let long_poll_seconds = cli
+        .long_poll
+        .as_deref()
+        .map(duration::parse_duration)
+        .transpose()?
+        .map(|d| d.as_secs());
+    if long_poll_seconds == Some(0) {
+        return Err(CliError::InvalidArgument(
+            "--long-poll must be at least 1 second".to_string(),
+        ));
+    }
+
+    let retry_on = cli
+        .retry_on
+        .as_deref()
+        .map(config::parse_status_codes)
+        .transpose()
+        .map_err(|e| CliError::InvalidArgument(format!("--retry-on: {:#}", e)))?;
+
+    let headers = cli
+        .headers
+        .iter()
+        .map(|header| config::parse_header(header))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| CliError::InvalidArgument(format!("--header: {:#}", e)))?;
+
     let cli_overrides = CliOverrides {
         base_url: cli.base_url.clone(),
         api_key: cli.api_key.clone(),
+        trace_http: cli.trace_http,
+        max_upload_bytes,
+        deadline_seconds,
+        attempt_timeout_seconds,
+        rate_limit_per_second: cli.rate_limit,
+        retry_on,
+        max_polls: cli.max_polls,
+        long_poll_seconds,
+        api_timeout_retries: cli.api_timeout_retries,
+        headers,
+        allow_auth_header_override: cli.allow_auth_header_override,
+        user_agent: cli.user_agent.clone(),
+        notify_webhook: cli.notify_webhook.clone(),
+        notify_on: cli.notify_on,
+        notify_format: cli.notify_format,
+        replay_file: cli.replay_file.clone(),
+        save_response: cli.save_response.clone(),
     };
-    let config = Config::load(cli.config_file.clone(), &cli_overrides)?;
+    let config = Config::load(&cli.config_file, &cli_overrides)
+        .map_err(|e| CliError::Configuration(format!("{:#}", e)))?;
+
+    if let Some(name) = config.environment_name() {
+        output::set_environment(&name);
+    }
+
+    let audit_log = config.audit_log.clone();
+    let base_url = config.base_url.clone();
+    let text_request_ids = cli.include_request_ids && cli.format.as_deref() != Some("json");
+    let result = dispatch(cli.command, config, cli.format).await;
+
+    let request_ids = output::request_ids();
+    if text_request_ids && !request_ids.is_empty() {
+        output::status(format!("Request IDs: {}", request_ids.join(", ")));
+    }
 
-    // Execute command
-    match cli.command {
+    // Best effort: a failed audit write must not change the command's outcome
+    if let Some(path) = audit_log {
+        let uuid = output::last_identifier().or(invocation.target_uuid);
+        let record = AuditRecord {
+            request_ids,
+            labels: output::labels(),
+            ..AuditRecord::new(&invocation.command, &base_url, uuid, &result)
+        };
+        if let Err(e) = audit::append(&path, &record) {
+            output::warning(e);
+        }
+    }
+    result
+}
+
+async fn dispatch(command: Commands, config: Config, format: Option<String>) -> Result<()> {
+    match command {
         #[cfg(feature = "get_packages")]
-        Commands::GetPackages { app_uuid } => {
-            commands::get_packages::execute(config, app_uuid, cli.format).await?;
+        Commands::GetPackages {
+            app_uuid,
+            concurrency,
+        } => {
+            commands::get_packages::execute(config, app_uuid, concurrency, format).await?;
+        }
+        #[cfg(feature = "deploy")]
+        Commands::ListDataSources => {
+            commands::list_data_sources::execute(config, format).await?;
         }
         #[cfg(feature = "export")]
-        Commands::Export { 
+        Commands::Export {
+            action: Some(ExportCommands::Cancel { uuid }),
+            ..
+        } => {
+            commands::export::execute_cancel(config, uuid, format).await?;
+        }
+        #[cfg(feature = "export")]
+        Commands::Export {
             uuids,
             export_type,
             name,
             description,
             dry_run,
+            estimate,
+            print_request,
+            concurrency,
+            continue_on_error,
+            action: None,
+        } => {
+            // Several packages fan out into one export each
+            if export_type.eq_ignore_ascii_case("package") && uuids.len() > 1 {
+                let requests = commands::export::build_package_requests(&uuids, name, description)?;
+                commands::export::execute_bulk(
+                    config,
+                    requests,
+                    dry_run,
+                    estimate,
+                    print_request,
+                    concurrency,
+                    continue_on_error,
+                    format,
+                )
+                .await?;
+            } else {
+                let request =
+                    commands::export::build_request(&uuids, &export_type, name, description)?;
+                commands::export::execute(
+                    config,
+                    request,
+                    dry_run,
+                    estimate,
+                    print_request,
+                    format,
+                )
+                .await?;
+            }
+        }
+        #[cfg(all(feature = "export", feature = "monitor", feature = "download"))]
+        Commands::ExportAndDownload {
+            uuids,
+            export_type,
+            name,
+            description,
+            output,
+            name_template,
+            overwrite,
+            interval,
+            timeout,
+            allow_export_errors,
         } => {
-            commands::export::execute(
+            let request = commands::export::build_request(&uuids, &export_type, name, description)?;
+            let download = commands::export_and_download::DownloadOptions {
+                output,
+                name_template,
+                overwrite,
+            };
+            commands::export_and_download::execute(
                 config,
-                uuids,
-                export_type,
-                name,
-                description,
-                dry_run,
-                cli.format,
-            ).await?;
+                request,
+                download,
+                interval,
+                timeout,
+                allow_export_errors,
+                format,
+            )
+            .await?;
         }
         #[cfg(feature = "validate")]
-        Commands::Inspect { package_zip_name, customization_file, admin_console_file } => {
+        Commands::Inspect {
+            package_zip_name,
+            customization_file,
+            admin_console_file,
+            print_request,
+        } => {
             commands::inspect::execute(
                 config,
                 package_zip_name,
                 customization_file,
                 admin_console_file,
-                cli.format,
-            ).await?;
+                print_request,
+                format,
+            )
+            .await?;
         }
         #[cfg(feature = "validate")]
-        Commands::GetInspection { uuid } => {
+        Commands::GetInspection {
+            uuid,
+            wait,
+            interval,
+            timeout,
+            summary_only,
+            max_entries,
+            raw,
+            export_problems,
+        } => {
             commands::inspection_results::execute(
                 config,
                 uuid,
-                cli.format,
-            ).await?;
+                wait.then_some(WaitOptions { interval, timeout }),
+                if summary_only { Some(0) } else { max_entries },
+                raw,
+                export_problems,
+                format,
+            )
+            .await?;
         }
         #[cfg(feature = "deploy")]
-        Commands::Deploy { 
+        Commands::Deploy {
             package_zip_name,
+            from_export,
+            keep_artifacts,
+            package_url,
+            package_sha256,
+            retry_failed,
+            rerun_database_scripts,
+            package_name,
             name,
             description,
             dry_run,
             rollback_on_failure,
             customization_file,
             admin_console_file,
+            admin_console_ref,
             plugins_file,
             data_source,
             database_scripts,
+            database_script,
+            strict_order,
+            strict,
+            labels,
+            inspect_first,
+            max_errors,
+            print_request,
+            wait,
+            interval,
+            timeout,
+            fail_on_pending,
+            force,
         } => {
-            commands::deploy::execute(
-                config,
+            let wait = wait.then_some(WaitOptions { interval, timeout });
+            let args = commands::deploy::DeployArgs {
+                // Filled in from the export's artifacts with --from-export, downloaded with --package-url or read from stdin with -
                 package_zip_name,
                 name,
                 description,
-                dry_run,
                 rollback_on_failure,
                 customization_file,
                 admin_console_file,
+                admin_console_ref,
                 plugins_file,
                 data_source,
                 database_scripts,
-                cli.format,
-            ).await?;
+                database_script,
+                strict_order,
+                strict,
+                labels,
+                inspect_first: inspect_first.then_some(max_errors),
+                inspect_wait: WaitOptions { interval, timeout },
+                force,
+            };
+            match (retry_failed, from_export, package_url) {
+                (Some(deployment_uuid), _, _) => {
+                    commands::deploy::execute_retry_failed(
+                        config,
+                        deployment_uuid,
+                        args,
+                        rerun_database_scripts,
+                        dry_run,
+                        print_request,
+                        wait,
+                        fail_on_pending,
+                        format,
+                    )
+                    .await?;
+                }
+                (None, Some(export_uuid), _) => {
+                    commands::deploy::execute_from_export(
+                        config,
+                        export_uuid,
+                        keep_artifacts,
+                        args,
+                        dry_run,
+                        print_request,
+                        wait,
+                        fail_on_pending,
+                        format,
+                    )
+                    .await?;
+                }
+                (None, None, Some(package_url)) => {
+                    commands::deploy::execute_from_url(
+                        config,
+                        package_url,
+                        package_sha256,
+                        args,
+                        dry_run,
+                        print_request,
+                        wait,
+                        fail_on_pending,
+                        format,
+                    )
+                    .await?;
+                }
+                (None, None, None)
+                    if args.package_zip_name.as_deref() == Some(std::path::Path::new("-")) =>
+                {
+                    commands::deploy::execute_from_stdin(
+                        config,
+                        package_name,
+                        args,
+                        dry_run,
+                        print_request,
+                        wait,
+                        fail_on_pending,
+                        format,
+                    )
+                    .await?;
+                }
+                (None, None, None) => {
+                    if package_name.is_some() {
+                        return Err(CliError::InvalidArgument(
+                            "--package-name only applies to a package read from stdin (--package-zip-name -)".to_string(),
+                        ));
+                    }
+                    commands::deploy::execute(
+                        config,
+                        args,
+                        dry_run,
+                        print_request,
+                        wait,
+                        fail_on_pending,
+                        format,
+                    )
+                    .await?;
+                }
+            }
         }
         #[cfg(feature = "status")]
-        Commands::Status { deployment_uuid, kind } => {
-            commands::status::execute(config, deployment_uuid, kind, cli.format).await?;
+        Commands::Status {
+            deployment_uuid,
+            kind,
+            raw,
+        } => {
+            commands::status::execute(config, deployment_uuid, kind, raw, format).await?;
         }
         #[cfg(feature = "status")]
-        Commands::GetDeploymentResults { deployment_uuid, poll } => {
-            commands::deployment_results::execute(config, deployment_uuid, cli.format, poll).await?;
+        Commands::GetDeploymentResults {
+            deployment_uuid,
+            poll,
+            follow,
+            interval,
+            timeout,
+            summary_only,
+            max_entries,
+            raw,
+        } => {
+            let max_entries = if summary_only { Some(0) } else { max_entries };
+            let wait = WaitOptions { interval, timeout };
+            commands::deployment_results::execute(
+                config,
+                deployment_uuid,
+                format,
+                poll,
+                follow,
+                wait,
+                max_entries,
+                raw,
+            )
+            .await?;
         }
         #[cfg(feature = "monitor")]
-        Commands::Monitor { 
+        Commands::Monitor {
             deployment_uuid,
             kind,
-            interval_seconds,
-            timeout_seconds,
+            interval,
+            timeout,
+            fail_on_pending,
+            metrics_file,
         } => {
             commands::monitor::execute(
                 config,
                 deployment_uuid,
                 kind,
-                interval_seconds,
-                timeout_seconds.unwrap_or(3600), // Default to 1 hour
-                cli.format,
-            ).await?;
+                interval,
+                timeout,
+                fail_on_pending,
+                metrics_file,
+                format,
+            )
+            .await?;
+        }
+        #[cfg(feature = "download")]
+        Commands::DownloadPackage {
+            deployment_uuid,
+            expected_sha256: Some(expected_sha256),
+            ..
+        } => {
+            commands::download_package::execute_verify(
+                config,
+                deployment_uuid,
+                expected_sha256,
+                format,
+            )
+            .await?;
         }
         #[cfg(feature = "download")]
-        Commands::DownloadPackage { 
+        Commands::DownloadPackage {
             deployment_uuid,
             output,
+            name_template,
             overwrite,
+            ..
         } => {
             commands::download_package::execute(
                 config,
                 deployment_uuid,
                 output,
+                name_template,
                 overwrite,
-                cli.format,
-            ).await?;
+                format,
+            )
+            .await?;
         }
         #[cfg(feature = "logs")]
         Commands::Logs {
             deployment_uuid,
             follow,
             tail,
+            since,
+            since_last_run,
+            min_level,
+            raw,
         } => {
             commands::logs::execute(
                 config,
                 deployment_uuid,
                 follow,
                 tail,
-                cli.format,
-            ).await?;
+                since,
+                since_last_run,
+                min_level,
+                raw,
+                format,
+            )
+            .await?;
         }
+        // Handled before the configuration is loaded
+        #[cfg(feature = "validate")]
+        Commands::InspectLocal { .. } => {}
+        Commands::Config(_) | Commands::Capabilities => {}
     }
 
     Ok(())
@@ -338,18 +1393,32 @@ async fn main() -> crate::error::Result<()> {
 fn setup_logging(cli: &Cli) -> Result<()> {
     use tracing_subscriber::{fmt, EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
-    let filter = if cli.quiet {
-        EnvFilter::new("error")
+    let level = if cli.quiet {
+        "error"
     } else if cli.verbose {
-        EnvFilter::new("debug")
+        "debug"
     } else {
-        EnvFilter::new("info")
+        "info"
+    };
+
+    // HTTP tracing has its own target so it can be enabled without --verbose
+    let filter = if cli.trace_http {
+        EnvFilter::new(format!("{},{}=debug", level, HTTP_TRACE_TARGET))
+    } else {
+        EnvFilter::new(level)
     };
 
     tracing_subscriber::registry()
-        .with(fmt::layer().with_target(false).with_thread_ids(false).with_file(false).with_line_number(false))
+        .with(
+            fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_target(false)
+                .with_thread_ids(false)
+                .with_file(false)
+                .with_line_number(false),
+        )
         .with(filter)
         .init();
-    
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,295 @@
+//! Markdown rendering for `--format markdown`, for posting inspection and
+//! deployment summaries as pull request comments.
+//!
+//! Counts become tables and object problems bulleted lists. The output has
+//! no colors and no fixed column widths, so it renders the same wherever
+//! it is pasted.
+
+use crate::models::{
+    DeploymentResults, ExportDeploymentResults, ImportDeploymentResults, InspectionResults,
+};
+use crate::output::status_name;
+use std::fmt::Write;
+
+/// Report for an inspection: expected counts, problem totals and the listed
+/// errors and warnings (at most `limit` of each when given).
+pub fn inspection_report(uuid: &str, results: &InspectionResults, limit: Option<usize>) -> String {
+    let summary = &results.summary;
+    let problems = &summary.problems;
+    let mut md = String::new();
+
+    let _ = writeln!(md, "### Appian inspection `{}`\n", uuid);
+    let _ = writeln!(md, "**Status:** {}\n", status_name(&results.status));
+
+    md.push_str("| | Total | Imported | Failed | Skipped |\n|---|---:|---:|---:|---:|\n");
+    for (label, counts) in [
+        (
+            "Admin Console Settings",
+            &summary.admin_console_settings_expected,
+        ),
+        ("Objects", &summary.objects_expected),
+    ] {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} | {} |",
+            label, counts.total, counts.imported, counts.failed, counts.skipped
+        );
+    }
+    let _ = writeln!(
+        md,
+        "\n**Errors:** {} · **Warnings:** {}",
+        problems.total_errors, problems.total_warnings
+    );
+
+    problem_list(
+        &mut md,
+        "Errors",
+        problems.errors.iter().map(|e| {
+            (
+                e.object_name.as_str(),
+                e.object_uuid.as_str(),
+                e.error_message.as_str(),
+            )
+        }),
+        limit,
+    );
+    problem_list(
+        &mut md,
+        "Warnings",
+        problems.warnings.iter().map(|w| {
+            (
+                w.object_name.as_str(),
+                w.object_uuid.as_str(),
+                w.warning_message.as_str(),
+            )
+        }),
+        limit,
+    );
+    md
+}
+
+/// Report for either kind of deployment results.
+pub fn deployment_report(uuid: &str, results: &DeploymentResults, limit: Option<usize>) -> String {
+    match results {
+        DeploymentResults::Import(import) => import_report(uuid, import, limit),
+        DeploymentResults::Export(export) => export_report(uuid, export),
+    }
+}
+
+/// Report for an import: the summary table and the failed objects.
+pub fn import_report(
+    uuid: &str,
+    results: &ImportDeploymentResults,
+    limit: Option<usize>,
+) -> String {
+    let summary = &results.summary;
+    let (admin, objects, plugins) = (
+        &summary.admin_console_settings,
+        &summary.objects,
+        &summary.plugins,
+    );
+    let mut md = String::new();
+
+    let _ = writeln!(md, "### Appian deployment `{}`\n", uuid);
+    let _ = writeln!(md, "**Status:** {}\n", status_name(&results.status));
+
+    md.push_str("| | Total | Imported | Failed | Skipped |\n|---|---:|---:|---:|---:|\n");
+    let _ = writeln!(
+        md,
+        "| Admin Console Settings | {} | {} | {} | {} |",
+        admin.total, admin.imported, admin.failed, admin.skipped
+    );
+    let _ = writeln!(
+        md,
+        "| Objects | {} | {} | {} | {} |",
+        objects.total, objects.imported, objects.failed, objects.skipped
+    );
+    let _ = writeln!(
+        md,
+        "| Plugins | {} | {} | - | {} |",
+        plugins.total, plugins.imported, plugins.skipped
+    );
+    let _ = writeln!(
+        md,
+        "| **Total** | {} | {} | {} | {} |",
+        admin.total + objects.total + plugins.total,
+        admin.imported + objects.imported + plugins.imported,
+        admin.failed + objects.failed,
+        admin.skipped + objects.skipped + plugins.skipped
+    );
+
+    let _ = writeln!(md, "\n**Database scripts:** {}", summary.database_scripts);
+    if !summary.deployment_log_url.is_empty() {
+        let _ = writeln!(md, "**Deployment log:** {}", summary.deployment_log_url);
+    }
+
+    problem_list(
+        &mut md,
+        "Failed objects",
+        objects.errors.iter().map(|e| {
+            (
+                e.object_name.as_str(),
+                e.object_uuid.as_str(),
+                e.error_message.as_str(),
+            )
+        }),
+        limit,
+    );
+    md
+}
+
+/// Report for an export: its status and the artifacts it produced.
+pub fn export_report(uuid: &str, results: &ExportDeploymentResults) -> String {
+    let mut md = String::new();
+
+    let _ = writeln!(md, "### Appian export `{}`\n", uuid);
+    let _ = writeln!(md, "**Status:** {}\n", status_name(&results.status));
+
+    let artifacts = [
+        ("Package", &results.package_zip),
+        ("Plug-ins", &results.plugins_zip),
+        ("Customization file", &results.customization_file),
+        (
+            "Customization file template",
+            &results.customization_file_template,
+        ),
+        ("Deployment log", &results.deployment_log_url),
+    ];
+    if artifacts.iter().any(|(_, link)| link.is_some()) || !results.database_scripts.is_empty() {
+        md.push_str("| Artifact | Link |\n|---|---|\n");
+        for (label, link) in artifacts {
+            if let Some(link) = link {
+                let _ = writeln!(md, "| {} | {} |", label, cell(link));
+            }
+        }
+        let mut scripts: Vec<_> = results.database_scripts.iter().collect();
+        scripts.sort_by_key(|s| s.order_id);
+        for script in scripts {
+            let _ = writeln!(
+                md,
+                "| Database script {} (`{}`) | {} |",
+                script.order_id,
+                cell(&script.file_name),
+                cell(&script.url)
+            );
+        }
+    }
+    if let Some(data_source) = &results.data_source {
+        let _ = writeln!(md, "\n**Data source:** {}", data_source);
+    }
+    md
+}
+
+/// Append a `#### heading` and one bullet per `(name, uuid, message)`,
+/// followed by a count of the entries left out by `limit`.
+fn problem_list<'a>(
+    md: &mut String,
+    heading: &str,
+    entries: impl ExactSizeIterator<Item = (&'a str, &'a str, &'a str)>,
+    limit: Option<usize>,
+) {
+    let count = entries.len();
+    if count == 0 || limit == Some(0) {
+        return;
+    }
+    let _ = writeln!(md, "\n#### {}\n", heading);
+    let shown = limit.unwrap_or(count).min(count);
+    for (name, uuid, message) in entries.take(shown) {
+        let _ = writeln!(
+            md,
+            "- **{}** (`{}`): {}",
+            inline(name),
+            uuid,
+            inline(message)
+        );
+    }
+    if shown < count {
+        let _ = writeln!(md, "- … and {} more", count - shown);
+    }
+}
+
+/// Keep a value on one line so it cannot break a list item.
+fn inline(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Make a value safe inside a table cell.
+fn cell(text: &str) -> String {
+    inline(text).replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspection_report() {
+        let results: InspectionResults = serde_json::from_str(
+            r#"{
+                "summary": {
+                    "adminConsoleSettingsExpected": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                    "objectsExpected": {"total": 2, "imported": 1, "failed": 1, "skipped": 0},
+                    "problems": {
+                        "totalErrors": 2,
+                        "totalWarnings": 1,
+                        "errors": [
+                            {"errorMessage": "Missing\nrule", "objectName": "My Rule", "objectUuid": "abc"},
+                            {"errorMessage": "Broken", "objectName": "Other", "objectUuid": "ghi"}
+                        ],
+                        "warnings": [{"warningMessage": "Deprecated", "objectName": "Old UI", "objectUuid": "def"}]
+                    }
+                },
+                "status": "COMPLETED"
+            }"#,
+        )
+        .unwrap();
+
+        let md = inspection_report("1234", &results, Some(1));
+        assert!(md.starts_with("### Appian inspection `1234`\n"));
+        assert!(md.contains("**Status:** COMPLETED"));
+        assert!(md.contains("| Objects | 2 | 1 | 1 | 0 |"));
+        assert!(md.contains("- **My Rule** (`abc`): Missing rule\n- … and 1 more"));
+        assert!(md.contains("#### Warnings\n\n- **Old UI** (`def`): Deprecated"));
+        assert!(!inspection_report("1234", &results, Some(0)).contains("####"));
+    }
+
+    #[test]
+    fn test_deployment_report() {
+        let import: DeploymentResults = serde_json::from_str(
+            r#"{
+                "summary": {
+                    "databaseScripts": 1,
+                    "adminConsoleSettings": {"total": 1, "imported": 1, "failed": 0, "skipped": 0},
+                    "plugins": {"total": 0, "imported": 0, "skipped": 0},
+                    "objects": {"total": 3, "imported": 2, "failed": 1, "skipped": 0,
+                        "errors": [{"errorMessage": "Conflict", "objectName": "Site", "objectUuid": "s1"}]},
+                    "deploymentLogUrl": "https://example.com/log"
+                },
+                "status": "COMPLETED_WITH_IMPORT_ERRORS"
+            }"#,
+        )
+        .unwrap();
+        let md = deployment_report("d1", &import, None);
+        assert!(md.contains("**Status:** COMPLETED_WITH_IMPORT_ERRORS"));
+        assert!(md.contains("| **Total** | 4 | 3 | 1 | 0 |"));
+        assert!(md.contains("#### Failed objects\n\n- **Site** (`s1`): Conflict"));
+
+        let export: DeploymentResults = serde_json::from_str(
+            r#"{
+                "packageZip": "https://example.com/pkg|1.zip",
+                "dataSource": null,
+                "databaseScripts": [{"fileName": "seed.sql", "orderId": 1, "url": "https://example.com/seed.sql"}],
+                "pluginsZip": null,
+                "customizationFile": null,
+                "customizationFileTemplate": null,
+                "deploymentLogUrl": null,
+                "status": "COMPLETED"
+            }"#,
+        )
+        .unwrap();
+        let md = deployment_report("e1", &export, None);
+        assert!(md.starts_with("### Appian export `e1`"));
+        assert!(md.contains("| Package | https://example.com/pkg\\|1.zip |"));
+        assert!(md.contains("| Database script 1 (`seed.sql`) | https://example.com/seed.sql |"));
+    }
+}
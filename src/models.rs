@@ -12,6 +12,15 @@ pub struct Package {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    // Not every Appian version reports these
+    #[serde(
+        rename = "objectCount",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub object_count: Option<u64>,
+    #[serde(rename = "sizeBytes", default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +29,78 @@ pub struct PackageListResponse {
     pub total: i32,
 }
 
+// Some Appian versions return a bare array of packages instead of the
+// `{packages, total}` object, so both shapes are accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PackageList {
+    Wrapped(PackageListResponse),
+    Bare(Vec<Package>),
+}
+
+impl PackageList {
+    pub fn into_packages(self) -> Vec<Package> {
+        match self {
+            PackageList::Wrapped(response) => response.packages,
+            PackageList::Bare(packages) => packages,
+        }
+    }
+}
+
+/// Rough size of an export, derived from package metadata since the API has
+/// no export preview.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportEstimate {
+    /// Packages the export would include
+    pub packages: Vec<Package>,
+    /// Requested UUIDs that matched no package
+    pub unmatched: Vec<Uuid>,
+    /// Total objects, when every package reports its count
+    pub object_count: Option<u64>,
+    /// Total size in bytes, when every package reports its size
+    pub size_bytes: Option<u64>,
+}
+
+impl ExportEstimate {
+    /// Aggregate `packages` for an export of `uuids`. A package export
+    /// selects packages by id; an application export takes every package
+    /// listed for its applications.
+    pub fn from_packages(export_type: &str, uuids: &[Uuid], packages: Vec<Package>) -> Self {
+        let is_requested = |id: &str| {
+            uuids
+                .iter()
+                .any(|u| id.eq_ignore_ascii_case(&u.to_string()))
+        };
+        let (packages, unmatched) = if export_type == "package" {
+            let packages: Vec<Package> = packages
+                .into_iter()
+                .filter(|p| is_requested(&p.id))
+                .collect();
+            let unmatched = uuids
+                .iter()
+                .filter(|u| {
+                    !packages
+                        .iter()
+                        .any(|p| p.id.eq_ignore_ascii_case(&u.to_string()))
+                })
+                .copied()
+                .collect();
+            (packages, unmatched)
+        } else {
+            (packages, Vec::new())
+        };
+
+        let object_count = packages.iter().map(|p| p.object_count).sum();
+        let size_bytes = packages.iter().map(|p| p.size_bytes).sum();
+        ExportEstimate {
+            packages,
+            unmatched,
+            object_count,
+            size_bytes,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportRequest {
     #[serde(rename = "uuids")]
@@ -39,7 +120,7 @@ pub struct ExportResponse {
     pub status: ExportStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ExportStatus {
     InProgress,
@@ -48,6 +129,9 @@ pub enum ExportStatus {
     // v2 API may return this more specific variant
     CompletedWithExportErrors,
     Failed,
+    /// Stopped by `export cancel`
+    #[serde(alias = "CANCELLED")]
+    Canceled,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,11 +147,23 @@ pub struct DeploymentRequest {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    #[serde(rename = "adminConsoleSettingsFileName", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "adminConsoleSettingsFileName",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub admin_console_settings_file_name: Option<String>,
+    /// Admin Console settings already held by the server, used in place of an uploaded file
+    #[serde(
+        rename = "adminConsoleSettingsRef",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub admin_console_settings_ref: Option<String>,
     #[serde(rename = "packageFileName", skip_serializing_if = "Option::is_none")]
     pub package_file_name: Option<String>,
-    #[serde(rename = "customizationFileName", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        rename = "customizationFileName",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub customization_file_name: Option<String>,
     #[serde(rename = "pluginsFileName", skip_serializing_if = "Option::is_none")]
     pub plugins_file_name: Option<String>,
@@ -77,6 +173,21 @@ pub struct DeploymentRequest {
     pub database_scripts: Option<Vec<DatabaseScript>>,
 }
 
+// Data sources (API: GET /data-sources)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSource {
+    pub uuid: String,
+    pub name: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub data_source_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourceListResponse {
+    #[serde(rename = "dataSources")]
+    pub data_sources: Vec<DataSource>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployResponse {
     pub uuid: Uuid,
@@ -108,12 +219,25 @@ pub struct AdminConsoleSettingsSummary {
     pub skipped: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportErrorEntry {
+    #[serde(rename = "errorMessage")]
+    pub error_message: String,
+    #[serde(rename = "objectName")]
+    pub object_name: String,
+    #[serde(rename = "objectUuid")]
+    pub object_uuid: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObjectsSummary {
     pub total: u32,
     pub imported: u32,
     pub failed: u32,
     pub skipped: u32,
+    // Only present when the API reports which objects failed to import
+    #[serde(default)]
+    pub errors: Vec<ImportErrorEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,10 +261,53 @@ pub struct ImportSummary {
     pub deployment_log_url: String,
 }
 
+/// An object that a rollback returned to its previous version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolledBackObject {
+    #[serde(rename = "objectName")]
+    pub object_name: String,
+    #[serde(rename = "objectUuid")]
+    pub object_uuid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackObjectsSummary {
+    pub total: u32,
+    pub reverted: u32,
+    pub failed: u32,
+    // Only present when the API lists which objects were reverted
+    #[serde(rename = "revertedObjects", default)]
+    pub reverted_objects: Vec<RolledBackObject>,
+}
+
+/// What `rollback_on_failure` undid after a failed import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackSummary {
+    #[serde(rename = "objects")]
+    pub objects: RollbackObjectsSummary,
+    #[serde(rename = "databaseScripts", default)]
+    pub database_scripts: u32,
+    #[serde(
+        rename = "rollbackLogUrl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rollback_log_url: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportDeploymentResults {
     pub summary: ImportSummary,
     pub status: ImportDeploymentStatus,
+    // Only sent for deployments that were rolled back, and not by every
+    // Appian version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback: Option<RollbackSummary>,
+    // Timestamps are not returned by every Appian version
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -158,7 +325,8 @@ pub struct ExportDeploymentResults {
     pub package_zip: Option<String>,
     #[serde(rename = "dataSource")]
     pub data_source: Option<String>,
-    #[serde(rename = "databaseScripts")]
+    // Absent while the export is still running
+    #[serde(rename = "databaseScripts", default)]
     pub database_scripts: Vec<ExportedDatabaseScript>,
     #[serde(rename = "pluginsZip")]
     pub plugins_zip: Option<String>,
@@ -169,6 +337,39 @@ pub struct ExportDeploymentResults {
     #[serde(rename = "deploymentLogUrl")]
     pub deployment_log_url: Option<String>,
     pub status: ExportStatus,
+    #[serde(rename = "createdAt", default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ExportDeploymentResults {
+    /// Every downloadable artifact the export produced, labelled, with
+    /// database scripts in execution order.
+    pub fn artifacts(&self) -> Vec<(String, &str)> {
+        let mut artifacts: Vec<(String, &str)> = [
+            ("Package Zip", &self.package_zip),
+            ("Plugins Zip", &self.plugins_zip),
+            ("Customization File", &self.customization_file),
+            (
+                "Customization File Template",
+                &self.customization_file_template,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(label, url)| Some((label.to_string(), url.as_deref()?)))
+        .collect();
+
+        let mut scripts: Vec<_> = self.database_scripts.iter().collect();
+        scripts.sort_by_key(|s| s.order_id);
+        artifacts.extend(scripts.into_iter().map(|s| {
+            (
+                format!("Database Script {} ({})", s.order_id, s.file_name),
+                s.url.as_str(),
+            )
+        }));
+        artifacts
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -266,6 +467,8 @@ pub enum DeploymentStatus {
     Succeeded,
     Failed,
     RolledBack,
+    // Waiting for someone to approve the deployment in Appian
+    PendingReview,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -276,13 +479,131 @@ pub struct DeploymentStatusResponse {
     #[serde(rename = "currentStep")]
     pub current_step: Option<String>,
     #[serde(rename = "resultLinks")]
-    pub result_links: Vec<String>,
+    pub result_links: Vec<ResultLink>,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
 }
 
+/// One entry of the site's deployment listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentSummary {
+    #[serde(alias = "deploymentId")]
+    pub uuid: Uuid,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Kept as sent: listings may include import statuses such as
+    /// `COMPLETED_WITH_IMPORT_ERRORS` that [`DeploymentStatus`] lacks
+    pub status: String,
+}
+
+impl DeploymentSummary {
+    /// Still running or waiting for review; unknown statuses are not
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.status.to_ascii_uppercase().as_str(),
+            "IN_PROGRESS" | "PENDING_REVIEW"
+        )
+    }
+}
+
+// Accepted both wrapped in `{deployments}` and as a bare array, like
+// package listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DeploymentList {
+    Wrapped { deployments: Vec<DeploymentSummary> },
+    Bare(Vec<DeploymentSummary>),
+}
+
+impl DeploymentList {
+    pub fn into_deployments(self) -> Vec<DeploymentSummary> {
+        match self {
+            DeploymentList::Wrapped { deployments } => deployments,
+            DeploymentList::Bare(deployments) => deployments,
+        }
+    }
+}
+
+impl DeploymentStatusResponse {
+    /// Where a reviewer approves the deployment: the link labelled
+    /// `Review`, or else the first link.
+    pub fn review_link(&self) -> Option<&ResultLink> {
+        self.result_links
+            .iter()
+            .find(|link| link.label == "Review")
+            .or(self.result_links.first())
+    }
+}
+
+/// A link in a deployment's status, such as its log or review page.
+/// The API may send bare URLs; those are labelled from their path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "RawResultLink")]
+pub struct ResultLink {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawResultLink {
+    Url(String),
+    Labeled {
+        #[serde(default, alias = "name")]
+        label: Option<String>,
+        #[serde(alias = "href")]
+        url: String,
+    },
+}
+
+impl From<RawResultLink> for ResultLink {
+    fn from(raw: RawResultLink) -> Self {
+        match raw {
+            RawResultLink::Url(url) => ResultLink::from_url(url),
+            RawResultLink::Labeled {
+                label: Some(label),
+                url,
+            } if !label.trim().is_empty() => ResultLink {
+                label: label.trim().to_string(),
+                url,
+            },
+            RawResultLink::Labeled { url, .. } => ResultLink::from_url(url),
+        }
+    }
+}
+
+impl ResultLink {
+    /// Label a bare URL by the last path segment that names what it links
+    /// to, e.g. `.../deployments/{uuid}/log` becomes `Log`.
+    pub fn from_url(url: String) -> Self {
+        let path = url::Url::parse(&url)
+            .map(|parsed| parsed.path().to_string())
+            .unwrap_or_else(|_| url.clone());
+        let label = path
+            .rsplit('/')
+            .find_map(|segment| match segment.to_ascii_lowercase().as_str() {
+                "review" | "reviews" | "approval" | "approvals" => Some("Review"),
+                "log" | "logs" => Some("Log"),
+                "artifact" | "artifacts" | "download" | "downloads" => Some("Artifact"),
+                "result" | "results" => Some("Results"),
+                _ => None,
+            })
+            .unwrap_or("Link");
+        ResultLink {
+            label: label.to_string(),
+            url,
+        }
+    }
+}
+
+impl std::fmt::Display for ResultLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.label, self.url)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: DateTime<Utc>,
@@ -291,7 +612,7 @@ pub struct LogEntry {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LogLevel {
     Error,
     Warn,
@@ -299,12 +620,54 @@ pub enum LogLevel {
     Debug,
 }
 
+impl LogLevel {
+    /// Higher is more severe
+    fn severity(self) -> u8 {
+        match self {
+            LogLevel::Debug => 0,
+            LogLevel::Info => 1,
+            LogLevel::Warn => 2,
+            LogLevel::Error => 3,
+        }
+    }
+
+    /// Whether an entry at this level passes a `minimum` level filter.
+    pub fn is_at_least(self, minimum: LogLevel) -> bool {
+        self.severity() >= minimum.severity()
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!(
+                "expected 'error', 'warn', 'info' or 'debug', not '{}'",
+                value
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogsResponse {
     pub logs: Vec<LogEntry>,
     pub total: i32,
     #[serde(rename = "hasMore")]
     pub has_more: bool,
+    /// Opaque position after the last entry, from servers that support
+    /// resuming with `cursor`
+    #[serde(
+        rename = "nextCursor",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -328,6 +691,12 @@ pub struct ValidationViolation {
     pub message: String,
 }
 
+impl std::fmt::Display for ValidationViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ViolationSeverity {
@@ -348,9 +717,126 @@ pub struct OperationStatus {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Status of an export or a deployment, depending on which endpoint was queried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StatusReport {
+    /// An export's status comes from its results, so it carries every
+    /// artifact link once the export has finished
+    Export(ExportDeploymentResults),
+    Deployment(DeploymentStatusResponse),
+}
+
+impl StatusReport {
+    pub fn is_terminal(&self) -> bool {
+        match self {
+            StatusReport::Export(export) => export.status.is_terminal(),
+            StatusReport::Deployment(deployment) => deployment.status.is_terminal(),
+        }
+    }
+
+    pub fn status_label(&self) -> String {
+        match self {
+            StatusReport::Export(export) => format!("{:?}", export.status),
+            StatusReport::Deployment(deployment) => format!("{:?}", deployment.status),
+        }
+    }
+
+    /// A deployment waiting for manual approval. Not terminal, but polling
+    /// it further would only hang until someone acts.
+    pub fn is_pending_review(&self) -> bool {
+        matches!(self, StatusReport::Deployment(deployment) if matches!(deployment.status, DeploymentStatus::PendingReview))
+    }
+
+    /// Seconds between creation and the last update, when known. Not every
+    /// server includes timestamps in export results.
+    pub fn duration_seconds(&self) -> Option<u64> {
+        match self {
+            StatusReport::Export(export) => {
+                Some(elapsed_seconds(export.created_at?, export.updated_at?))
+            }
+            StatusReport::Deployment(deployment) => Some(elapsed_seconds(
+                deployment.created_at,
+                deployment.updated_at,
+            )),
+        }
+    }
+}
+
+impl DeploymentResults {
+    /// Seconds between creation and the last update, when the server
+    /// included both timestamps.
+    pub fn duration_seconds(&self) -> Option<u64> {
+        let (created, updated) = match self {
+            DeploymentResults::Import(import) => (import.created_at, import.updated_at),
+            DeploymentResults::Export(export) => (export.created_at, export.updated_at),
+        };
+        Some(elapsed_seconds(created?, updated?))
+    }
+
+    /// `import` or `export`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeploymentResults::Import(_) => "import",
+            DeploymentResults::Export(_) => "export",
+        }
+    }
+
+    /// The results with their [`kind`](Self::kind) alongside, for JSON
+    /// output. Parsing API bodies stays untagged.
+    pub fn tagged(&self) -> TaggedResults<'_> {
+        TaggedResults {
+            kind: self.kind(),
+            results: self,
+        }
+    }
+}
+
+/// Serializes as the results' own fields plus a `kind` discriminator
+#[derive(Debug, Serialize)]
+pub struct TaggedResults<'a> {
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub results: &'a DeploymentResults,
+}
+
+fn elapsed_seconds(created: DateTime<Utc>, updated: DateTime<Utc>) -> u64 {
+    (updated - created).num_seconds().max(0) as u64
+}
+
 impl DeploymentStatus {
     pub fn is_terminal(&self) -> bool {
-        matches!(self, DeploymentStatus::Succeeded | DeploymentStatus::Failed | DeploymentStatus::RolledBack)
+        matches!(
+            self,
+            DeploymentStatus::Succeeded | DeploymentStatus::Failed | DeploymentStatus::RolledBack
+        )
+    }
+}
+
+impl InspectionOperationStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            InspectionOperationStatus::Completed | InspectionOperationStatus::Failed
+        )
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, InspectionOperationStatus::Completed)
+    }
+}
+
+impl ImportDeploymentStatus {
+    /// `PendingReview` waits on a human, so it is not terminal
+    pub fn is_terminal(&self) -> bool {
+        !matches!(
+            self,
+            ImportDeploymentStatus::InProgress | ImportDeploymentStatus::PendingReview
+        )
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, ImportDeploymentStatus::Completed)
     }
 }
 
@@ -362,8 +848,15 @@ impl ExportStatus {
                 | ExportStatus::CompletedWithErrors
                 | ExportStatus::CompletedWithExportErrors
                 | ExportStatus::Failed
+                | ExportStatus::Canceled
         )
     }
+
+    /// Only a clean completion; the with-errors variants still produce
+    /// artifacts but are not a success
+    pub fn is_success(&self) -> bool {
+        matches!(self, ExportStatus::Completed)
+    }
 }
 
 #[cfg(test)]
@@ -376,11 +869,204 @@ mod tests {
         assert!(DeploymentStatus::Succeeded.is_terminal());
         assert!(DeploymentStatus::Failed.is_terminal());
         assert!(DeploymentStatus::RolledBack.is_terminal());
+        assert!(!DeploymentStatus::PendingReview.is_terminal());
 
         assert!(!ExportStatus::InProgress.is_terminal());
         assert!(ExportStatus::Completed.is_terminal());
         assert!(ExportStatus::CompletedWithErrors.is_terminal());
         assert!(ExportStatus::CompletedWithExportErrors.is_terminal());
         assert!(ExportStatus::Failed.is_terminal());
+        assert!(ExportStatus::Canceled.is_terminal());
+    }
+
+    #[test]
+    fn test_result_links_labeled_or_bare() {
+        let status: DeploymentStatusResponse = serde_json::from_value(serde_json::json!({
+            "deploymentId": "00000000-0000-0000-0000-000000000000",
+            "status": "PENDING_REVIEW",
+            "resultLinks": [
+                "https://site.appiancloud.com/suite/deployment/v2/deployments/abc/log",
+                "https://site.appiancloud.com/suite/design/deployments/abc/review?tab=1",
+                "https://site.appiancloud.com/other",
+                {"label": "Package", "url": "https://site.appiancloud.com/artifacts/1"},
+                {"url": "https://site.appiancloud.com/artifacts/2"}
+            ],
+            "createdAt": "2024-01-01T00:00:00Z",
+            "updatedAt": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap();
+
+        let labels: Vec<&str> = status
+            .result_links
+            .iter()
+            .map(|link| link.label.as_str())
+            .collect();
+        assert_eq!(labels, ["Log", "Review", "Link", "Package", "Artifact"]);
+        assert_eq!(
+            status.result_links[3].url,
+            "https://site.appiancloud.com/artifacts/1"
+        );
+        assert!(status.review_link().unwrap().url.ends_with("/review?tab=1"));
+
+        // Always serialized with labels, and read back unchanged
+        let json = serde_json::to_value(&status.result_links[0]).unwrap();
+        assert_eq!(json["label"], "Log");
+        assert_eq!(
+            serde_json::from_value::<ResultLink>(json).unwrap(),
+            status.result_links[0]
+        );
+    }
+
+    #[test]
+    fn test_export_estimate() {
+        let packages: Vec<Package> = serde_json::from_str(
+            r#"[
+                {"id": "11111111-1111-1111-1111-111111111111", "name": "A", "version": "1", "dependencies": [],
+                 "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z", "objectCount": 10, "sizeBytes": 2048},
+                {"id": "22222222-2222-2222-2222-222222222222", "name": "B", "version": "1", "dependencies": [],
+                 "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z", "objectCount": 5}
+            ]"#,
+        )
+        .unwrap();
+        let a = Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
+        let missing = Uuid::parse_str("33333333-3333-3333-3333-333333333333").unwrap();
+
+        let estimate = ExportEstimate::from_packages("package", &[a, missing], packages.clone());
+        assert_eq!(estimate.packages.len(), 1);
+        assert_eq!(estimate.unmatched, vec![missing]);
+        assert_eq!(
+            (estimate.object_count, estimate.size_bytes),
+            (Some(10), Some(2048))
+        );
+
+        // One package without a size makes the total unknown
+        let estimate = ExportEstimate::from_packages("application", &[a], packages);
+        assert_eq!(
+            (estimate.object_count, estimate.size_bytes),
+            (Some(15), None)
+        );
+    }
+
+    #[test]
+    fn test_export_status_success() {
+        assert!(ExportStatus::Completed.is_success());
+        assert!(!ExportStatus::CompletedWithErrors.is_success());
+        assert!(!ExportStatus::CompletedWithExportErrors.is_success());
+        assert!(!ExportStatus::Failed.is_success());
+        assert!(!ExportStatus::InProgress.is_success());
+    }
+
+    #[test]
+    fn test_import_and_inspection_status_matrix() {
+        use ImportDeploymentStatus::*;
+        let matrix = [
+            (InProgress, false, false),
+            (Completed, true, true),
+            (CompletedWithImportErrors, true, false),
+            (CompletedWithPublishErrors, true, false),
+            (Failed, true, false),
+            (PendingReview, false, false),
+            (Rejected, true, false),
+        ];
+        for (status, terminal, success) in matrix {
+            assert_eq!(status.is_terminal(), terminal, "{:?}", status);
+            assert_eq!(status.is_success(), success, "{:?}", status);
+        }
+
+        let matrix = [
+            (InspectionOperationStatus::InProgress, false, false),
+            (InspectionOperationStatus::Completed, true, true),
+            (InspectionOperationStatus::Failed, true, false),
+        ];
+        for (status, terminal, success) in matrix {
+            assert_eq!(status.is_terminal(), terminal, "{:?}", status);
+            assert_eq!(status.is_success(), success, "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn test_package_list_shapes() {
+        let package = r#"{
+            "id": "pkg-1", "name": "HR Release", "version": "1.0", "dependencies": [],
+            "createdAt": "2024-01-01T10:00:00Z", "updatedAt": "2024-01-02T10:00:00Z"
+        }"#;
+
+        let wrapped: PackageList =
+            serde_json::from_str(&format!(r#"{{"packages": [{}], "total": 1}}"#, package)).unwrap();
+        assert!(matches!(wrapped, PackageList::Wrapped(_)));
+        assert_eq!(wrapped.into_packages()[0].name, "HR Release");
+
+        let bare: PackageList = serde_json::from_str(&format!("[{}]", package)).unwrap();
+        assert!(matches!(bare, PackageList::Bare(_)));
+        assert_eq!(bare.into_packages()[0].id, "pkg-1");
+
+        let empty: PackageList = serde_json::from_str("[]").unwrap();
+        assert!(empty.into_packages().is_empty());
+        assert!(serde_json::from_str::<PackageList>(r#"{"items": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_import_results_failed_objects() {
+        let body = r#"{
+            "summary": {
+                "databaseScripts": 0,
+                "adminConsoleSettings": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                "plugins": {"total": 0, "imported": 0, "skipped": 0},
+                "objects": {
+                    "total": 3, "imported": 2, "failed": 1, "skipped": 0,
+                    "errors": [{"errorMessage": "Missing dependency", "objectName": "My Rule", "objectUuid": "abc-123"}]
+                },
+                "deploymentLogUrl": "https://example.com/log"
+            },
+            "status": "COMPLETED_WITH_IMPORT_ERRORS"
+        }"#;
+
+        match serde_json::from_str::<DeploymentResults>(body).unwrap() {
+            DeploymentResults::Import(import) => {
+                assert_eq!(import.summary.objects.errors.len(), 1);
+                assert_eq!(import.summary.objects.errors[0].object_name, "My Rule");
+            }
+            DeploymentResults::Export(_) => panic!("expected import results"),
+        }
+
+        // Older responses omit the errors list entirely
+        let objects: ObjectsSummary =
+            serde_json::from_str(r#"{"total": 1, "imported": 1, "failed": 0, "skipped": 0}"#)
+                .unwrap();
+        assert!(objects.errors.is_empty());
+    }
+
+    #[test]
+    fn test_tagged_results_carry_kind() {
+        let import: DeploymentResults = serde_json::from_str(
+            r#"{
+                "summary": {
+                    "databaseScripts": 0,
+                    "adminConsoleSettings": {"total": 0, "imported": 0, "failed": 0, "skipped": 0},
+                    "plugins": {"total": 0, "imported": 0, "skipped": 0},
+                    "objects": {"total": 1, "imported": 1, "failed": 0, "skipped": 0},
+                    "deploymentLogUrl": "https://example.com/log"
+                },
+                "status": "COMPLETED"
+            }"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(import.tagged()).unwrap();
+        assert_eq!(json["kind"], "import");
+        assert_eq!(json["summary"]["objects"]["imported"], 1);
+
+        let export: DeploymentResults = serde_json::from_str(
+            r#"{"packageZip": "https://example.com/pkg.zip", "status": "COMPLETED"}"#,
+        )
+        .unwrap();
+        let json = serde_json::to_value(export.tagged()).unwrap();
+        assert_eq!(json["kind"], "export");
+        assert_eq!(json["packageZip"], "https://example.com/pkg.zip");
+
+        // The discriminator is output only; the API shape still parses untagged
+        assert!(matches!(
+            serde_json::from_value::<DeploymentResults>(json).unwrap(),
+            DeploymentResults::Export(_)
+        ));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,550 @@
+//! Output helpers shared by the commands.
+//!
+//! Result payloads (JSON, tables, identifiers) go to stdout; progress and
+//! decoration go to stderr so `--format json` output can be piped directly.
+
+use crate::client::RequestPreview;
+use crate::Result;
+use colored::*;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static COMPACT_JSON: AtomicBool = AtomicBool::new(false);
+static QUERY: OnceLock<String> = OnceLock::new();
+static ENVIRONMENT: OnceLock<String> = OnceLock::new();
+static LAST_IDENTIFIER: Mutex<Option<String>> = Mutex::new(None);
+static REQUEST_IDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static INCLUDE_REQUEST_IDS: AtomicBool = AtomicBool::new(false);
+static LABELS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// When to use ANSI colors, from `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors on a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => anyhow::bail!("expected 'auto', 'always' or 'never', not '{}'", value),
+        }
+    }
+}
+
+/// Apply `choice` for the rest of the process. `always` and `never` win
+/// over the environment; `auto` leaves the decision to `colored`, which
+/// checks for a terminal and honors `NO_COLOR`, `CLICOLOR` and
+/// `CLICOLOR_FORCE`.
+pub fn set_color(choice: ColorChoice) {
+    match choice {
+        ColorChoice::Auto => colored::control::unset_override(),
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+    }
+}
+
+/// Suppress human-oriented decoration for the rest of the process.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print JSON on a single line instead of pretty-printed, for the rest of
+/// the process.
+pub fn set_compact_json(compact: bool) {
+    COMPACT_JSON.store(compact, Ordering::Relaxed);
+}
+
+/// Serialize `value` for printing: pretty-printed, or on one line under
+/// `--json-compact`.
+pub fn to_json_string<T: serde::Serialize + ?Sized>(value: &T) -> Result<String> {
+    serialize(value, COMPACT_JSON.load(Ordering::Relaxed))
+}
+
+fn serialize<T: serde::Serialize + ?Sized>(value: &T, compact: bool) -> Result<String> {
+    Ok(if compact {
+        serde_json::to_string(value)?
+    } else {
+        serde_json::to_string_pretty(value)?
+    })
+}
+
+/// Name the Appian site every result comes from, for the rest of the
+/// process; see [`crate::Config::environment_name`].
+pub fn set_environment(name: &str) {
+    let _ = ENVIRONMENT.set(name.to_string());
+}
+
+pub fn environment() -> Option<&'static str> {
+    ENVIRONMENT.get().map(String::as_str)
+}
+
+/// `message` prefixed with the environment, e.g. `[prod.appiancloud.com]
+/// Deployment completed`. Leading blank lines stay in front and empty
+/// messages are left alone. Unchanged under `--quiet` or without an
+/// environment.
+pub fn tagged(message: impl Display) -> String {
+    let message = message.to_string();
+    match environment() {
+        Some(name) if !is_quiet() => tag(&message, name),
+        _ => message,
+    }
+}
+
+fn tag(message: &str, name: &str) -> String {
+    let text = message.trim_start_matches('\n');
+    if text.is_empty() {
+        return message.to_string();
+    }
+    let newlines = &message[..message.len() - text.len()];
+    format!("{}{} {}", newlines, format!("[{}]", name).dimmed(), text)
+}
+
+/// Add the environment to a top-level JSON object under `environment`.
+fn with_environment(mut value: serde_json::Value) -> serde_json::Value {
+    if let (Some(name), Some(object)) = (environment(), value.as_object_mut()) {
+        object
+            .entry("environment")
+            .or_insert_with(|| serde_json::json!(name));
+    }
+    value
+}
+
+/// Add the server request ids seen so far to a top-level JSON object under
+/// `requestIds`, with `--include-request-ids`.
+fn with_request_ids(mut value: serde_json::Value) -> serde_json::Value {
+    if !INCLUDE_REQUEST_IDS.load(Ordering::Relaxed) {
+        return value;
+    }
+    let ids = request_ids();
+    if let (false, Some(object)) = (ids.is_empty(), value.as_object_mut()) {
+        object
+            .entry("requestIds")
+            .or_insert_with(|| serde_json::json!(ids));
+    }
+    value
+}
+
+/// Include the request ids of [`request_ids`] in every JSON result, for the
+/// rest of the process.
+pub fn set_include_request_ids(include: bool) {
+    INCLUDE_REQUEST_IDS.store(include, Ordering::Relaxed);
+}
+
+/// Remember a correlation id the server sent with a response, so the run
+/// can be matched with the server's logs.
+pub fn record_request_id(id: &str) {
+    if let Ok(mut ids) = REQUEST_IDS.lock() {
+        if !ids.iter().any(|seen| seen == id) {
+            ids.push(id.to_string());
+        }
+    }
+}
+
+/// Every distinct request id recorded by [`record_request_id`], oldest
+/// first.
+pub fn request_ids() -> Vec<String> {
+    REQUEST_IDS
+        .lock()
+        .map(|ids| ids.clone())
+        .unwrap_or_default()
+}
+
+/// Remember the `--label`s of the deployment this run starts, so the audit
+/// log can record them whatever the output mode.
+pub fn record_labels(labels: &BTreeMap<String, String>) {
+    if let Ok(mut recorded) = LABELS.lock() {
+        recorded.clone_from(labels);
+    }
+}
+
+/// The labels recorded by [`record_labels`], if any.
+pub fn labels() -> BTreeMap<String, String> {
+    LABELS
+        .lock()
+        .map(|labels| labels.clone())
+        .unwrap_or_default()
+}
+
+/// Narrow every JSON result printed by [`json`] to the values selected by
+/// the `--query` path.
+pub fn set_query(path: &str) {
+    let _ = QUERY.set(path.to_string());
+}
+
+/// Print a command's JSON result to stdout. Objects gain an `environment`
+/// field naming the Appian site, and `requestIds` with
+/// `--include-request-ids`. With `--query`, only the selected values are
+/// printed, one per line.
+pub fn json<T: serde::Serialize>(value: &T) -> Result<()> {
+    let value = with_request_ids(with_environment(serde_json::to_value(value)?));
+    match QUERY.get() {
+        Some(path) => {
+            for selected in crate::query::select(&value, path)? {
+                println!("{}", crate::query::render(selected)?);
+            }
+        }
+        None => println!("{}", to_json_string(&value)?),
+    }
+    Ok(())
+}
+
+/// [`CliError::to_json`](crate::CliError::to_json) with the environment
+/// (and request ids) added, for `--format json` errors.
+pub fn error_json(err: &crate::CliError) -> serde_json::Value {
+    with_request_ids(with_environment(err.to_json()))
+}
+
+/// Print a decorative or progress line to stderr, prefixed with the
+/// environment. Dropped under `--quiet`.
+pub fn status(message: impl Display) {
+    if !is_quiet() {
+        eprintln!("{}", tagged(message));
+    }
+}
+
+/// Overwrite the current stderr line with `message` (no trailing newline).
+/// Dropped under `--quiet`.
+pub fn progress(message: impl Display) {
+    if !is_quiet() {
+        eprint!("\r{}", message);
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// Print a warning to stderr. Warnings are shown even under `--quiet` so
+/// they are not lost when stdout is captured.
+pub fn warning(message: impl Display) {
+    eprintln!(
+        "{}",
+        tagged(format!(
+            "{} {}",
+            "Warning:".yellow().bold(),
+            message.to_string().yellow()
+        ))
+    );
+}
+
+/// Print a response body for `--raw` to stdout exactly as received, ending
+/// it with a newline if the server did not.
+pub fn raw(body: &str) {
+    print!("{}", body);
+    if !body.ends_with('\n') {
+        println!();
+    }
+}
+
+/// Print a request preview for `--print-request` to stdout.
+pub fn request_preview(preview: &RequestPreview, format: Option<&str>) -> Result<()> {
+    match format {
+        Some("json") => json(preview)?,
+        _ => print!("{}", preview),
+    }
+    Ok(())
+}
+
+/// Print `value` like [`json`], with a `durationSeconds` field added when
+/// the duration is known.
+pub fn json_with_duration<T: serde::Serialize>(
+    value: &T,
+    duration_seconds: Option<u64>,
+) -> Result<()> {
+    let mut value = serde_json::to_value(value)?;
+    if let (Some(seconds), Some(object)) = (duration_seconds, value.as_object_mut()) {
+        object.insert("durationSeconds".to_string(), serde_json::json!(seconds));
+    }
+    json(&value)
+}
+
+/// Print the essential identifier of an operation (e.g. the UUID it created).
+/// Under `--quiet` the bare value is the only thing written to stdout;
+/// otherwise it is a labelled status line and the full result follows.
+pub fn identifier(label: &str, value: impl Display) {
+    if let Ok(mut last) = LAST_IDENTIFIER.lock() {
+        *last = Some(value.to_string());
+    }
+    if is_quiet() {
+        println!("{}", value);
+    } else {
+        status(format!("{}: {}", label, value.to_string().cyan()));
+    }
+}
+
+/// The identifier most recently printed by [`identifier`], e.g. for the
+/// audit log.
+pub fn last_identifier() -> Option<String> {
+    LAST_IDENTIFIER.lock().ok().and_then(|last| last.clone())
+}
+
+/// Print at most `limit` of `entries` (all of them when `None`) with
+/// `print`, followed by an "... and N more" line for the rest.
+pub fn capped<T>(entries: &[T], limit: Option<usize>, indent: &str, mut print: impl FnMut(&T)) {
+    let shown = limit.unwrap_or(entries.len()).min(entries.len());
+    for entry in &entries[..shown] {
+        print(entry);
+    }
+    if shown < entries.len() {
+        println!(
+            "{}{}",
+            indent,
+            format!("... and {} more", entries.len() - shown).dimmed()
+        );
+    }
+}
+
+/// Format a byte count for people, e.g. `1.5 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit_index])
+}
+
+/// Broad outcome of an API status, which decides how it is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Success,
+    /// Finished with problems, or waiting on a person
+    Warning,
+    Failure,
+    InProgress,
+}
+
+/// Statuses that share the icons and colors of [`status_style`].
+pub trait StatusTone {
+    fn tone(&self) -> Tone;
+}
+
+impl StatusTone for crate::models::DeploymentStatus {
+    fn tone(&self) -> Tone {
+        use crate::models::DeploymentStatus::*;
+        match self {
+            Succeeded => Tone::Success,
+            PendingReview => Tone::Warning,
+            Failed | RolledBack => Tone::Failure,
+            InProgress => Tone::InProgress,
+        }
+    }
+}
+
+impl StatusTone for crate::models::ExportStatus {
+    fn tone(&self) -> Tone {
+        use crate::models::ExportStatus::*;
+        match self {
+            Completed => Tone::Success,
+            CompletedWithErrors | CompletedWithExportErrors => Tone::Warning,
+            Failed | Canceled => Tone::Failure,
+            InProgress => Tone::InProgress,
+        }
+    }
+}
+
+impl StatusTone for crate::models::ImportDeploymentStatus {
+    fn tone(&self) -> Tone {
+        use crate::models::ImportDeploymentStatus::*;
+        match self {
+            Completed => Tone::Success,
+            CompletedWithImportErrors | CompletedWithPublishErrors | PendingReview => Tone::Warning,
+            Failed | Rejected => Tone::Failure,
+            InProgress => Tone::InProgress,
+        }
+    }
+}
+
+impl StatusTone for crate::models::InspectionOperationStatus {
+    fn tone(&self) -> Tone {
+        use crate::models::InspectionOperationStatus::*;
+        match self {
+            Completed => Tone::Success,
+            Failed => Tone::Failure,
+            InProgress => Tone::InProgress,
+        }
+    }
+}
+
+impl StatusTone for crate::models::StatusReport {
+    fn tone(&self) -> Tone {
+        match self {
+            crate::models::StatusReport::Export(export) => export.status.tone(),
+            crate::models::StatusReport::Deployment(deployment) => deployment.status.tone(),
+        }
+    }
+}
+
+/// Icon and color for a status. Without color (`--color never`, `NO_COLOR`)
+/// the icon is a bracketed word such as `[OK]`, so it still reads in logs.
+pub fn status_style(status: &impl StatusTone) -> (&'static str, Color) {
+    let colored = colored::control::SHOULD_COLORIZE.should_colorize();
+    match (status.tone(), colored) {
+        (Tone::Success, true) => ("✓", Color::Green),
+        (Tone::Success, false) => ("[OK]", Color::Green),
+        (Tone::Warning, true) => ("⚠", Color::Yellow),
+        (Tone::Warning, false) => ("[WARN]", Color::Yellow),
+        (Tone::Failure, true) => ("✗", Color::Red),
+        (Tone::Failure, false) => ("[FAIL]", Color::Red),
+        (Tone::InProgress, true) => ("⏳", Color::Yellow),
+        (Tone::InProgress, false) => ("[WAIT]", Color::Yellow),
+    }
+}
+
+/// A status as its icon and API name, e.g. `✓ COMPLETED`, in its color.
+pub fn styled_status<S: StatusTone + serde::Serialize + std::fmt::Debug>(status: &S) -> String {
+    let (icon, color) = status_style(status);
+    format!("{} {}", icon, status_name(status))
+        .color(color)
+        .to_string()
+}
+
+/// Print the artifact links, data source and log of export results as
+/// indented detail lines on stdout.
+pub fn export_details(export: &crate::models::ExportDeploymentResults) {
+    for (label, url) in export.artifacts() {
+        println!("  {}: {}", label.dimmed(), url);
+    }
+    if let Some(data_source) = &export.data_source {
+        println!("  {}: {}", "Data Source".dimmed(), data_source);
+    }
+    if let Some(url) = &export.deployment_log_url {
+        println!("  {}: {}", "Deployment Log".dimmed(), url);
+    }
+}
+
+/// The stable one-line outcome summary for log scrapers:
+/// `RESULT: status=<STATUS> errors=<N> warnings=<N>`. `STATUS` is the API's
+/// status name (e.g. `COMPLETED_WITH_IMPORT_ERRORS`); the tokens are always
+/// present and always in this order.
+pub fn format_result_line(status: &str, errors: u64, warnings: u64) -> String {
+    format!(
+        "RESULT: status={} errors={} warnings={}",
+        status, errors, warnings
+    )
+}
+
+/// Print the summary line from [`format_result_line`] to stderr, even under
+/// `--quiet`: red when `failed` or there are errors, yellow with only
+/// warnings, green otherwise (plain with `--color never`).
+pub fn result_line(status: &str, errors: u64, warnings: u64, failed: bool) {
+    let line = format_result_line(status, errors, warnings);
+    let line = if failed || errors > 0 {
+        line.red()
+    } else if warnings > 0 {
+        line.yellow()
+    } else {
+        line.green()
+    };
+    eprintln!("{}", line);
+}
+
+/// API name of a status enum, e.g. `COMPLETED` for `Completed`
+pub fn status_name<S: serde::Serialize + std::fmt::Debug>(status: &S) -> String {
+    match serde_json::to_value(status) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => format!("{:?}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_line_format() {
+        assert_eq!(
+            format_result_line(
+                &status_name(&crate::models::ImportDeploymentStatus::CompletedWithImportErrors),
+                3,
+                12
+            ),
+            "RESULT: status=COMPLETED_WITH_IMPORT_ERRORS errors=3 warnings=12"
+        );
+    }
+
+    #[test]
+    fn test_status_style_without_color() {
+        use crate::models::{
+            DeploymentStatus, ExportStatus, ImportDeploymentStatus, InspectionOperationStatus,
+        };
+        colored::control::set_override(false);
+
+        assert_eq!(
+            styled_status(&DeploymentStatus::Succeeded),
+            "[OK] SUCCEEDED"
+        );
+        assert_eq!(
+            styled_status(&DeploymentStatus::RolledBack),
+            "[FAIL] ROLLED_BACK"
+        );
+        assert_eq!(
+            styled_status(&ExportStatus::InProgress),
+            "[WAIT] IN_PROGRESS"
+        );
+        assert_eq!(
+            styled_status(&ImportDeploymentStatus::CompletedWithPublishErrors),
+            "[WARN] COMPLETED_WITH_PUBLISH_ERRORS"
+        );
+        assert_eq!(
+            status_style(&InspectionOperationStatus::Failed),
+            ("[FAIL]", Color::Red)
+        );
+    }
+
+    #[test]
+    fn test_parse_color_choice() {
+        assert_eq!(
+            "Always".parse::<ColorChoice>().unwrap(),
+            ColorChoice::Always
+        );
+        assert_eq!("never".parse::<ColorChoice>().unwrap(), ColorChoice::Never);
+        assert_eq!(ColorChoice::default(), ColorChoice::Auto);
+        assert!("sometimes"
+            .parse::<ColorChoice>()
+            .unwrap_err()
+            .to_string()
+            .contains("'auto', 'always' or 'never'"));
+    }
+
+    #[test]
+    fn test_environment_tag() {
+        colored::control::set_override(false);
+        assert_eq!(
+            tag("Deployment completed", "prod.appiancloud.com"),
+            "[prod.appiancloud.com] Deployment completed"
+        );
+        assert_eq!(tag("\nDone", "dev"), "\n[dev] Done");
+        assert_eq!(tag("", "dev"), "");
+    }
+
+    #[test]
+    fn test_compact_json_is_one_line() {
+        let value = serde_json::json!({"status": "COMPLETED", "links": ["a", "b"]});
+        assert_eq!(
+            serialize(&value, true).unwrap(),
+            r#"{"links":["a","b"],"status":"COMPLETED"}"#
+        );
+        assert!(serialize(&value, false)
+            .unwrap()
+            .contains("\n  \"links\": ["));
+    }
+}
@@ -0,0 +1,196 @@
+//! Shared wait loop for commands that follow a long-running operation
+//! (exports, deployments, inspections) until it finishes.
+
+use crate::client::Client;
+use crate::error::{CliError, Result};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// `--interval` and `--timeout` given for a wait. Unset values fall back to
+/// the defaults of the command that waits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WaitOptions {
+    pub interval: Option<Duration>,
+    pub timeout: Option<Duration>,
+}
+
+impl WaitOptions {
+    pub fn interval_or(&self, default: Duration) -> Duration {
+        self.interval.unwrap_or(default)
+    }
+
+    pub fn timeout_or(&self, default: Duration) -> Duration {
+        self.timeout.unwrap_or(default)
+    }
+}
+
+/// Call `fetch` every `interval` until `is_terminal` accepts the result.
+///
+/// `on_poll` sees every result with the elapsed seconds so callers can report
+/// progress. Fails with [`CliError::Timeout`] once `timeout` has passed, or
+/// with [`CliError::MaxPolls`] after `[monitor] max_polls` polls, whichever
+/// comes first.
+///
+/// `timeout` bounds the whole wait and is independent of the HTTP timeout:
+/// a poll that outlives `timeout_seconds` is retried by the client, and a
+/// poll still running when `timeout` expires is cancelled. The sleeps
+/// between polls also respect the client's command deadline.
+pub async fn poll_until<T, F, Fut>(
+    client: &Client,
+    what: &str,
+    interval: Duration,
+    timeout: Duration,
+    mut fetch: F,
+    is_terminal: impl Fn(&T) -> bool,
+    on_poll: impl FnMut(u64, &T),
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let fetch = || {
+        let poll = fetch();
+        async move { Ok((poll.await?, false)) }
+    };
+    poll_until_waited(client, what, interval, timeout, fetch, is_terminal, on_poll).await
+}
+
+/// Like [`poll_until`], but `fetch` also says whether the server already
+/// held the request until something changed (a long poll); the next poll
+/// then follows at once instead of after `interval`.
+pub async fn poll_until_waited<T, F, Fut>(
+    client: &Client,
+    what: &str,
+    interval: Duration,
+    timeout: Duration,
+    mut fetch: F,
+    is_terminal: impl Fn(&T) -> bool,
+    mut on_poll: impl FnMut(u64, &T),
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(T, bool)>>,
+{
+    let start = Instant::now();
+    let timed_out = || {
+        CliError::Timeout(format!(
+            "{} did not reach a terminal status within {} seconds",
+            what,
+            timeout.as_secs()
+        ))
+    };
+
+    let mut polls = 0;
+    loop {
+        if start.elapsed() >= timeout {
+            return Err(timed_out());
+        }
+
+        let remaining = timeout.saturating_sub(start.elapsed());
+        let (value, waited) = tokio::time::timeout(remaining, fetch())
+            .await
+            .map_err(|_| timed_out())??;
+        on_poll(start.elapsed().as_secs(), &value);
+
+        if is_terminal(&value) {
+            return Ok(value);
+        }
+        polls += 1;
+        check_poll_count(client, what, polls)?;
+
+        if !waited {
+            // Never sleep past the timeout; the check above then fails promptly
+            client
+                .pause(interval.min(timeout.saturating_sub(start.elapsed())))
+                .await?;
+        }
+    }
+}
+
+/// Fail once `polls` non-terminal polls have used up `[monitor] max_polls`.
+pub fn check_poll_count(client: &Client, what: &str, polls: u32) -> Result<()> {
+    match client.config().monitor.max_polls {
+        Some(max) if polls >= max => Err(CliError::MaxPolls(format!(
+            "{} did not reach a terminal status within {} polls",
+            what, max
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_poll_until_terminal_or_timeout() {
+        let config = Config {
+            base_url: "https://test.example.com".to_string(),
+            api_key: "test-key".to_string(),
+            ..Config::default()
+        };
+        let client = Client::new(config).unwrap();
+
+        let mut calls = 0;
+        let mut seen = Vec::new();
+        let value = poll_until(
+            &client,
+            "Counter",
+            Duration::from_millis(1),
+            Duration::from_secs(60),
+            || {
+                calls += 1;
+                let value = calls;
+                async move { Ok(value) }
+            },
+            |value| *value == 3,
+            |_, value| seen.push(*value),
+        )
+        .await
+        .unwrap();
+        assert_eq!(value, 3);
+        assert_eq!(seen, vec![1, 2, 3]);
+
+        let err = poll_until(
+            &client,
+            "Counter",
+            Duration::from_millis(5),
+            Duration::from_millis(1),
+            || async { Ok(0) },
+            |_| false,
+            |_, _| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, CliError::Timeout(ref message) if message.starts_with("Counter did not"))
+        );
+
+        // Whichever limit comes first applies
+        let mut config = client.config().clone();
+        config.monitor.max_polls = Some(2);
+        let client = Client::new(config).unwrap();
+        let mut calls = 0;
+        let err = poll_until(
+            &client,
+            "Counter",
+            Duration::from_millis(1),
+            Duration::from_secs(60),
+            || {
+                calls += 1;
+                async { Ok(0) }
+            },
+            |_| false,
+            |_, _| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, CliError::MaxPolls(ref message) if message.ends_with("within 2 polls"))
+        );
+        assert_eq!(err.exit_code(), 6);
+        assert_eq!(calls, 2);
+    }
+}
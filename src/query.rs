@@ -0,0 +1,96 @@
+//! Dotted-path selector behind `--query`.
+//!
+//! A path is a `.`-separated list of object keys and array indexes, with `*`
+//! matching every element of an array or every value of an object:
+//! `status`, `summary.objects.failed`, `resultLinks.0.url`, `logs.*.message`.
+
+use crate::error::{CliError, Result};
+use serde_json::Value;
+
+/// Return every value in `root` matched by `path`, in document order.
+/// An empty path selects the whole document.
+pub fn select<'a>(root: &'a Value, path: &str) -> Result<Vec<&'a Value>> {
+    let path = path.trim().trim_start_matches('.');
+    let mut current = vec![root];
+    if path.is_empty() {
+        return Ok(current);
+    }
+
+    for segment in path.split('.') {
+        if segment.is_empty() {
+            return Err(CliError::InvalidArgument(format!(
+                "Invalid --query '{}': empty path segment",
+                path
+            )));
+        }
+
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (segment, value) {
+                    ("*", Value::Array(items)) => items.iter().collect(),
+                    ("*", Value::Object(map)) => map.values().collect(),
+                    (_, Value::Object(map)) => map.get(segment).into_iter().collect(),
+                    (_, Value::Array(items)) => segment
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| items.get(index))
+                        .into_iter()
+                        .collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+
+    if current.is_empty() {
+        return Err(CliError::InvalidArgument(format!(
+            "--query '{}' did not match anything in the result",
+            path
+        )));
+    }
+    Ok(current)
+}
+
+/// Render a selected value for the terminal: strings without quotes,
+/// other scalars as JSON, objects and arrays as [`crate::output::json`]
+/// prints them.
+pub fn render(value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        Value::Object(_) | Value::Array(_) => crate::output::to_json_string(value)?,
+        other => other.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_paths() {
+        let doc = json!({
+            "status": "IN_PROGRESS",
+            "summary": {"objects": {"failed": 2}},
+            "resultLinks": ["a", "b"],
+            "logs": [{"message": "one"}, {"message": "two"}]
+        });
+
+        let status = select(&doc, "status").unwrap();
+        assert_eq!(render(status[0]).unwrap(), "IN_PROGRESS");
+        assert_eq!(
+            select(&doc, "summary.objects.failed").unwrap(),
+            vec![&json!(2)]
+        );
+        assert_eq!(select(&doc, "resultLinks.1").unwrap(), vec![&json!("b")]);
+        assert_eq!(
+            select(&doc, "logs.*.message").unwrap(),
+            vec![&json!("one"), &json!("two")]
+        );
+
+        assert!(select(&doc, "summary.missing").is_err());
+        assert!(select(&doc, "resultLinks.9").is_err());
+        assert!(select(&doc, "status..x").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Client-side request pacing, so concurrent commands (per-application
+//! `get-packages`, bulk exports) do not trigger the server's own 429s.
+
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// A token bucket refilled at `per_second` tokens a second and holding up
+/// to one second's worth, so short bursts pass at once and sustained
+/// traffic is spread out. Shared by every request of a [`crate::Client`].
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `per_second` requests a second; `None` for `0`,
+    /// which means unlimited.
+    pub fn new(per_second: u32) -> Option<Self> {
+        (per_second > 0).then(|| {
+            let per_second = f64::from(per_second);
+            RateLimiter {
+                per_second,
+                bucket: Mutex::new(Bucket {
+                    tokens: per_second,
+                    refilled_at: Instant::now(),
+                }),
+            }
+        })
+    }
+
+    /// Wait until a request may be sent, then take its token.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled_at).as_secs_f64() * self.per_second;
+                bucket.tokens = (bucket.tokens + refill).min(self.per_second);
+                bucket.refilled_at = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_are_paced_after_a_burst() {
+        assert!(RateLimiter::new(0).is_none());
+
+        let limiter = RateLimiter::new(2).unwrap();
+        let started = Instant::now();
+        // The first second's worth passes at once
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(1990) && elapsed < Duration::from_millis(2100),
+            "{:?}",
+            elapsed
+        );
+    }
+}
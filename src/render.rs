@@ -0,0 +1,294 @@
+//! Output renderers selected by `--format`.
+//!
+//! Commands hand their results to the [`OutputRenderer`] returned by
+//! [`renderer`] instead of matching on the format themselves. The built-in
+//! `text`, `json`, `markdown` and `junit` renderers implement it, and a
+//! build can add its own format with [`register`]. A renderer only has to
+//! implement the results it has something to say about; every other method
+//! falls back to the text output. Commands whose results have no method here
+//! print the built-in formats themselves and refuse registered ones through
+//! [`check_builtin`].
+
+use crate::error::CliError;
+use crate::models::{
+    DataSource, DeployResponse, DeploymentResults, InspectionResults, Package, StatusReport,
+};
+use crate::{output, text, Result};
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Prints one kind of result per method, in one output format
+pub trait OutputRenderer: Send + Sync {
+    /// Packages of `get-packages`
+    fn render_packages(&self, packages: &[Package]) -> Result<()> {
+        text::packages(packages);
+        Ok(())
+    }
+
+    /// Data sources of `list-data-sources`
+    fn render_data_sources(&self, data_sources: &[DataSource]) -> Result<()> {
+        text::data_sources(data_sources);
+        Ok(())
+    }
+
+    /// A deployment that was just started, with the `--label`s given for it
+    fn render_deploy_result(
+        &self,
+        response: &DeployResponse,
+        labels: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        text::deploy_result(response, labels);
+        Ok(())
+    }
+
+    /// Status of the export or deployment `uuid`
+    fn render_status(&self, uuid: &str, report: &StatusReport) -> Result<()> {
+        text::status(uuid, report);
+        Ok(())
+    }
+
+    /// Results of the inspection `uuid`; `max_entries` caps listed problems
+    fn render_inspection(
+        &self,
+        _uuid: &str,
+        results: &InspectionResults,
+        max_entries: Option<usize>,
+    ) -> Result<()> {
+        text::inspection(results, max_entries);
+        Ok(())
+    }
+
+    /// Results of the deployment `uuid`; `max_entries` caps listed problems
+    fn render_deployment_results(
+        &self,
+        _uuid: &str,
+        results: &DeploymentResults,
+        max_entries: Option<usize>,
+    ) -> Result<()> {
+        text::deployment_results(results, max_entries);
+        Ok(())
+    }
+}
+
+/// Builds the renderer for one `--format` value
+pub type RendererFactory = fn() -> Box<dyn OutputRenderer>;
+
+fn registry() -> &'static RwLock<BTreeMap<String, RendererFactory>> {
+    static REGISTRY: OnceLock<RwLock<BTreeMap<String, RendererFactory>>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Make `--format <name>` use the renderer built by `factory`. Takes
+/// precedence over a built-in format of the same name.
+pub fn register(name: &str, factory: RendererFactory) {
+    if let Ok(mut formats) = registry().write() {
+        formats.insert(name.to_string(), factory);
+    }
+}
+
+/// Names added with [`register`]
+pub fn registered_formats() -> Vec<String> {
+    registry()
+        .read()
+        .map(|formats| formats.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The renderer for `format`. Without a format, or with one nothing
+/// renders, the text output is used.
+pub fn renderer(format: Option<&str>) -> Box<dyn OutputRenderer> {
+    let registered = format.and_then(|name| registry().read().ok()?.get(name).copied());
+    if let Some(factory) = registered {
+        return factory();
+    }
+    match format {
+        Some("json") => Box::new(JsonRenderer),
+        Some("markdown") => Box::new(MarkdownRenderer),
+        Some("junit") => Box::new(JunitRenderer),
+        _ => Box::new(TextRenderer),
+    }
+}
+
+/// Fail when `format` was added with [`register`] but `command` prints its
+/// results itself, in the built-in formats only, so the registered renderer
+/// would be skipped without notice.
+pub fn check_builtin(format: Option<&str>, command: &str) -> Result<()> {
+    match format {
+        Some(name)
+            if registry()
+                .read()
+                .is_ok_and(|formats| formats.contains_key(name)) =>
+        {
+            Err(CliError::InvalidArgument(format!(
+                "--format {} is not available for {}, which only prints the built-in formats",
+                name, command
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Colored, human-readable blocks
+pub struct TextRenderer;
+
+impl OutputRenderer for TextRenderer {}
+
+/// Pretty-printed JSON of each result, honoring `--query`
+pub struct JsonRenderer;
+
+impl OutputRenderer for JsonRenderer {
+    fn render_packages(&self, packages: &[Package]) -> Result<()> {
+        output::json(&packages)
+    }
+
+    fn render_data_sources(&self, data_sources: &[DataSource]) -> Result<()> {
+        output::json(&data_sources)
+    }
+
+    fn render_deploy_result(
+        &self,
+        response: &DeployResponse,
+        labels: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut value = serde_json::to_value(response)?;
+        if let (false, Some(object)) = (labels.is_empty(), value.as_object_mut()) {
+            object.insert("labels".to_string(), serde_json::json!(labels));
+        }
+        output::json(&value)
+    }
+
+    fn render_status(&self, _uuid: &str, report: &StatusReport) -> Result<()> {
+        output::json_with_duration(report, report.duration_seconds())
+    }
+
+    fn render_inspection(
+        &self,
+        _uuid: &str,
+        results: &InspectionResults,
+        _max_entries: Option<usize>,
+    ) -> Result<()> {
+        output::json(results)
+    }
+
+    fn render_deployment_results(
+        &self,
+        _uuid: &str,
+        results: &DeploymentResults,
+        _max_entries: Option<usize>,
+    ) -> Result<()> {
+        output::json_with_duration(&results.tagged(), results.duration_seconds())
+    }
+}
+
+/// Pull request comments; see [`crate::markdown`]
+pub struct MarkdownRenderer;
+
+impl OutputRenderer for MarkdownRenderer {
+    fn render_inspection(
+        &self,
+        uuid: &str,
+        results: &InspectionResults,
+        max_entries: Option<usize>,
+    ) -> Result<()> {
+        print!(
+            "{}",
+            crate::markdown::inspection_report(uuid, results, max_entries)
+        );
+        Ok(())
+    }
+
+    fn render_deployment_results(
+        &self,
+        uuid: &str,
+        results: &DeploymentResults,
+        max_entries: Option<usize>,
+    ) -> Result<()> {
+        print!(
+            "{}",
+            crate::markdown::deployment_report(uuid, results, max_entries)
+        );
+        Ok(())
+    }
+}
+
+/// CI test reports; see [`crate::junit`]
+pub struct JunitRenderer;
+
+impl OutputRenderer for JunitRenderer {
+    fn render_inspection(
+        &self,
+        uuid: &str,
+        results: &InspectionResults,
+        _max_entries: Option<usize>,
+    ) -> Result<()> {
+        print!("{}", crate::junit::inspection_report(uuid, results));
+        Ok(())
+    }
+
+    fn render_deployment_results(
+        &self,
+        uuid: &str,
+        results: &DeploymentResults,
+        _max_entries: Option<usize>,
+    ) -> Result<()> {
+        match results {
+            DeploymentResults::Import(import) => {
+                print!("{}", crate::junit::import_report(uuid, import));
+                Ok(())
+            }
+            DeploymentResults::Export(_) => Err(CliError::InvalidArgument(
+                "--format junit is only available for import results".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RENDERED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingRenderer;
+
+    impl OutputRenderer for CountingRenderer {
+        fn render_data_sources(&self, data_sources: &[DataSource]) -> Result<()> {
+            RENDERED.fetch_add(data_sources.len(), Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_registered_formats_are_selected() {
+        register("counting", || Box::new(CountingRenderer));
+        assert!(registered_formats().contains(&"counting".to_string()));
+
+        let data_source = DataSource {
+            name: "jdbc/Appian".to_string(),
+            uuid: "ds-1".to_string(),
+            data_source_type: None,
+        };
+        renderer(Some("counting"))
+            .render_data_sources(&[data_source.clone(), data_source])
+            .unwrap();
+        assert_eq!(RENDERED.load(Ordering::SeqCst), 2);
+
+        let export: DeploymentResults = serde_json::from_str(r#"{"status": "COMPLETED"}"#).unwrap();
+        let err = renderer(Some("junit"))
+            .render_deployment_results("e1", &export, None)
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+
+        let err = check_builtin(Some("counting"), "inspect-local").unwrap_err();
+        assert!(matches!(err, CliError::InvalidArgument(_)));
+        assert!(
+            err.to_string()
+                .contains("--format counting is not available for inspect-local"),
+            "{}",
+            err
+        );
+        assert!(check_builtin(Some("json"), "inspect-local").is_ok());
+        assert!(check_builtin(None, "inspect-local").is_ok());
+    }
+}
@@ -0,0 +1,156 @@
+//! Small files kept between runs under the state directory (see
+//! [`crate::Config::state_dir`]), such as how far `logs --since-last-run`
+//! got for each deployment.
+
+use crate::error::{CliError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The last log entry printed for a deployment. Entries share timestamps,
+/// so the number printed at `timestamp` is kept too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogCursor {
+    pub timestamp: DateTime<Utc>,
+    pub printed_at_timestamp: usize,
+}
+
+impl LogCursor {
+    /// The cursor after printing `timestamps`, in order, from where `self`
+    /// left off. Unchanged when nothing was printed.
+    pub fn advance(
+        previous: Option<Self>,
+        timestamps: impl IntoIterator<Item = DateTime<Utc>>,
+    ) -> Option<Self> {
+        timestamps
+            .into_iter()
+            .fold(previous, |cursor, timestamp| match cursor {
+                Some(cursor) if cursor.timestamp == timestamp => Some(LogCursor {
+                    printed_at_timestamp: cursor.printed_at_timestamp + 1,
+                    ..cursor
+                }),
+                _ => Some(LogCursor {
+                    timestamp,
+                    printed_at_timestamp: 1,
+                }),
+            })
+    }
+}
+
+/// Per-deployment [`LogCursor`]s, one JSON file each under `logs/` in the
+/// state directory.
+#[derive(Debug, Clone)]
+pub struct CursorStore {
+    dir: PathBuf,
+}
+
+impl CursorStore {
+    pub fn new(state_dir: &Path) -> Self {
+        CursorStore {
+            dir: state_dir.join("logs"),
+        }
+    }
+
+    fn path(&self, deployment_uuid: &str) -> PathBuf {
+        // Keep an odd UUID from escaping the directory
+        let name: String = deployment_uuid
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// The saved cursor, `None` when there is none yet. A file that cannot
+    /// be read or parsed is an error, which callers may treat as no cursor.
+    pub fn load(&self, deployment_uuid: &str) -> Result<Option<LogCursor>> {
+        let path = self.path(deployment_uuid);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(CliError::FileSystem(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        serde_json::from_str(&contents).map(Some).map_err(|e| {
+            CliError::FileSystem(format!(
+                "Unreadable log position in {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Save `cursor`, replacing the file atomically so an interrupted run
+    /// cannot leave it half-written.
+    pub fn save(&self, deployment_uuid: &str, cursor: &LogCursor) -> Result<()> {
+        let path = self.path(deployment_uuid);
+        let temp_path = path.with_extension("json.tmp");
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(&self.dir)?;
+            std::fs::write(&temp_path, serde_json::to_vec(cursor)?)?;
+            std::fs::rename(&temp_path, &path)
+        };
+        write().map_err(|e| {
+            CliError::FileSystem(format!(
+                "Failed to save log position to {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_store_round_trip_and_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CursorStore::new(dir.path());
+        assert_eq!(store.load("d-1").unwrap(), None);
+
+        let t1: DateTime<Utc> = "2024-01-01T10:00:00Z".parse().unwrap();
+        let t2: DateTime<Utc> = "2024-01-01T10:00:05Z".parse().unwrap();
+        let cursor = LogCursor::advance(None, [t1, t2, t2]).unwrap();
+        assert_eq!(
+            cursor,
+            LogCursor {
+                timestamp: t2,
+                printed_at_timestamp: 2
+            }
+        );
+        assert_eq!(
+            LogCursor::advance(Some(cursor), [t2])
+                .unwrap()
+                .printed_at_timestamp,
+            3
+        );
+        assert_eq!(LogCursor::advance(Some(cursor), []), Some(cursor));
+
+        store.save("d-1", &cursor).unwrap();
+        assert_eq!(store.load("d-1").unwrap(), Some(cursor));
+        assert!(store.load("d-2").unwrap().is_none());
+
+        std::fs::write(dir.path().join("logs").join("d-1.json"), "{not json").unwrap();
+        assert!(store
+            .load("d-1")
+            .unwrap_err()
+            .to_string()
+            .contains("Unreadable log position"));
+        assert_eq!(
+            store.path("../etc/x"),
+            dir.path().join("logs").join("___etc_x.json")
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Human-readable rendering, the default when `--format` is not given.
+//!
+//! Everything here prints colored, indented blocks to stdout; colors are
+//! dropped by `--color never`.
+
+use crate::duration::format_elapsed;
+use crate::models::{
+    DataSource, DeployResponse, DeploymentResults, ImportSummary, InspectionResults, Package,
+    RollbackSummary, StatusReport,
+};
+use crate::output;
+use colored::*;
+use std::collections::BTreeMap;
+
+pub fn packages(packages: &[Package]) {
+    println!("{}", output::tagged("Packages:".bold().green()));
+    println!("Total packages: {}", packages.len().to_string().cyan());
+    println!();
+
+    if packages.is_empty() {
+        println!("{}", "No packages found.".yellow());
+    } else {
+        for package in packages {
+            package_entry(package);
+        }
+    }
+}
+
+/// One package as a bulleted block
+pub fn package_entry(package: &Package) {
+    println!("{} {}", "•".cyan(), package.name.bold());
+    println!("  {}: {}", "Version".dimmed(), package.version);
+    println!("  {}: {}", "ID".dimmed(), package.id);
+
+    if !package.dependencies.is_empty() {
+        println!(
+            "  {}: {}",
+            "Dependencies".dimmed(),
+            package.dependencies.join(", ")
+        );
+    }
+
+    println!(
+        "  {}: {}",
+        "Created".dimmed(),
+        package.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    println!();
+}
+
+pub fn data_sources(data_sources: &[DataSource]) {
+    println!("{}", output::tagged("Data Sources:".bold().green()));
+    if data_sources.is_empty() {
+        println!("{}", "No data sources found.".yellow());
+        return;
+    }
+
+    let width = data_sources
+        .iter()
+        .map(|ds| ds.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Name".len());
+    println!(
+        "  {:<width$}  {:<36}  {}",
+        "Name".dimmed(),
+        "UUID".dimmed(),
+        "Type".dimmed(),
+        width = width
+    );
+    for data_source in data_sources {
+        println!(
+            "  {:<width$}  {:<36}  {}",
+            data_source.name,
+            data_source.uuid,
+            data_source.data_source_type.as_deref().unwrap_or("-"),
+            width = width
+        );
+    }
+}
+
+pub fn deploy_result(response: &DeployResponse, labels: &BTreeMap<String, String>) {
+    println!("\n{}", output::tagged("Deployment Details:".bold()));
+    println!("  {}: {}", "Deployment UUID".dimmed(), response.uuid);
+    println!("  {}: {}", "Status".dimmed(), response.status);
+    println!("  {}: {}", "Results URL".dimmed(), response.url);
+    for (key, value) in labels {
+        println!("  {}: {}={}", "Label".dimmed(), key, value);
+    }
+    output::status(format!(
+        "\n{}",
+        "Use 'status' or 'monitor' commands to track progress".dimmed()
+    ));
+}
+
+pub fn status(uuid: &str, report: &StatusReport) {
+    let duration = report.duration_seconds();
+    match report {
+        StatusReport::Export(export) => {
+            println!("{}", output::tagged("Export Status:".bold().green()));
+            println!("  {}: {}", "Export UUID".dimmed(), uuid);
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&export.status)
+            );
+            if let Some(seconds) = duration {
+                println!("  {}: {}", "Duration".dimmed(), format_elapsed(seconds));
+            }
+            output::export_details(export);
+
+            if export.status.is_terminal() {
+                println!("\n{}", "Operation completed".green());
+            } else {
+                println!("\n{}", "Operation in progress...".yellow());
+            }
+        }
+        StatusReport::Deployment(response) => {
+            println!("{}", output::tagged("Deployment Status:".bold().green()));
+            println!("  {}: {}", "Deployment ID".dimmed(), response.deployment_id);
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&response.status)
+            );
+
+            if let Some(current_step) = &response.current_step {
+                println!("  {}: {}", "Current Step".dimmed(), current_step);
+            }
+
+            if !response.result_links.is_empty() {
+                println!("  {}:", "Result Links".dimmed());
+                for link in &response.result_links {
+                    println!("    • {}", link);
+                }
+            }
+
+            println!(
+                "  {}: {}",
+                "Created".dimmed(),
+                response.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            println!(
+                "  {}: {}",
+                "Updated".dimmed(),
+                response.updated_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+            if let Some(seconds) = duration {
+                println!("  {}: {}", "Duration".dimmed(), format_elapsed(seconds));
+            }
+
+            if response.status.is_terminal() {
+                println!("\n{}", "Deployment completed".green());
+            } else {
+                println!("\n{}", "Deployment in progress...".yellow());
+            }
+        }
+    }
+}
+
+/// `max_entries` caps the errors and warnings listed; `Some(0)` prints only
+/// the counts.
+pub fn inspection(results: &InspectionResults, max_entries: Option<usize>) {
+    println!("{}", output::tagged("Inspection Results:".bold().green()));
+    println!(
+        "  {}: {}",
+        "Status".dimmed(),
+        output::styled_status(&results.status)
+    );
+
+    let admin = &results.summary.admin_console_settings_expected;
+    println!("{}", "  Admin Console Settings:".bold());
+    println!("    {}: {}", "Total".dimmed(), admin.total);
+    println!("    {}: {}", "Imported".dimmed(), admin.imported);
+    println!("    {}: {}", "Failed".dimmed(), admin.failed);
+    println!("    {}: {}", "Skipped".dimmed(), admin.skipped);
+
+    let objs = &results.summary.objects_expected;
+    println!("{}", "  Package Objects:".bold());
+    println!("    {}: {}", "Total".dimmed(), objs.total);
+    println!("    {}: {}", "Imported".dimmed(), objs.imported);
+    println!("    {}: {}", "Failed".dimmed(), objs.failed);
+    println!("    {}: {}", "Skipped".dimmed(), objs.skipped);
+
+    let probs = &results.summary.problems;
+    println!("{}", "  Problems:".bold());
+    println!("    {}: {}", "Total Errors".dimmed(), probs.total_errors);
+    println!(
+        "    {}: {}",
+        "Total Warnings".dimmed(),
+        probs.total_warnings
+    );
+
+    if !probs.errors.is_empty() && max_entries != Some(0) {
+        println!("{}", "    Errors:".bold());
+        output::capped(&probs.errors, max_entries, "      ", |e| {
+            println!("      • {}", e.object_name.bold());
+            println!("        {}: {}", "UUID".dimmed(), e.object_uuid);
+            println!("        {}: {}", "Message".dimmed(), e.error_message);
+        });
+    }
+
+    if !probs.warnings.is_empty() && max_entries != Some(0) {
+        println!("{}", "    Warnings:".bold());
+        output::capped(&probs.warnings, max_entries, "      ", |w| {
+            println!("      • {}", w.object_name.bold());
+            println!("        {}: {}", "UUID".dimmed(), w.object_uuid);
+            println!("        {}: {}", "Message".dimmed(), w.warning_message);
+        });
+    }
+}
+
+/// `max_entries` caps the failed objects listed; `Some(0)` prints only the
+/// counts.
+pub fn deployment_results(results: &DeploymentResults, max_entries: Option<usize>) {
+    println!("{}", output::tagged("Deployment Results:".bold().green()));
+    let duration = results.duration_seconds();
+    match results {
+        DeploymentResults::Import(import) => {
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&import.status)
+            );
+            if let Some(seconds) = duration {
+                println!("  {}: {}", "Duration".dimmed(), format_elapsed(seconds));
+            }
+            println!(
+                "  {}: {}",
+                "Deployment Log".dimmed(),
+                import.summary.deployment_log_url
+            );
+            println!();
+            for line in render_summary_table(&summary_rows(&import.summary)) {
+                println!("  {}", line);
+            }
+            println!();
+            if !import.summary.objects.errors.is_empty() && max_entries != Some(0) {
+                println!("  {}:", "Failed Objects".dimmed());
+                output::capped(&import.summary.objects.errors, max_entries, "    ", |e| {
+                    println!("    • {}", e.object_name.bold());
+                    println!("      {}: {}", "UUID".dimmed(), e.object_uuid);
+                    println!("      {}: {}", "Message".dimmed(), e.error_message);
+                });
+            }
+            println!(
+                "  {}: {}",
+                "Database Scripts".dimmed(),
+                import.summary.database_scripts
+            );
+            if let Some(rollback) = &import.rollback {
+                println!();
+                for line in rollback_lines(Some(rollback)) {
+                    println!("  {}", line);
+                }
+            }
+        }
+        DeploymentResults::Export(export) => {
+            println!(
+                "  {}: {}",
+                "Status".dimmed(),
+                output::styled_status(&export.status)
+            );
+            if let Some(seconds) = duration {
+                println!("  {}: {}", "Duration".dimmed(), format_elapsed(seconds));
+            }
+            output::export_details(export);
+        }
+    }
+}
+
+/// What a rollback reverted, as lines without indentation. Without a
+/// summary from the API, only says that a rollback happened.
+pub fn rollback_lines(summary: Option<&RollbackSummary>) -> Vec<String> {
+    let Some(summary) = summary else {
+        return vec![format!(
+            "{} The deployment was rolled back; the server reported no details of what was reverted",
+            "↺".yellow()
+        )];
+    };
+
+    let objects = &summary.objects;
+    let mut lines = vec![
+        format!("{}", "Rollback:".bold().yellow()),
+        format!(
+            "  {}: {} of {}",
+            "Objects Reverted".dimmed(),
+            objects.reverted,
+            objects.total
+        ),
+    ];
+    if objects.failed > 0 {
+        lines.push(format!(
+            "  {}: {}",
+            "Not Reverted".dimmed(),
+            objects.failed.to_string().red().bold()
+        ));
+    }
+    if summary.database_scripts > 0 {
+        lines.push(format!(
+            "  {}: {}",
+            "Database Scripts Reverted".dimmed(),
+            summary.database_scripts
+        ));
+    }
+    if let Some(url) = &summary.rollback_log_url {
+        lines.push(format!("  {}: {}", "Rollback Log".dimmed(), url));
+    }
+    for object in &objects.reverted_objects {
+        lines.push(format!(
+            "  • {} ({})",
+            object.object_name.bold(),
+            object.object_uuid
+        ));
+    }
+    lines
+}
+
+/// One line of the import summary table. Plugins have no failed count.
+struct SummaryRow {
+    label: &'static str,
+    total: u32,
+    imported: u32,
+    failed: Option<u32>,
+    skipped: u32,
+}
+
+fn summary_rows(summary: &ImportSummary) -> Vec<SummaryRow> {
+    let admin = &summary.admin_console_settings;
+    let objects = &summary.objects;
+    let plugins = &summary.plugins;
+
+    vec![
+        SummaryRow {
+            label: "Admin Console Settings",
+            total: admin.total,
+            imported: admin.imported,
+            failed: Some(admin.failed),
+            skipped: admin.skipped,
+        },
+        SummaryRow {
+            label: "Objects",
+            total: objects.total,
+            imported: objects.imported,
+            failed: Some(objects.failed),
+            skipped: objects.skipped,
+        },
+        SummaryRow {
+            label: "Plugins",
+            total: plugins.total,
+            imported: plugins.imported,
+            failed: None,
+            skipped: plugins.skipped,
+        },
+        SummaryRow {
+            label: "Total",
+            total: admin.total + objects.total + plugins.total,
+            imported: admin.imported + objects.imported + plugins.imported,
+            failed: Some(admin.failed + objects.failed),
+            skipped: admin.skipped + objects.skipped + plugins.skipped,
+        },
+    ]
+}
+
+/// Render rows as an aligned table; the last row is treated as the total.
+/// Cells are padded before coloring so escape codes don't skew alignment.
+fn render_summary_table(rows: &[SummaryRow]) -> Vec<String> {
+    const HEADERS: [&str; 5] = ["", "Total", "Imported", "Failed", "Skipped"];
+
+    let cells: Vec<[String; 5]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.label.to_string(),
+                row.total.to_string(),
+                row.imported.to_string(),
+                row.failed
+                    .map_or_else(|| "-".to_string(), |f| f.to_string()),
+                row.skipped.to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let header = format!(
+        "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+        HEADERS[0],
+        HEADERS[1],
+        HEADERS[2],
+        HEADERS[3],
+        HEADERS[4],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+        w3 = widths[3],
+        w4 = widths[4]
+    );
+    let rule = "-".repeat(header.len());
+
+    let mut lines = vec![header.dimmed().to_string(), rule.dimmed().to_string()];
+    for (index, (row, cell)) in rows.iter().zip(&cells).enumerate() {
+        let is_total = index + 1 == rows.len();
+        if is_total {
+            lines.push(rule.dimmed().to_string());
+        }
+
+        let label = format!("{:<w$}", cell[0], w = widths[0]);
+        let failed = format!("{:>w$}", cell[3], w = widths[3]);
+        let failed = match row.failed {
+            Some(0) => failed.green(),
+            Some(_) => failed.red().bold(),
+            None => failed.dimmed(),
+        };
+
+        lines.push(format!(
+            "{}  {:>w1$}  {:>w2$}  {}  {:>w4$}",
+            if is_total {
+                label.bold()
+            } else {
+                label.normal()
+            },
+            cell[1],
+            cell[2],
+            failed,
+            cell[4],
+            w1 = widths[1],
+            w2 = widths[2],
+            w4 = widths[4]
+        ));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AdminConsoleSettingsSummary, ObjectsSummary, PluginsSummary};
+
+    #[test]
+    fn test_summary_table_alignment_and_totals() {
+        colored::control::set_override(false);
+
+        let summary = ImportSummary {
+            database_scripts: 0,
+            admin_console_settings: AdminConsoleSettingsSummary {
+                total: 2,
+                imported: 2,
+                failed: 0,
+                skipped: 0,
+            },
+            plugins: PluginsSummary {
+                total: 1,
+                imported: 1,
+                skipped: 0,
+            },
+            objects: ObjectsSummary {
+                total: 1250,
+                imported: 1240,
+                failed: 7,
+                skipped: 3,
+                errors: vec![],
+            },
+            deployment_log_url: String::new(),
+        };
+
+        let lines = render_summary_table(&summary_rows(&summary));
+        let widths: Vec<usize> = lines.iter().map(|l| l.chars().count()).collect();
+        assert!(
+            widths.iter().all(|w| *w == widths[0]),
+            "misaligned rows: {:?}",
+            lines
+        );
+
+        let total = lines.last().unwrap();
+        assert!(total.starts_with("Total"));
+        assert!(total.contains("1253") && total.contains("1243"));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("Plugins") && l.contains(" - ")));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::error::{CliError, Result};
+use reqwest::{Request, Response};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = reqwest::Result<Response>> + Send + 'a>>;
+
+/// Sends a fully built request. `Client` goes through this trait so tests can
+/// substitute canned responses for the network.
+pub trait HttpTransport: Send + Sync {
+    fn execute(&self, request: Request) -> TransportFuture<'_>;
+}
+
+/// Default transport backed by a `reqwest::Client`.
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        ReqwestTransport { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        Box::pin(self.client.execute(request))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl MockResponse {
+    pub fn json(status: u16, body: &str) -> Self {
+        MockResponse {
+            status,
+            body: body.to_string(),
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// A request as seen by [`MockTransport`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: reqwest::Method,
+    pub url: String,
+    pub headers: reqwest::header::HeaderMap,
+}
+
+/// Test double that answers requests from a queue of canned responses.
+/// The last response is repeated once the queue runs dry, which keeps
+/// polling loops simple to script.
+#[derive(Default)]
+pub struct MockTransport {
+    responses: Mutex<VecDeque<MockResponse>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockTransport {
+    pub fn new(responses: Vec<MockResponse>) -> Self {
+        MockTransport {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn next_response(&self) -> MockResponse {
+        let mut responses = self.responses.lock().unwrap();
+        if responses.len() > 1 {
+            responses.pop_front().unwrap()
+        } else {
+            responses
+                .front()
+                .cloned()
+                .expect("MockTransport has no canned responses")
+        }
+    }
+}
+
+impl HttpTransport for MockTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            method: request.method().clone(),
+            url: request.url().to_string(),
+            headers: request.headers().clone(),
+        });
+
+        let canned = self.next_response();
+        let mut builder = http::Response::builder().status(canned.status);
+        for (name, value) in &canned.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let response = builder
+            .body(canned.body)
+            .expect("MockResponse must form a valid HTTP response");
+
+        Box::pin(async move { Ok(Response::from(response)) })
+    }
+}
+
+/// Answers requests from a file of saved responses instead of the network,
+/// for `--replay-file`.
+///
+/// The file holds either a single saved JSON body, returned for every
+/// request, or `{"responses": [{"method", "path", "status", "body"}]}`. A
+/// request gets the entries whose path its URL path ends with (and whose
+/// method matches, when given) in file order, repeating the last one, so a
+/// status poll can be scripted. Requests nothing matches get a 404.
+pub struct ReplayTransport {
+    source: PathBuf,
+    entries: Vec<ReplayEntry>,
+    used: Mutex<Vec<bool>>,
+}
+
+#[derive(serde::Deserialize)]
+struct ReplayEntry {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default = "default_replay_status")]
+    status: u16,
+    body: serde_json::Value,
+}
+
+fn default_replay_status() -> u16 {
+    200
+}
+
+#[derive(serde::Deserialize)]
+struct ReplayFile {
+    responses: Vec<ReplayEntry>,
+}
+
+impl ReplayTransport {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            CliError::FileSystem(format!(
+                "Failed to read replay file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| {
+            CliError::InvalidArgument(format!(
+                "Replay file {} is not valid JSON: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let entries = match value.get("responses") {
+            Some(_) => {
+                serde_json::from_value::<ReplayFile>(value)
+                    .map_err(|e| {
+                        CliError::InvalidArgument(format!(
+                            "Invalid responses in replay file {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?
+                    .responses
+            }
+            None => vec![ReplayEntry {
+                method: None,
+                path: None,
+                status: 200,
+                body: value,
+            }],
+        };
+
+        Ok(ReplayTransport {
+            source: path.to_path_buf(),
+            used: Mutex::new(vec![false; entries.len()]),
+            entries,
+        })
+    }
+
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    fn respond(&self, method: &reqwest::Method, path: &str) -> MockResponse {
+        let matching: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| {
+                let entry = &self.entries[i];
+                entry
+                    .method
+                    .as_deref()
+                    .is_none_or(|m| m.eq_ignore_ascii_case(method.as_str()))
+                    && entry
+                        .path
+                        .as_deref()
+                        .is_none_or(|p| path.ends_with(p.trim_end_matches('/')))
+            })
+            .collect();
+
+        let mut used = self.used.lock().unwrap();
+        let chosen = matching
+            .iter()
+            .copied()
+            .find(|&i| !used[i])
+            .or(matching.last().copied());
+        match chosen {
+            Some(i) => {
+                used[i] = true;
+                let entry = &self.entries[i];
+                MockResponse::json(entry.status, &entry.body.to_string())
+            }
+            None => MockResponse::json(
+                404,
+                &format!(
+                    "No saved response for {} {} in replay file {}",
+                    method,
+                    path,
+                    self.source.display()
+                ),
+            ),
+        }
+    }
+}
+
+impl HttpTransport for ReplayTransport {
+    fn execute(&self, request: Request) -> TransportFuture<'_> {
+        let canned = self.respond(request.method(), request.url().path());
+        let mut builder = http::Response::builder().status(canned.status);
+        for (name, value) in &canned.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        let response = builder
+            .body(canned.body)
+            .expect("saved responses form valid HTTP responses");
+
+        Box::pin(async move { Ok(Response::from(response)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_file_matches_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("replay.json");
+        std::fs::write(
+            &path,
+            r#"{"responses": [
+                {"method": "GET", "path": "/deployments/d1", "body": {"status": "IN_PROGRESS"}},
+                {"method": "GET", "path": "/deployments/d1", "body": {"status": "COMPLETED"}},
+                {"method": "POST", "path": "/deployments", "status": 202, "body": {"uuid": "d1"}}
+            ]}"#,
+        )
+        .unwrap();
+        let replay = ReplayTransport::load(&path).unwrap();
+
+        let get = reqwest::Method::GET;
+        assert!(replay
+            .respond(&get, "/suite/deployment-management/v2/deployments/d1")
+            .body
+            .contains("IN_PROGRESS"));
+        assert!(replay
+            .respond(&get, "/suite/deployment-management/v2/deployments/d1")
+            .body
+            .contains("COMPLETED"));
+        assert!(replay
+            .respond(&get, "/suite/deployment-management/v2/deployments/d1")
+            .body
+            .contains("COMPLETED"));
+        assert_eq!(
+            replay
+                .respond(&reqwest::Method::POST, "/deployment/v2/deployments")
+                .status,
+            202
+        );
+        assert_eq!(replay.respond(&get, "/deployment/v2/packages").status, 404);
+
+        std::fs::write(&path, r#"{"status": "COMPLETED"}"#).unwrap();
+        let single = ReplayTransport::load(&path).unwrap();
+        assert_eq!(
+            single.respond(&get, "/anything").body,
+            r#"{"status":"COMPLETED"}"#
+        );
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(ReplayTransport::load(&path).err().unwrap().exit_code(), 2);
+    }
+}
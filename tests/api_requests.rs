@@ -0,0 +1,768 @@
+//! End-to-end request construction tests against a mock Appian API.
+#![cfg(all(feature = "export", feature = "deploy", feature = "status"))]
+
+use appian_deployment_cli::{Client, Config, DeployArgs};
+use flate2::write::GzEncoder;
+use serde_json::json;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wiremock::matchers::{body_string_contains, header, headers, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const DEPLOYMENT_UUID: &str = "8a0b5f52-3c1e-4f7b-9d6a-1e2f3a4b5c6d";
+
+fn client_for(server: &MockServer) -> Client {
+    let config = Config {
+        base_url: server.uri(),
+        api_key: "test-key".to_string(),
+        timeout_seconds: 30,
+        ..Config::default()
+    };
+    Client::new(config).unwrap()
+}
+
+fn temp_file(dir: &tempfile::TempDir, name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let path = dir.path().join(name);
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(contents)
+        .unwrap();
+    path
+}
+
+#[tokio::test]
+async fn export_sends_action_type_and_json_part() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/suite/deployment-management/v2/deployments"))
+        .and(header("Action-Type", "export"))
+        .and(body_string_contains("name=\"json\""))
+        .and(body_string_contains("\"exportType\":\"package\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "uuid": DEPLOYMENT_UUID,
+            "url": "https://example.com/deployments/1",
+            "status": "IN_PROGRESS"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = appian_deployment_cli::commands::export::build_request(
+        &[DEPLOYMENT_UUID.to_string()],
+        "package",
+        Some("Nightly".to_string()),
+        None,
+    )
+    .unwrap();
+    let response = appian_deployment_cli::export(&client_for(&server), &request)
+        .await
+        .unwrap();
+    assert_eq!(response.uuid.to_string(), DEPLOYMENT_UUID);
+}
+
+#[tokio::test]
+async fn deploy_sends_multipart_keys_and_files() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/deployment/v2/deployments"))
+        .and(header("Action-Type", "import"))
+        .and(body_string_contains("name=\"json\""))
+        .and(body_string_contains(
+            "name=\"packageFileName\"; filename=\"pkg.zip\"",
+        ))
+        .and(body_string_contains(
+            "name=\"customizationFileName\"; filename=\"import.properties\"",
+        ))
+        .and(body_string_contains(
+            "name=\"databaseScript1\"; filename=\"01_schema.sql\"",
+        ))
+        .and(body_string_contains(
+            "name=\"databaseScript2\"; filename=\"02_seed.sql\"",
+        ))
+        .and(body_string_contains("PACKAGE-BYTES"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "uuid": DEPLOYMENT_UUID,
+            "url": "https://example.com/deployments/1",
+            "status": "IN_PROGRESS"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let args = DeployArgs {
+        package_zip_name: Some(temp_file(&dir, "pkg.zip", b"PACKAGE-BYTES")),
+        name: "Integration".to_string(),
+        customization_file: Some(temp_file(&dir, "import.properties", b"key=value")),
+        data_source: Some("11111111-1111-1111-1111-111111111111".to_string()),
+        database_scripts: Some(vec![
+            temp_file(&dir, "01_schema.sql", b"create table t (id int);"),
+            temp_file(&dir, "02_seed.sql", b"insert into t values (1);"),
+        ]),
+        ..Default::default()
+    };
+
+    let plan = appian_deployment_cli::commands::deploy::plan(&args).unwrap();
+    let response = appian_deployment_cli::deploy(&client_for(&server), &args, &plan)
+        .await
+        .unwrap();
+    assert_eq!(response.status, "IN_PROGRESS");
+}
+
+#[tokio::test]
+async fn plugins_only_deploy_omits_package_part() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/deployment/v2/deployments"))
+        .and(body_string_contains("name=\"pluginsFileName\""))
+        .and(|request: &wiremock::Request| {
+            !String::from_utf8_lossy(&request.body).contains("packageFileName")
+        })
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "uuid": DEPLOYMENT_UUID,
+            "url": "https://example.com/deployments/1",
+            "status": "IN_PROGRESS"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let args = DeployArgs {
+        name: "Plug-ins".to_string(),
+        plugins_file: Some(temp_file(&dir, "plugins.zip", b"PLUGIN-BYTES")),
+        ..Default::default()
+    };
+
+    let plan = appian_deployment_cli::commands::deploy::plan(&args).unwrap();
+    let response = appian_deployment_cli::deploy(&client_for(&server), &args, &plan)
+        .await
+        .unwrap();
+    assert_eq!(response.status, "IN_PROGRESS");
+}
+
+#[tokio::test]
+async fn status_hits_deployment_path() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "deploymentId": DEPLOYMENT_UUID,
+            "status": "SUCCEEDED",
+            "currentStep": null,
+            "resultLinks": [],
+            "createdAt": "2024-01-01T10:00:00Z",
+            "updatedAt": "2024-01-01T10:03:42Z"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let report =
+        appian_deployment_cli::status(&client_for(&server), DEPLOYMENT_UUID, Some("deployment"))
+            .await
+            .unwrap();
+    assert!(report.is_terminal());
+    assert_eq!(report.duration_seconds(), Some(222));
+}
+
+#[tokio::test]
+async fn requests_include_both_auth_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(header("Authorization", "Bearer test-key"))
+        .and(header("appian-api-key", "test-key"))
+        .and(header("Accept", "application/json"))
+        .and(header(
+            "User-Agent",
+            appian_deployment_cli::config::DEFAULT_USER_AGENT,
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "uuid": DEPLOYMENT_UUID,
+            "url": "https://example.com/deployments/1",
+            "status": "COMPLETED"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let report =
+        appian_deployment_cli::status(&client_for(&server), DEPLOYMENT_UUID, Some("export"))
+            .await
+            .unwrap();
+    assert!(report.is_terminal());
+}
+
+#[tokio::test]
+async fn gzip_responses_are_decoded() {
+    let body = json!({
+        "deploymentId": DEPLOYMENT_UUID,
+        "status": "IN_PROGRESS",
+        "currentStep": "Importing objects",
+        "resultLinks": [],
+        "createdAt": "2024-01-01T10:00:00Z",
+        "updatedAt": "2024-01-01T10:01:00Z"
+    });
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.to_string().as_bytes()).unwrap();
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .and(headers("Accept-Encoding", vec!["gzip", "deflate"]))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Encoding", "gzip")
+                .set_body_raw(encoder.finish().unwrap(), "application/json"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let report =
+        appian_deployment_cli::status(&client_for(&server), DEPLOYMENT_UUID, Some("deployment"))
+            .await
+            .unwrap();
+    assert!(!report.is_terminal());
+}
+
+#[cfg(feature = "monitor")]
+#[tokio::test]
+async fn monitor_retries_a_poll_slower_than_the_http_timeout() {
+    let server = MockServer::start().await;
+    let status = |status: &str| {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "deploymentId": DEPLOYMENT_UUID,
+            "status": status,
+            "currentStep": null,
+            "resultLinks": [],
+            "createdAt": "2024-01-01T10:00:00Z",
+            "updatedAt": "2024-01-01T10:01:00Z"
+        }))
+    };
+    // The first poll outlives the 1 second HTTP timeout
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(status("IN_PROGRESS").set_delay(std::time::Duration::from_millis(1500)))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(status("SUCCEEDED"))
+        .mount(&server)
+        .await;
+
+    let mut config = Config {
+        base_url: server.uri(),
+        api_key: "test-key".to_string(),
+        timeout_seconds: 1,
+        ..Config::default()
+    };
+    config.monitor.backoff_initial_ms = 1;
+    config.monitor.backoff_max_ms = 1;
+    let client = Client::new(config).unwrap();
+
+    // Interval longer than the HTTP timeout, operation timeout longer still
+    let report = appian_deployment_cli::monitor(
+        &client,
+        DEPLOYMENT_UUID,
+        Some("deployment"),
+        std::time::Duration::from_secs(2),
+        std::time::Duration::from_secs(30),
+        |_, _| {},
+    )
+    .await
+    .unwrap();
+    assert!(report.is_terminal());
+}
+
+#[cfg(feature = "monitor")]
+#[tokio::test]
+async fn attempt_timeout_cancels_a_hung_poll() {
+    let server = MockServer::start().await;
+    let hung = std::time::Duration::from_secs(20);
+    let status = |status: &str| {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "deploymentId": DEPLOYMENT_UUID,
+            "status": status,
+            "currentStep": null,
+            "resultLinks": [],
+            "createdAt": "2024-01-01T10:00:00Z",
+            "updatedAt": "2024-01-01T10:01:00Z"
+        }))
+    };
+    // The first poll hangs well past the attempt timeout, inside the HTTP timeout
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(status("IN_PROGRESS").set_delay(hung))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/deployment/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(status("SUCCEEDED"))
+        .mount(&server)
+        .await;
+
+    let mut config = Config {
+        base_url: server.uri(),
+        api_key: "test-key".to_string(),
+        timeout_seconds: 60,
+        attempt_timeout_seconds: Some(1),
+        ..Config::default()
+    };
+    config.monitor.backoff_initial_ms = 1;
+    config.monitor.backoff_max_ms = 1;
+
+    let started = std::time::Instant::now();
+    let client = Client::new(config.clone()).unwrap();
+    let report = appian_deployment_cli::monitor(
+        &client,
+        DEPLOYMENT_UUID,
+        Some("deployment"),
+        std::time::Duration::ZERO,
+        std::time::Duration::from_secs(30),
+        |_, _| {},
+    )
+    .await
+    .unwrap();
+    assert!(report.is_terminal());
+    assert!(started.elapsed() < hung);
+
+    // Without retries left, the hung attempt fails as a timeout
+    server.reset().await;
+    Mock::given(method("GET"))
+        .respond_with(status("IN_PROGRESS").set_delay(hung))
+        .mount(&server)
+        .await;
+    config.monitor.max_retries = 0;
+    let client = Client::new(config.clone()).unwrap();
+    let err = appian_deployment_cli::status(&client, DEPLOYMENT_UUID, Some("deployment"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), 6);
+    assert!(err.to_string().contains("attempt timeout of 1s"), "{}", err);
+
+    // Timeouts have their own cap, independent of transient retries
+    server.reset().await;
+    Mock::given(method("GET"))
+        .respond_with(status("IN_PROGRESS").set_delay(hung))
+        .mount(&server)
+        .await;
+    config.monitor.max_retries = 5;
+    config.monitor.max_retries_timeout = Some(0);
+    let client = Client::new(config).unwrap();
+    let err = appian_deployment_cli::status(&client, DEPLOYMENT_UUID, Some("deployment"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), 6);
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[cfg(feature = "monitor")]
+#[tokio::test]
+async fn long_polled_export_status_skips_the_interval() {
+    let server = MockServer::start().await;
+    let results =
+        |status: &str| ResponseTemplate::new(200).set_body_json(json!({ "status": status }));
+    let results_path = format!(
+        "/suite/deployment-management/v2/deployments/{}",
+        DEPLOYMENT_UUID
+    );
+    // The server holds the first long poll past the HTTP and attempt timeouts
+    Mock::given(method("GET"))
+        .and(path(results_path.clone()))
+        .and(query_param("wait", "3"))
+        .respond_with(results("IN_PROGRESS").set_delay(std::time::Duration::from_millis(1600)))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(results_path.clone()))
+        .and(query_param("wait", "3"))
+        .respond_with(results("COMPLETED"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(results_path))
+        .respond_with(results("IN_PROGRESS"))
+        .mount(&server)
+        .await;
+
+    let mut config = Config {
+        base_url: server.uri(),
+        api_key: "test-key".to_string(),
+        timeout_seconds: 1,
+        attempt_timeout_seconds: Some(1),
+        ..Config::default()
+    };
+    config.monitor.long_poll_seconds = Some(3);
+    let client = Client::new(config).unwrap();
+
+    let interval = std::time::Duration::from_secs(60);
+    let started = std::time::Instant::now();
+    let report = appian_deployment_cli::monitor(
+        &client,
+        DEPLOYMENT_UUID,
+        Some("export"),
+        interval,
+        std::time::Duration::from_secs(120),
+        |_, _| {},
+    )
+    .await
+    .unwrap();
+    assert!(report.is_terminal());
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].url.query().is_none());
+}
+
+#[cfg(feature = "download")]
+#[tokio::test]
+async fn from_export_downloads_linked_artifacts() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/suite/deployment-management/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "packageZip": format!("{}/artifacts/Nightly.zip", server.uri()),
+            "dataSource": null,
+            "databaseScripts": [],
+            "pluginsZip": null,
+            "customizationFile": "/artifacts/import.properties",
+            "customizationFileTemplate": null,
+            "deploymentLogUrl": null,
+            "status": "COMPLETED"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/artifacts/Nightly.zip"))
+        .and(header("appian-api-key", "test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"PACKAGE-BYTES".to_vec()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/artifacts/import.properties"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"key=value".to_vec()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let artifacts = appian_deployment_cli::commands::deploy::fetch_export_artifacts(
+        &client_for(&server),
+        DEPLOYMENT_UUID,
+        dir.path(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(artifacts.package_zip, dir.path().join("Nightly.zip"));
+    assert_eq!(
+        std::fs::read(&artifacts.package_zip).unwrap(),
+        b"PACKAGE-BYTES"
+    );
+    assert_eq!(
+        artifacts.customization_file,
+        Some(dir.path().join("import.properties"))
+    );
+    assert!(artifacts.plugins_file.is_none());
+}
+
+/// Serve each canned raw HTTP response on its own connection and close it
+/// straight after, so a response shorter than its `Content-Length` arrives
+/// truncated. Returns the base URL and the requests received.
+#[cfg(feature = "download")]
+async fn raw_http_server(
+    responses: Vec<String>,
+) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    tokio::spawn(async move {
+        for response in responses.iter().cycle() {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            seen.lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&request).to_lowercase());
+            socket.write_all(response.as_bytes()).await.unwrap();
+            socket.shutdown().await.unwrap();
+        }
+    });
+    (uri, requests)
+}
+
+#[cfg(feature = "download")]
+#[tokio::test]
+async fn truncated_download_resumes_with_a_range_request() {
+    let truncated = "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\nPACK".to_string();
+    let rest = "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-6/7\r\nContent-Length: 3\r\n\r\nAGE".to_string();
+    let (uri, requests) = raw_http_server(vec![truncated.clone(), rest]).await;
+
+    let mut config = Config {
+        base_url: uri,
+        api_key: "test-key".to_string(),
+        ..Config::default()
+    };
+    config.monitor.backoff_initial_ms = 1;
+    config.monitor.backoff_max_ms = 1;
+    let client = Client::new(config.clone()).unwrap();
+    let bytes = client.download_artifact("a1").await.unwrap();
+    assert_eq!(bytes, b"PACKAGE");
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert!(!requests[0].contains("range:"));
+    assert!(requests[1].contains("range: bytes=4-"), "{}", requests[1]);
+
+    // A connection that keeps dropping exhausts the retry budget
+    let (uri, requests) = raw_http_server(vec![truncated]).await;
+    config.base_url = uri;
+    config.monitor.max_retries = 2;
+    let err = Client::new(config)
+        .unwrap()
+        .download_artifact("a1")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, appian_deployment_cli::CliError::Network(_)),
+        "{:?}",
+        err
+    );
+    assert!(
+        err.to_string()
+            .contains("connection closed after 4 of 7 bytes, 2 resume attempt(s)"),
+        "{}",
+        err
+    );
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+/// A keep-alive HTTP/1.1 server answering every request with `body`,
+/// counting the connections it accepts and the requests it serves.
+async fn keep_alive_http_server(body: String) -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}", listener.local_addr().unwrap());
+    let (connections, requests) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+    let (accepted, served) = (connections.clone(), requests.clone());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let served = served.clone();
+            let body = body.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                loop {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                    while let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        request.drain(..end + 4);
+                        served.fetch_add(1, Ordering::SeqCst);
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        if socket.write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    (uri, connections, requests)
+}
+
+#[tokio::test]
+async fn repeated_polls_reuse_one_connection() {
+    let body = json!({
+        "deploymentId": DEPLOYMENT_UUID, "status": "IN_PROGRESS", "resultLinks": [],
+        "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z"
+    })
+    .to_string();
+
+    let (uri, connections, requests) = keep_alive_http_server(body.clone()).await;
+    let config = Config {
+        base_url: uri,
+        api_key: "test-key".to_string(),
+        ..Config::default()
+    };
+    let client = Client::new(config.clone()).unwrap();
+    for _ in 0..5 {
+        client.get_deployment_status(DEPLOYMENT_UUID).await.unwrap();
+    }
+    assert_eq!(requests.load(Ordering::SeqCst), 5);
+    assert_eq!(
+        connections.load(Ordering::SeqCst),
+        1,
+        "every poll should share the first connection"
+    );
+
+    // Without idle connections in the pool, every request connects afresh
+    let (uri, connections, _) = keep_alive_http_server(body).await;
+    let mut config = Config {
+        base_url: uri,
+        ..config
+    };
+    config.http.pool_max_idle_per_host = 0;
+    let client = Client::new(config).unwrap();
+    for _ in 0..3 {
+        client.get_deployment_status(DEPLOYMENT_UUID).await.unwrap();
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
+
+#[cfg(feature = "get_packages")]
+#[tokio::test]
+async fn export_estimate_aggregates_application_packages() {
+    let server = MockServer::start().await;
+    let package = |id: &str, objects: u64, size: u64| {
+        json!({
+            "id": id, "name": id, "version": "1", "dependencies": [],
+            "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-01T00:00:00Z",
+            "objectCount": objects, "sizeBytes": size
+        })
+    };
+    Mock::given(method("GET"))
+        .and(path("/deployment/v2/packages"))
+        .and(query_param(
+            "app_uuids",
+            "11111111-1111-1111-1111-111111111111",
+        ))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([package("p1", 12, 1000), package("p2", 3, 500)])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let app = uuid::Uuid::parse_str("11111111-1111-1111-1111-111111111111").unwrap();
+    let estimate = client_for(&server)
+        .estimate_export("application", &[app])
+        .await
+        .unwrap();
+    assert_eq!(estimate.packages.len(), 2);
+    assert_eq!(estimate.object_count, Some(15));
+    assert_eq!(estimate.size_bytes, Some(1500));
+}
+
+#[tokio::test]
+async fn export_status_keeps_every_artifact() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/suite/deployment-management/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "COMPLETED",
+            "packageZip": "https://example.com/package.zip",
+            "pluginsZip": "https://example.com/plugins.zip",
+            "customizationFile": "https://example.com/app.properties",
+            "customizationFileTemplate": "https://example.com/template.properties",
+            "databaseScripts": [
+                {"fileName": "seed.sql", "orderId": 2, "url": "https://example.com/seed.sql"},
+                {"fileName": "schema.sql", "orderId": 1, "url": "https://example.com/schema.sql"}
+            ],
+            "dataSource": "jdbc/Appian",
+            "deploymentLogUrl": "https://example.com/log"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let results = client_for(&server)
+        .get_export_status(DEPLOYMENT_UUID)
+        .await
+        .unwrap();
+    let labels: Vec<String> = results
+        .artifacts()
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            "Package Zip",
+            "Plugins Zip",
+            "Customization File",
+            "Customization File Template",
+            "Database Script 1 (schema.sql)",
+            "Database Script 2 (seed.sql)",
+        ]
+    );
+    assert_eq!(results.data_source.as_deref(), Some("jdbc/Appian"));
+}
+
+#[tokio::test]
+async fn raw_get_returns_the_unparsed_body() {
+    let server = MockServer::start().await;
+    let body = r#"{"status": "SOMETHING_NEW", "unexpected": [1, 2]}"#;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/suite/deployment-management/v2/deployments/{}",
+            DEPLOYMENT_UUID
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/plain"))
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server);
+    let results_path = client.endpoints().results(DEPLOYMENT_UUID);
+    assert_eq!(client.get_raw(&results_path).await.unwrap(), body);
+    assert!(client
+        .get_deployment_results(DEPLOYMENT_UUID)
+        .await
+        .is_err());
+
+    // Error statuses still fail as they do for parsed requests
+    let err = client
+        .get_raw("/suite/deployment-management/v2/missing")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Resource not found"));
+}